@@ -0,0 +1,164 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::process::ExitStatusExt,
+    path::PathBuf,
+    process,
+};
+
+use crate::{
+    output::print_json,
+    runtime::{format_duration_ago, state_dir, unix_now_secs},
+    verbosity,
+};
+
+const AUDIT_FILE: &str = "audit.log";
+
+/// Действия, которые меняют префикс или процессы игры и попадают в журнал.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Init,
+    Run,
+    Exec,
+    Killwin,
+    Stop,
+}
+
+impl AuditAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Init => "init",
+            Self::Run => "run",
+            Self::Exec => "exec",
+            Self::Killwin => "killwin",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+/// Строка журнала: JSON на строку, только дописывается.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AuditEntry {
+    /// Начало действия, unix-время.
+    started_at: u64,
+    duration_secs: u64,
+    action: AuditAction,
+    appid: String,
+    /// Полная командная строка protonhax.
+    argv: Vec<String>,
+    /// Код выхода; при завершении сигналом — 128 + номер, как в оболочке.
+    exit_code: Option<i32>,
+}
+
+/// Путь журнала: `$XDG_STATE_HOME/protonhax/audit.log`.
+fn audit_path() -> PathBuf {
+    state_dir().join(AUDIT_FILE)
+}
+
+/// Дописывает запись о завершённом действии. Журнал не должен мешать запуску:
+/// ошибки записи только показываются в `-v`.
+pub fn record(action: AuditAction, appid: &str, started_at: u64, status: process::ExitStatus) {
+    let entry = AuditEntry {
+        started_at,
+        duration_secs: unix_now_secs().saturating_sub(started_at),
+        action,
+        appid: appid.to_string(),
+        argv: env::args().collect(),
+        exit_code: status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal)),
+    };
+    if let Err(err) = append(&entry) {
+        verbosity::verbose(format_args!(
+            "не удалось записать {}: {err}",
+            audit_path().display()
+        ));
+    }
+}
+
+fn append(entry: &AuditEntry) -> io::Result<()> {
+    let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    line.push('\n');
+    fs::create_dir_all(state_dir())?;
+    // Одна запись за один write: параллельные protonhax не перемешают строки.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_path())?
+        .write_all(line.as_bytes())
+}
+
+/// Последние `limit` записей, подходящих под фильтры, от старых к новым.
+pub fn handle_audit_show(
+    appid: Option<&str>,
+    action: Option<AuditAction>,
+    limit: usize,
+    json_output: bool,
+) -> io::Result<()> {
+    let content = match fs::read_to_string(audit_path()) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let matching: Vec<AuditEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|entry| appid.is_none_or(|appid| entry.appid == appid))
+        .filter(|entry| action.is_none_or(|action| entry.action == action))
+        .collect();
+    let entries = &matching[matching.len().saturating_sub(limit)..];
+
+    if json_output {
+        return print_json(&entries);
+    }
+    if entries.is_empty() {
+        println!("Журнал пуст");
+    }
+    for entry in entries {
+        let code = match entry.exit_code {
+            Some(0) => "ok".green(),
+            Some(code) => format!("exit {code}").red(),
+            None => "-".normal(),
+        };
+        println!(
+            "{:<12} {:<8} {:<10} {:<8} {}",
+            format_duration_ago(entry.started_at).dimmed(),
+            entry.action.as_str().cyan(),
+            entry.appid.green(),
+            code,
+            shell_words::join(&entry.argv)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditAction, AuditEntry};
+
+    #[test]
+    fn entry_round_trips_as_one_json_line() {
+        let entry = AuditEntry {
+            started_at: 1_718_900_000,
+            duration_secs: 42,
+            action: AuditAction::Run,
+            appid: "620".to_string(),
+            argv: vec![
+                "protonhax".into(),
+                "run".into(),
+                "620".into(),
+                "setup.exe".into(),
+            ],
+            exit_code: Some(0),
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(r#""action":"run""#));
+        assert_eq!(serde_json::from_str::<AuditEntry>(&line).unwrap(), entry);
+    }
+}
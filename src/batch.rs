@@ -0,0 +1,190 @@
+use colored::Colorize;
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+    process,
+};
+
+use crate::{cli::fail, context::prepare_context};
+
+#[derive(Debug, PartialEq, Eq)]
+enum BatchMode {
+    /// Через `proton run` (по умолчанию).
+    Run,
+    /// Нативно, как `protonhax exec`.
+    Exec,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct BatchStep {
+    line: usize,
+    mode: BatchMode,
+    argv: Vec<String>,
+}
+
+/// Выполняет команды из файла (или stdin) по одной в контексте игры.
+pub fn handle_batch(
+    phd: &Path,
+    selector: &str,
+    file: Option<&Path>,
+    keep_going: bool,
+) -> io::Result<()> {
+    let script = match file {
+        Some(path) if path != Path::new("-") => fs::read_to_string(path)?,
+        _ => {
+            let mut script = String::new();
+            io::stdin().read_to_string(&mut script)?;
+            script
+        }
+    };
+    let steps = parse_batch(&script).unwrap_or_else(|err| fail(&err));
+
+    let target = prepare_context(phd, selector)?;
+    let proton = target.exe()?;
+    let mut first_failure: Option<i32> = None;
+
+    for step in &steps {
+        eprintln!(
+            "{} [{}] {}",
+            "==>".cyan().bold(),
+            step.line,
+            shell_words::join(&step.argv)
+        );
+
+        let mut cmd = match step.mode {
+            BatchMode::Run => {
+                let mut cmd = process::Command::new(&proton);
+                cmd.arg("run").args(&step.argv);
+                cmd
+            }
+            BatchMode::Exec => {
+                let mut cmd = process::Command::new(&step.argv[0]);
+                cmd.args(&step.argv[1..]);
+                cmd
+            }
+        };
+        let code = match cmd.status() {
+            Ok(status) if status.success() => continue,
+            Ok(status) => status.code().unwrap_or(1),
+            Err(err) => {
+                eprintln!("{} строка {}: {err}", "Ошибка:".bold().red(), step.line);
+                127
+            }
+        };
+
+        eprintln!(
+            "{} строка {} завершилась с кодом {code}",
+            "Ошибка:".bold().red(),
+            step.line
+        );
+        if !keep_going {
+            process::exit(code);
+        }
+        first_failure.get_or_insert(code);
+    }
+
+    if let Some(code) = first_failure {
+        process::exit(code);
+    }
+    Ok(())
+}
+
+/// Строка — одна команда в синтаксисе shell; `#` начинает комментарий,
+/// префиксы `run:`/`exec:` выбирают способ запуска.
+fn parse_batch(script: &str) -> Result<Vec<BatchStep>, String> {
+    let mut steps = Vec::new();
+    for (index, raw) in script.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let (mode, rest) = if let Some(rest) = text.strip_prefix("exec:") {
+            (BatchMode::Exec, rest)
+        } else if let Some(rest) = text.strip_prefix("run:") {
+            (BatchMode::Run, rest)
+        } else {
+            (BatchMode::Run, text)
+        };
+
+        // shell_words не знает про комментарии в конце строки.
+        let argv = shell_words::split(strip_comment(rest))
+            .map_err(|err| format!("Строка {line}: не удалось разобрать команду: {err}"))?;
+        if argv.is_empty() {
+            return Err(format!("Строка {line}: пустая команда"));
+        }
+        steps.push(BatchStep { line, mode, argv });
+    }
+    Ok(steps)
+}
+
+/// Отрезает комментарий, как shell: `#` в начале слова и вне кавычек; `"#1"` и `a#b` —
+/// часть аргументов.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut word_start = true;
+    for (index, c) in text.char_indices() {
+        let literal = escaped;
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '#') if word_start => return &text[..index],
+            _ => {}
+        }
+        word_start = quote.is_none() && !literal && c.is_whitespace();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchMode, BatchStep, parse_batch};
+
+    #[test]
+    fn parses_batch_script() {
+        let script = "# prefix setup\n\
+                      \n\
+                      /tmp/vc_redist.x64.exe /quiet\n\
+                      exec: winetricks -q corefonts  # fonts\n\
+                      run: \"C:\\\\Program Files\\\\Tool\\\\tool.exe\"\n";
+
+        let steps = parse_batch(script).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                BatchStep {
+                    line: 3,
+                    mode: BatchMode::Run,
+                    argv: vec!["/tmp/vc_redist.x64.exe".into(), "/quiet".into()],
+                },
+                BatchStep {
+                    line: 4,
+                    mode: BatchMode::Exec,
+                    argv: vec!["winetricks".into(), "-q".into(), "corefonts".into()],
+                },
+                BatchStep {
+                    line: 5,
+                    mode: BatchMode::Run,
+                    argv: vec![r"C:\Program Files\Tool\tool.exe".into()],
+                },
+            ]
+        );
+        assert!(parse_batch("exec:\n").is_err());
+    }
+
+    #[test]
+    fn keeps_quoted_hashes() {
+        let steps =
+            parse_batch("run: tool.exe \"#1\" 'x #2' a#b \\#c a\\ #d --flag # note\n").unwrap();
+        assert_eq!(
+            steps[0].argv,
+            ["tool.exe", "#1", "x #2", "a#b", "#c", "a #d", "--flag"]
+        );
+    }
+}
@@ -1,6 +1,14 @@
 use clap::CommandFactory;
-use clap::{Parser, Subcommand};
-use clap_complete::shells::Shell as CompleteShell;
+use clap::{ArgAction, Args, Command, Parser, Subcommand};
+use colored::Colorize;
+use regex::Regex;
+use std::{path::PathBuf, process};
+
+use crate::{
+    audit::AuditAction, completions::CompletionShell, environment::EnvShell, i18n,
+    inject::GraphicsApi, paths::PathTarget, prefix::WinebootMode, priority::Priority,
+    proton::ProtonVerb, scope::ResourceLimits,
+};
 
 #[derive(Parser)]
 #[command(
@@ -8,6 +16,23 @@ use clap_complete::shells::Shell as CompleteShell;
     about = "Tool to help running other programs inside Steam's proton."
 )]
 pub struct Cli {
+    /// Directory for runtime contexts (overrides `PROTONHAX_ROOT` and `$XDG_RUNTIME_DIR/protonhax`)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub runtime_dir: Option<PathBuf>,
+    /// Machine-readable JSON output (ls, info, du, doctor, env diff, explain, monitor,
+    /// programs, prompt, protons, shortcuts, tool list/detect, snapshot list, audit show)
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Show context resolution, the final argv and applied environment (`-vv` for more)
+    #[arg(short = 'v', long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+    /// Suppress informational messages; errors and warnings are still printed
+    #[arg(short = 'q', long, global = true)]
+    pub quiet: bool,
+    /// Don't ask for confirmation before destructive operations (snapshot restore,
+    /// prefix repair, protons remove, setup); required when stdin is not a terminal
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -16,53 +41,715 @@ pub struct Cli {
 pub enum Commands {
     /// Should only be called by Steam with "protonhax init %COMMAND%"
     Init {
+        /// Keep the context after the game exits (marked as ended) for post-mortem inspection;
+        /// also enabled by `PROTONHAX_KEEP=1`
+        #[arg(long)]
+        keep: bool,
         /// The command to initialize with (e.g., the original %COMMAND%)
         #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
         cmd: Vec<String>,
     },
     /// Lists all currently running games
     Ls {
+        /// Only list games whose appid, id or name matches: `*` matches anything, case-insensitive;
+        /// without `*` a substring is enough
+        #[arg(value_name = "PATTERN")]
+        pattern: Option<String>,
+        /// Filter with a regular expression instead (searched anywhere; `(?i)` ignores case)
+        #[arg(long, value_name = "RE", value_parser = Regex::new, conflicts_with = "pattern")]
+        regex: Option<Regex>,
         /// Show extra details (name, install path)
         #[arg(short = 'l', long = "long")]
         long: bool,
-        /// Output as JSON
-        #[arg(long = "json")]
-        json: bool,
+        /// Stable tab-separated output: id, appid, name, install path, start time, end time (unix),
+        /// status
+        #[arg(long = "plain", conflicts_with = "json")]
+        plain: bool,
+        /// Also list registered contexts and show each context's status
+        /// (running, ended, stale, broken, registered)
+        #[arg(short = 'a', long, conflicts_with = "installed")]
+        all: bool,
+        /// List Windows games installed by other launchers (Heroic, Lutris) usable as <target>
+        #[arg(long, conflicts_with = "plain")]
+        installed: bool,
+        /// Keep the listing on screen and refresh it when contexts appear or vanish, and every
+        /// SECS seconds (default 2); newly started games are marked with +, exited with -
+        #[arg(
+            short = 'w',
+            long,
+            value_name = "SECS",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "2",
+            value_parser = clap::value_parser!(u64).range(1..),
+            conflicts_with_all = ["json", "plain", "installed"]
+        )]
+        watch: Option<u64>,
     },
     /// Runs <cmd> in the context of <target> with proton
-    Run {
-        /// Target game: appid, `latest`, or part of game name
-        appid: String,
-        /// The command to run with proton
-        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
-        cmd: Vec<String>,
-    },
+    Run(RunArgs),
     /// Runs cmd.exe in the context of <target>
     Cmd {
-        /// Target game: appid, `latest`, or part of game name
+        /// Bypass proton and run with system Wine (or the given wine binary) against the prefix
+        #[arg(
+            long,
+            value_name = "PATH",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "wine"
+        )]
+        wine: Option<String>,
+        /// Target game: appid, `latest`, or part of game name (defaults to the game in the
+        /// current directory)
+        #[arg(default_value = ".")]
         appid: String,
     },
     /// Runs <cmd> in the context of <target>
     Exec {
-        /// Target game: appid, `latest`, or part of game name
+        /// Kill the command's process group after this many seconds (exit code 124)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+        #[command(flatten)]
+        priority: Priority,
+        /// Run inside a bubblewrap sandbox that only sees the prefix, the install dir, the
+        /// Proton build and system directories; the home directory is empty
+        #[arg(long)]
+        sandbox: bool,
+        /// Target game: appid, `latest`, part of game name, or `.` for the game in the
+        /// current directory; a path-like first argument is treated as the command and the
+        /// game is taken from the current directory
         appid: String,
         /// The command to execute natively
-        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        #[arg(num_args = 0.., trailing_var_arg = true, allow_hyphen_values = true)]
         cmd: Vec<String>,
     },
+    /// Prints how `run` would launch <cmd> without executing it: proton, argv, working
+    /// directory, wrappers and the environment difference from the current shell
+    Explain {
+        #[command(subcommand)]
+        command: ExplainCommands,
+    },
+    /// Runs commands from a file or stdin one by one in the context of <target>
+    Batch {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Script with one command per line (`#` comments, `run:`/`exec:` prefixes); stdin if omitted
+        #[arg(short = 'f', long = "file", value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// Continue after a failed command and exit with the first failure's code at the end
+        #[arg(long)]
+        keep_going: bool,
+    },
     /// Generate shell completion scripts
     Completions {
-        /// The shell to generate completions for
-        #[arg(value_enum)]
-        shell: CompleteShell,
+        /// The shell to generate completions for (detected from `$SHELL` with --install)
+        #[arg(value_enum, required_unless_present = "install")]
+        shell: Option<CompletionShell>,
+        /// Write the script into the shell's completions directory instead of stdout
+        #[arg(long)]
+        install: bool,
+    },
+    /// Print candidates for the word after the given ones (used by completion scripts)
+    #[command(hide = true)]
+    Complete {
+        /// Print `value<TAB>description` (game names for targets)
+        #[arg(long)]
+        describe: bool,
+        /// Rebuild the cached target names instead (started in the background by completion)
+        #[arg(long, conflicts_with = "words")]
+        refresh: bool,
+        /// Words of the command line after `protonhax`, up to the cursor
+        #[arg(num_args = 0.., trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+    /// Generate roff man pages (prints the top-level page without --dir)
+    Man {
+        /// Write pages for the command and every subcommand into this directory
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+    /// Runs `wineboot` in the game's prefix through proton, e.g. the reboot a registry import
+    /// or DLL installer asks for
+    Wineboot {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        #[command(flatten)]
+        mode: WinebootMode,
+    },
+    /// Freezes the game's processes (SIGSTOP), e.g. to free CPU while AFK
+    Pause {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Resumes processes frozen with `pause` (SIGCONT)
+    Resume {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Force-kills a Windows process inside the prefix by image name (`taskkill /F /IM`)
+    Killwin {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Image name, e.g. `ModOrganizer.exe` (`.exe` is appended when missing)
+        image: String,
+    },
+    /// Starts winedbg in the context of <target> (interactive without --attach/--launch)
+    Debug {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Attach to a running Windows process (pid from `info process`, decimal or 0x-hex)
+        #[arg(long, value_name = "PID", conflicts_with = "launch")]
+        attach: Option<String>,
+        /// Launch a Windows program under the debugger
+        #[arg(long, value_name = "EXE")]
+        launch: Option<String>,
+        /// Use winedbg's gdb proxy instead of the built-in debugger
+        #[arg(long)]
+        gdb: bool,
+        /// Arguments for the program started with --launch
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            requires = "launch"
+        )]
+        args: Vec<String>,
+    },
+    /// Packs Proton logs, crash backtraces, redacted env and system info into a tar.gz
+    Crashdump {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Archive path (defaults to `protonhax-crashdump-<appid>-<time>.tar.gz` in the current dir)
+        #[arg(short = 'o', long = "output", value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Lists likely save directories of a game (prefix profile and Steam Cloud) and can
+    /// archive them
+    Saves {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Pack the saves into a tar.gz: an archive path, or a directory for
+        /// `protonhax-saves-<appid>-<time>.tar.gz`
+        #[arg(long, value_name = "DEST")]
+        backup: Option<PathBuf>,
+    },
+    /// Inspect or edit the saved environment of a context
+    Env {
+        #[command(subcommand)]
+        command: EnvCommands,
+    },
+    /// Queries the audit log of init/run/exec/killwin/stop invocations
+    /// (`$XDG_STATE_HOME/protonhax/audit.log`)
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Writes a standalone shell script reproducing the context (env, proton, prefix)
+    Export {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Script path (printed to stdout when omitted)
+        output: Option<PathBuf>,
+    },
+    /// Stops the running game gracefully: asks Windows processes to exit so the game can save,
+    /// then escalates to SIGTERM and SIGKILL. Unlike `killwin`, nothing is killed right away
+    Stop {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Seconds to wait for a clean exit before SIGTERM
+        #[arg(long, value_name = "SECS", default_value_t = 15)]
+        grace: u64,
+        /// Seconds to wait after SIGTERM before SIGKILL
+        #[arg(long, value_name = "SECS", default_value_t = 5)]
+        kill_after: u64,
+    },
+    /// Gracefully stops the running game (like `stop`), starts it again
+    /// through Steam and reports the new context — handy after changing DLL overrides
+    Restart {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Don't ask Steam to start the game; wait until it is started manually
+        #[arg(long)]
+        no_launch: bool,
+        /// How long to wait for the game to come back, in seconds
+        #[arg(long, value_name = "SECS", default_value_t = 120)]
+        timeout: u64,
+    },
+    /// Re-reads the proton path and environment from the running game into its context
+    /// (fixes contexts from older versions or after a Proton update)
+    Reinit {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Saves the live context so `run`/`cmd`/`exec` keep working after the game exits
+    Register {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Manage standalone prefixes that are not tied to a Steam game
+    Prefix {
+        #[command(subcommand)]
+        command: PrefixCommands,
+    },
+    /// Lists installed Proton builds: Valve ones from Steam libraries and custom ones from
+    /// `compatibilitytools.d`
+    Protons {
+        #[command(subcommand)]
+        command: Option<ProtonsCommands>,
     },
     /// Validate current runtime contexts and environment
     Doctor,
+    /// Put `protonhax init %command%` into Steam launch options of Proton games, remove it or
+    /// migrate older invocations; without arguments lists the games and their setup. Steam
+    /// must be closed, `localconfig.vdf` is backed up
+    Setup {
+        /// Games to set up, by appid
+        appids: Vec<String>,
+        /// Every installed Proton game
+        #[arg(long, conflicts_with_all = ["appids", "interactive"])]
+        all: bool,
+        /// Pick games with checkboxes instead of listing appids
+        #[arg(short, long, conflicts_with = "appids")]
+        interactive: bool,
+        /// Remove the protonhax invocation, keeping the rest of the launch options
+        #[arg(long, conflicts_with_all = ["interactive", "migrate"])]
+        remove: bool,
+        /// Rewrite older invocations (`protonhax.sh`, own wrapper scripts) to
+        /// `protonhax init %command%`; without appids goes through every game
+        #[arg(long, conflicts_with = "interactive")]
+        migrate: bool,
+        /// Own wrapper script to migrate away from (path or file name); repeatable
+        #[arg(long, value_name = "SCRIPT", requires = "migrate")]
+        wrapper: Vec<String>,
+    },
+    /// Manage registered external tools (Cheat Engine, mod managers, ...)
+    Tool {
+        #[command(subcommand)]
+        command: ToolCommands,
+    },
+    /// Add protonhax launches to Steam as non-Steam shortcuts (Big Picture, Game Mode)
+    Shortcut {
+        #[command(subcommand)]
+        command: ShortcutCommands,
+    },
+    /// Route nxm:// links (Nexus Mods) to a mod manager inside a context
+    Nxm {
+        #[command(subcommand)]
+        command: NxmCommands,
+    },
+    /// Symlinks a host directory into the prefix at a Windows path (`C:\Mods`) or as a
+    /// new drive (`M:`)
+    Mount {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Host directory to expose inside the prefix
+        host_dir: PathBuf,
+        /// Windows path or drive letter to mount it at
+        target: String,
+    },
+    /// Removes a symlink created by `mount`
+    Unmount {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Windows path or drive letter passed to `mount`
+        target: String,
+    },
+    /// Install injectors (ReShade) into the game directory
+    Inject {
+        #[command(subcommand)]
+        command: InjectCommands,
+    },
+    /// protontricks-style helper: winetricks or a shell command in the game's prefix
+    Pt {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Open the winetricks GUI
+        #[arg(long = "gui")]
+        gui: bool,
+        /// Run a shell command with the prefix's Wine environment
+        #[arg(short = 'c', long = "command", conflicts_with = "gui")]
+        command: Option<String>,
+        /// Winetricks verbs and options
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Installs common redistributables (corefonts, vcrun2022, dotnet48, ...) via winetricks;
+    /// without names lists them and marks the installed ones
+    InstallDeps {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Dependency names
+        deps: Vec<String>,
+    },
+    /// Shows everything known about one context: name, install dir, prefix, proton build,
+    /// uptime, PID liveness, environment size and log locations
+    Info {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Streams context events (created, removed) as they happen; with `--json` one JSON
+    /// object per line
+    Monitor,
+    /// Prints a short summary for shell prompts: the context the shell runs in
+    /// (`PROTONHAX_CONTEXT`) or the number of running games; prints nothing otherwise
+    Prompt,
+    /// Shows disk usage of the prefix, shader cache and install dir
+    Du {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+    },
+    /// Opens a game or prefix directory in the file manager
+    Open {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Which directory to open
+        #[arg(value_enum, default_value_t = PathTarget::Install)]
+        target: PathTarget,
+    },
+    /// Prints a game or prefix directory for `cd "$(protonhax cd --print ...)"` (see `phcd`)
+    Cd {
+        /// Print the directory (the shell has to do the actual `cd`)
+        #[arg(long = "print")]
+        print: bool,
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Which directory to print
+        #[arg(value_enum, default_value_t = PathTarget::Install)]
+        target: PathTarget,
+    },
+    /// Lists Start Menu/Desktop shortcuts in the prefix (`shortcuts run` launches one)
+    #[command(args_conflicts_with_subcommands = true)]
+    Shortcuts {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: Option<String>,
+        #[command(subcommand)]
+        command: Option<ShortcutsCommands>,
+    },
+    /// Lists Windows programs installed in the prefix (registry Uninstall keys)
+    Programs {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+    },
+    /// Create, list, restore and diff prefix snapshots
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+}
+
+/// Аргументы `run`; их же принимает `explain run`.
+#[derive(Args)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RunArgs {
+    /// Run the file as is: no `msiexec` for `.msi`, `cmd.exe` for `.bat`/`.cmd` or `regedit` for `.reg`
+    #[arg(long)]
+    pub no_auto: bool,
+    /// Kill the command's process group after this many seconds (exit code 124)
+    #[arg(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
+    /// Wrap proton in `strace -f`, optionally with an `-e` filter (`--strace=file`);
+    /// the trace goes to `$XDG_STATE_HOME/protonhax/logs/<appid>/`
+    #[arg(
+        long,
+        value_name = "FILTER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub strace: Option<String>,
+    /// Bypass proton and run with system Wine (or the given wine binary) against the prefix
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "wine"
+    )]
+    pub wine: Option<String>,
+    /// Convert host path arguments after the program into Windows paths (`Z:\...`, `C:\...`)
+    #[arg(long)]
+    pub win_paths: bool,
+    /// Append a library to `LD_PRELOAD` of the launched proton process (repeatable),
+    /// e.g. obs-vkcapture or a custom shim
+    #[arg(long, value_name = "LIB")]
+    pub preload: Vec<String>,
+    /// Enable the vkBasalt post-processing layer for this launch, optionally with its
+    /// config file (`--vkbasalt=path/to/vkBasalt.conf`)
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub vkbasalt: Option<String>,
+    /// Make the launched program capturable by OBS: wrap it in `obs-gamecapture` and set
+    /// `OBS_VKCAPTURE=1` for obs-vkcapture
+    #[arg(long)]
+    pub obs_capture: bool,
+    /// Run against a throwaway copy of the prefix (fuse-overlayfs, or a reflink copy without
+    /// it); every change is discarded on exit — for trying out installers
+    #[arg(long)]
+    pub ephemeral: bool,
+    /// Proton verb: `runinprefix` skips the prefix setup for quick tools,
+    /// `waitforexitandrun` waits for the prefix's other programs (some injectors need it)
+    #[arg(long, value_enum, default_value_t = ProtonVerb::Run, conflicts_with = "wine")]
+    pub verb: ProtonVerb,
+    #[command(flatten)]
+    pub limits: ResourceLimits,
+    #[command(flatten)]
+    pub priority: Priority,
+    /// Target game: appid, `latest`, part of game name, or `.` for the game in the
+    /// current directory; a path-like first argument is treated as the command and the
+    /// game is taken from the current directory
+    pub appid: String,
+    /// The command to run with proton; a `C:\...` program path is mapped to the host
+    /// through the prefix's `dosdevices`
+    #[arg(num_args = 0.., trailing_var_arg = true, allow_hyphen_values = true)]
+    pub cmd: Vec<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ExplainCommands {
+    /// Resolve `run` with the same arguments
+    Run(RunArgs),
+}
+
+#[derive(Subcommand)]
+pub enum EnvCommands {
+    /// Shows variables added (+), removed (-) and changed (~) in the context relative to
+    /// another context or the current shell
+    Diff {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Context to compare against (defaults to the current shell environment)
+        #[arg(conflicts_with = "current")]
+        other: Option<String>,
+        /// Compare against the current shell environment
+        #[arg(long)]
+        current: bool,
+    },
+    /// Opens the saved environment in `$EDITOR` and writes it back after a syntax check
+    Edit {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Prints the game's environment as shell assignments for `eval` (or direnv), so the
+    /// whole terminal session adopts it
+    Source {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Output syntax; detected from `$SHELL` by default (bash inside direnv's `.envrc`)
+        #[arg(long, value_enum)]
+        shell: Option<EnvShell>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PrefixCommands {
+    /// Creates a fresh prefix with `proton run wineboot` and registers it as context <name>
+    New {
+        /// Context name used as <target> later (letters, digits, `-`, `_`)
+        name: String,
+        /// Proton build: directory, path to the `proton` script, or name (e.g. "GE-Proton9-20");
+        /// defaults to Proton Experimental or the newest Valve Proton
+        #[arg(long, value_name = "PROTON")]
+        proton: Option<String>,
+        /// Steam appid passed to Proton (enables per-game fixes), `0` by default
+        #[arg(long, value_name = "N")]
+        appid: Option<String>,
+    },
+    /// Checks the prefix: registry files, Windows version and broken symlinks
+    Verify {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+    },
+    /// Rebuilds the prefix plumbing with `wineboot -u` (keeps installed programs and saves),
+    /// then verifies it
+    Repair {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProtonsCommands {
+    /// Downloads a GE-Proton release, verifies its SHA-512 and unpacks it into `compatibilitytools.d`
+    Install {
+        /// Release tag (e.g. "GE-Proton9-20") or `latest`
+        version: String,
+    },
+    /// Removes a custom build from `compatibilitytools.d`
+    Remove {
+        /// Build name as shown by `protonhax protons`
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ToolCommands {
+    /// Register (or replace) a tool in the config
+    Add {
+        /// Short name used with `tool run`
+        name: String,
+        /// Path to the tool; supports `{appid}`, `{name}`, `{install_dir}`, `{pfx}`, `{drive_c}`
+        path: String,
+        /// Default arguments (templated like the path)
+        #[arg(long = "args", num_args = 1.., allow_hyphen_values = true)]
+        args: Vec<String>,
+        /// Run natively with the game environment instead of through proton
+        #[arg(long = "native")]
+        native: bool,
+        /// Always launch inside a `systemd-run --user --scope` unit
+        #[arg(long = "scope")]
+        scope: bool,
+    },
+    /// Scans the install dir and the prefix for known companion tools (script extenders,
+    /// mod managers, Cheat Engine and its tables, launchers) and offers to register them
+    Detect {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+    },
+    /// Lists registered tools
+    List,
+    /// Runs a registered tool in the context of <target>
+    Run {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Tool name from `tool list`; defaults to `tool` in the game's `[apps.<appid>]`
+        name: Option<String>,
+        /// Launch inside a `systemd-run --user --scope` unit named `protonhax-<appid>-<tool>-<pid>`
+        #[arg(long = "scope")]
+        scope: bool,
+        /// Extra arguments appended after the configured ones
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NxmCommands {
+    /// Register protonhax as the x-scheme-handler/nxm desktop handler
+    Install {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Registered tool that receives the link (e.g. Mod Organizer 2 or Vortex)
+        #[arg(long = "tool", default_value = "mo2")]
+        tool: String,
+    },
+    /// Dispatch an nxm:// link (called by the desktop entry)
+    Handle {
+        /// The nxm:// URL
+        url: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum InjectCommands {
+    /// Copy ReShade next to the game executable and set the DLL override
+    Reshade {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Graphics API used by the game
+        #[arg(long = "api", value_enum, default_value_t = GraphicsApi::Dx11)]
+        api: GraphicsApi,
+        /// Game executable relative to the install dir (when auto-detection is ambiguous)
+        #[arg(long = "exe")]
+        exe: Option<String>,
+        /// Directory containing ReShade64.dll / ReShade32.dll
+        #[arg(long = "from", conflicts_with = "download")]
+        from: Option<PathBuf>,
+        /// Download this ReShade version (e.g. 6.3.3) into the local cache
+        #[arg(long = "download")]
+        download: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// Shows the latest entries, oldest first
+    Show {
+        /// Only entries for this appid
+        appid: Option<String>,
+        /// Only entries of this action
+        #[arg(long, value_enum)]
+        action: Option<AuditAction>,
+        /// How many entries to show
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 50)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Snapshot the prefix (reflink copy, or btrfs snapshot for subvolumes)
+    Create {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Snapshot name (defaults to the current unix time)
+        name: Option<String>,
+    },
+    /// Lists snapshots of the prefix
+    List {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+    },
+    /// Replaces the prefix with a snapshot
+    Restore {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Snapshot name
+        name: String,
+        /// Restore even if the game is running
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Shows files changed between two snapshots (or a snapshot and the live prefix)
+    Diff {
+        /// Target game: appid, `latest`, or part of game name
+        appid: String,
+        /// Older snapshot
+        from: String,
+        /// Newer snapshot (defaults to the current prefix)
+        to: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShortcutsCommands {
+    /// Launches a shortcut through proton
+    Run {
+        /// Target game: appid (works without a running game), `latest`, or part of game name
+        appid: String,
+        /// Shortcut name (or part of it) from `shortcuts <target>`
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ShortcutCommands {
+    /// Adds a shortcut running `protonhax run <appid> <cmd>` to `shortcuts.vdf`; Steam must be
+    /// closed, the previous file is backed up
+    Add {
+        /// Target game as passed to `run`: appid, `latest`, or part of game name
+        appid: String,
+        /// Name shown in the Steam library; an existing shortcut with it is replaced
+        #[arg(long)]
+        name: String,
+        /// The command for `protonhax run`, as it would be typed after `protonhax run <appid>`
+        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        cmd: Vec<String>,
+    },
+}
+
+/// Описание CLI с описаниями команд на языке пользователя: из него разбираются аргументы
+/// и строятся справка, автодополнение и man-страницы.
+pub fn command() -> Command {
+    i18n::localize(Cli::command(), i18n::Lang::from_env())
 }
 
 /// Вывод справки для конкретной подкоманды.
 pub fn sub_usage(sub: &str) {
-    let mut cmd = Cli::command();
+    let mut cmd = command();
     if let Some(sc) = cmd.find_subcommand_mut(sub) {
         let _ = sc.print_help();
         println!();
@@ -72,3 +759,16 @@ pub fn sub_usage(sub: &str) {
         println!();
     }
 }
+
+/// Вывод ошибки вместе со справкой подкоманды и завершение процесса.
+pub fn print_subcommand_usage_error(subcommand: &str, message: &str) -> ! {
+    eprintln!("{} {message}", "Ошибка:".bold().red());
+    sub_usage(subcommand);
+    process::exit(1);
+}
+
+/// Вывод ошибки без справки и завершение процесса с кодом 2.
+pub fn fail(message: &str) -> ! {
+    eprintln!("{} {message}", "Ошибка:".bold().red());
+    process::exit(2);
+}
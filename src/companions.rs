@@ -0,0 +1,249 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{Config, ToolConfig},
+    output::print_json,
+    steam::find_app_meta,
+    ui,
+    wine::locate_prefix,
+};
+
+/// Глубина поиска от каталога игры и от `drive_c`.
+const SEARCH_DEPTH: usize = 4;
+/// Каталоги `drive_c`, где инструментов не бывает, а файлов тысячи.
+const SKIPPED_DIRS: &[&str] = &["windows", "programdata"];
+
+/// Известные спутники игр: имя файла (без учёта регистра) и имя инструмента.
+const KNOWN_TOOLS: &[(&str, &str)] = &[
+    ("skse64_loader.exe", "skse"),
+    ("skse_loader.exe", "skse"),
+    ("f4se_loader.exe", "f4se"),
+    ("sfse_loader.exe", "sfse"),
+    ("obse_loader.exe", "obse"),
+    ("fose_loader.exe", "fose"),
+    ("nvse_loader.exe", "nvse"),
+    ("modorganizer.exe", "mo2"),
+    ("vortex.exe", "vortex"),
+    ("cheatengine-x86_64.exe", "cheatengine"),
+    ("cheat engine.exe", "cheatengine"),
+    ("loot.exe", "loot"),
+    ("sseedit.exe", "xedit"),
+    ("fo4edit.exe", "xedit"),
+    ("tes5edit.exe", "xedit"),
+    ("xedit.exe", "xedit"),
+    ("wrye bash.exe", "wryebash"),
+    ("bodyslide x64.exe", "bodyslide"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CompanionKind {
+    /// Загрузчик, модменеджер или редактор из [`KNOWN_TOOLS`].
+    Tool,
+    /// `*launcher*.exe` рядом с игрой.
+    Launcher,
+    /// Таблица Cheat Engine (`.CT`): открывается в Cheat Engine, отдельным инструментом
+    /// не регистрируется.
+    CheatTable,
+}
+
+impl CompanionKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Tool => "tool",
+            Self::Launcher => "launcher",
+            Self::CheatTable => "table",
+        }
+    }
+}
+
+struct Companion {
+    name: String,
+    kind: CompanionKind,
+    path: PathBuf,
+    /// Путь для `[tools]`: от `{install_dir}` или `{drive_c}`, чтобы пережить переезд игры.
+    template: String,
+}
+
+/// `tool detect`: ищет спутники игры в каталоге установки и в префиксе и предлагает
+/// зарегистрировать найденные инструменты.
+pub fn handle_tool_detect(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let mut roots = Vec::new();
+    if let Some(install_path) = find_app_meta(&prefix.appid).install_path {
+        roots.push((PathBuf::from(install_path), "{install_dir}"));
+    }
+    roots.push((prefix.pfx.join("drive_c"), "{drive_c}"));
+
+    let mut companions = Vec::new();
+    for (root, placeholder) in &roots {
+        let mut files = Vec::new();
+        collect_files(root, SEARCH_DEPTH, &mut files);
+        files.sort();
+        for path in files {
+            let Some((name, kind)) = path
+                .file_name()
+                .and_then(|file_name| classify(&file_name.to_string_lossy()))
+            else {
+                continue;
+            };
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            companions.push(Companion {
+                name,
+                kind,
+                template: format!("{placeholder}/{}", relative.display()),
+                path,
+            });
+        }
+    }
+    // Из нескольких копий одного инструмента (xEdit для разных игр) берём первую.
+    let mut seen = HashSet::new();
+    companions.retain(|companion| {
+        companion.kind == CompanionKind::CheatTable || seen.insert(companion.name.clone())
+    });
+
+    let config = Config::load()?;
+    if json_output {
+        let data: Vec<_> = companions
+            .iter()
+            .map(|companion| {
+                json!({
+                    "name": companion.name,
+                    "kind": companion.kind.as_str(),
+                    "path": companion.path,
+                    "template": companion.template,
+                    "registered": config.tools.contains_key(&companion.name),
+                })
+            })
+            .collect();
+        return print_json(&json!({ "appid": prefix.appid, "companions": data }));
+    }
+    if companions.is_empty() {
+        println!("Известных инструментов для {} не найдено", prefix.appid);
+        return Ok(());
+    }
+    for companion in &companions {
+        let mut parts = vec![
+            format!("{:<12}", companion.name).green().to_string(),
+            format!("{:<9}", companion.kind.as_str()),
+            companion.path.display().to_string().dimmed().to_string(),
+        ];
+        if config.tools.contains_key(&companion.name) {
+            parts.push("registered".cyan().to_string());
+        }
+        println!("{}", parts.join(" "));
+    }
+
+    let added = offer_registration(&companions, &config);
+    if !added.is_empty() {
+        Config::save_tools(added.iter().map(|(name, tool)| (name.as_str(), tool)))?;
+    }
+    Ok(())
+}
+
+/// Спрашивает про каждый незарегистрированный инструмент; без терминала и `--yes`
+/// только подсказывает команду.
+fn offer_registration(companions: &[Companion], config: &Config) -> Vec<(String, ToolConfig)> {
+    let mut added = Vec::new();
+    let candidates = companions.iter().filter(|companion| {
+        companion.kind != CompanionKind::CheatTable && !config.tools.contains_key(&companion.name)
+    });
+    for companion in candidates {
+        if !ui::can_confirm() {
+            println!(
+                "  {} protonhax tool add {} {}",
+                "+".green(),
+                companion.name,
+                shell_words::quote(&companion.template)
+            );
+            continue;
+        }
+        if ui::confirm(&format!(
+            "Зарегистрировать {} ({})?",
+            companion.name, companion.template
+        )) {
+            println!("Инструмент {} добавлен", companion.name.green());
+            added.push((
+                companion.name.clone(),
+                ToolConfig {
+                    path: companion.template.clone(),
+                    args: Vec::new(),
+                    native: false,
+                    scope: false,
+                },
+            ));
+        }
+    }
+    added
+}
+
+/// Имя и вид спутника по имени файла.
+fn classify(file_name: &str) -> Option<(String, CompanionKind)> {
+    let lower = file_name.to_lowercase();
+    if let Some((_, name)) = KNOWN_TOOLS.iter().find(|(file, _)| *file == lower) {
+        return Some(((*name).to_string(), CompanionKind::Tool));
+    }
+    if let Some(stem) = lower.strip_suffix(".ct") {
+        return Some((stem.to_string(), CompanionKind::CheatTable));
+    }
+    let stem = lower.strip_suffix(".exe")?;
+    stem.contains("launcher")
+        .then(|| (stem.replace(' ', "-"), CompanionKind::Launcher))
+}
+
+/// Файлы до `depth` уровней вглубь; нечитаемые каталоги пропускаются.
+fn collect_files(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let skipped = entry.file_name().to_str().is_some_and(|name| {
+                SKIPPED_DIRS
+                    .iter()
+                    .any(|skipped| name.eq_ignore_ascii_case(skipped))
+            });
+            if depth > 0 && !skipped {
+                collect_files(&path, depth - 1, out);
+            }
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompanionKind, classify};
+
+    #[test]
+    fn classifies_companion_files() {
+        assert_eq!(
+            classify("SKSE64_Loader.exe"),
+            Some(("skse".to_string(), CompanionKind::Tool))
+        );
+        assert_eq!(
+            classify("ModOrganizer.exe"),
+            Some(("mo2".to_string(), CompanionKind::Tool))
+        );
+        assert_eq!(
+            classify("REDprelauncher.exe"),
+            Some(("redprelauncher".to_string(), CompanionKind::Launcher))
+        );
+        assert_eq!(
+            classify("Elden Ring.CT"),
+            Some(("elden ring".to_string(), CompanionKind::CheatTable))
+        );
+        assert_eq!(classify("game.exe"), None);
+        assert_eq!(classify("launcher.dll"), None);
+    }
+}
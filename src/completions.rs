@@ -0,0 +1,511 @@
+use clap::{Command, CommandFactory, ValueEnum, ValueHint};
+use clap_complete::{Generator, generate, shells::Shell as CompleteShell};
+use clap_complete_nushell::Nushell;
+use colored::Colorize;
+use serde_json::{Map, Value, json};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::{self, Cli, fail},
+    config::Config,
+    listing_cache,
+    runtime::{config_dir_base, data_home},
+    verbosity,
+};
+
+/// Функция bash/zsh-скрипта, в начало которой вставляется вызов динамических дополнений.
+const COMPLETION_FUNCTION: &str = "_protonhax() {\n";
+
+/// Значения, которые знает только protonhax: их скрипт спрашивает у `protonhax complete`.
+#[derive(Debug, PartialEq, Eq)]
+enum DynamicValues {
+    /// Контексты, в том числе зарегистрированные под своими именами, и `latest`.
+    Targets,
+    /// Инструменты из `[tools]` конфига.
+    Tools,
+}
+
+/// Оболочки `clap_complete` плюс Nushell и спецификация carapace.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+    Elvish,
+    Nushell,
+    /// Carapace spec (JSON, which carapace reads as YAML)
+    Carapace,
+}
+
+impl CompletionShell {
+    fn clap_shell(self) -> Option<CompleteShell> {
+        match self {
+            Self::Bash => Some(CompleteShell::Bash),
+            Self::Zsh => Some(CompleteShell::Zsh),
+            Self::Fish => Some(CompleteShell::Fish),
+            Self::PowerShell => Some(CompleteShell::PowerShell),
+            Self::Elvish => Some(CompleteShell::Elvish),
+            Self::Nushell | Self::Carapace => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::PowerShell => "powershell",
+            Self::Elvish => "elvish",
+            Self::Nushell => "nushell",
+            Self::Carapace => "carapace",
+        }
+    }
+}
+
+pub fn handle_completions(shell: Option<CompletionShell>, install: bool) -> io::Result<()> {
+    if install {
+        let shell = shell
+            .or_else(detect_shell)
+            .unwrap_or_else(|| fail("Не удалось определить оболочку по $SHELL; укажите её явно"));
+        return install_completions(shell);
+    }
+
+    // clap гарантирует наличие shell без --install.
+    let Some(shell) = shell else {
+        fail("Не указана оболочка");
+    };
+    let mut stdout = io::stdout();
+    stdout.write_all(&completion_script(shell)?)?;
+    if let Some(helper) = cd_helper(shell) {
+        writeln!(stdout, "\n{helper}")?;
+    }
+    Ok(())
+}
+
+/// Кандидаты для слова после `words`: по строке на значение, пусто — пусть оболочка
+/// дополняет статически.
+pub fn handle_complete(phd: &Path, words: &[String], describe: bool) -> io::Result<()> {
+    let mut cmd = Cli::command();
+    cmd.build();
+    let values: Vec<(String, Option<String>)> = match dynamic_values(&cmd, words) {
+        // Имена игр — из кэша последнего `ls`: манифесты на каждый Tab не разбираем.
+        Some(DynamicValues::Targets) => listing_cache::cached_targets(phd)
+            .into_iter()
+            .chain([("latest".to_string(), None)])
+            .collect(),
+        Some(DynamicValues::Tools) => Config::load()?
+            .tools
+            .into_keys()
+            .map(|name| (name, None))
+            .collect(),
+        None => return Ok(()),
+    };
+    let mut stdout = io::stdout().lock();
+    for (value, description) in values {
+        // У зарегистрированных контекстов имя совпадает с id.
+        match description.filter(|description| describe && *description != value) {
+            Some(description) => writeln!(stdout, "{value}\t{description}")?,
+            None => writeln!(stdout, "{value}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Какое значение ждёт следующее слово: подкоманды проходим по `words`, флаги со
+/// значением пропускаем вместе с ним, позиционные аргументы считаем.
+fn dynamic_values(root: &Command, words: &[String]) -> Option<DynamicValues> {
+    let mut cmd = root;
+    let mut path = Vec::new();
+    let mut positionals = 0;
+    let mut pending_flag: Option<&clap::Arg> = None;
+    for word in words {
+        if pending_flag.take().is_some() {
+            continue;
+        }
+        if let Some(long) = word.strip_prefix("--") {
+            if !long.contains('=') {
+                pending_flag = find_flag(root, cmd, |arg| arg.get_long() == Some(long));
+            }
+        } else if let Some(shorts) = word.strip_prefix('-').filter(|shorts| !shorts.is_empty()) {
+            // `-n5` несёт значение в себе, `-n` — в следующем слове.
+            pending_flag = shorts
+                .chars()
+                .last()
+                .and_then(|short| find_flag(root, cmd, |arg| arg.get_short() == Some(short)));
+        } else if let Some(subcommand) = (positionals == 0)
+            .then(|| cmd.find_subcommand(word))
+            .flatten()
+        {
+            path.push(subcommand.get_name());
+            cmd = subcommand;
+        } else {
+            positionals += 1;
+        }
+    }
+
+    let arg = match pending_flag {
+        Some(flag) => flag,
+        None => cmd.get_positionals().nth(positionals)?,
+    };
+    match arg.get_id().as_str() {
+        "appid" => Some(DynamicValues::Targets),
+        "tool" => Some(DynamicValues::Tools),
+        "name" if path.first() == Some(&"tool") && path.get(1) != Some(&"add") => {
+            Some(DynamicValues::Tools)
+        }
+        _ => None,
+    }
+}
+
+/// Флаг со значением у подкоманды или глобальный.
+fn find_flag<'a>(
+    root: &'a Command,
+    cmd: &'a Command,
+    matches: impl Fn(&clap::Arg) -> bool,
+) -> Option<&'a clap::Arg> {
+    cmd.get_arguments()
+        .chain(root.get_arguments())
+        .find(|arg| !arg.is_positional() && matches(arg))
+        .filter(|arg| arg.get_action().takes_values())
+}
+
+fn completion_script(shell: CompletionShell) -> io::Result<Vec<u8>> {
+    let mut cmd = cli::command();
+    match shell.clap_shell() {
+        Some(clap_shell) => {
+            let script = generate_with(clap_shell, &mut cmd);
+            Ok(with_dynamic_values(shell, script))
+        }
+        None if shell == CompletionShell::Nushell => Ok(generate_with(Nushell, &mut cmd)),
+        None => {
+            let mut spec =
+                serde_json::to_vec_pretty(&carapace_spec(&cmd)).map_err(io::Error::other)?;
+            spec.push(b'\n');
+            Ok(spec)
+        }
+    }
+}
+
+/// Подключает к скрипту `protonhax complete`: цели и инструменты берутся из текущих
+/// контекстов и конфига при каждом нажатии Tab.
+fn with_dynamic_values(shell: CompletionShell, script: Vec<u8>) -> Vec<u8> {
+    let helper = match shell {
+        CompletionShell::Bash => {
+            "_protonhax_dynamic() {\n    \
+             local cur=\"${COMP_WORDS[COMP_CWORD]}\" values\n    \
+             [[ $cur == -* ]] && return 1\n    \
+             values=\"$(protonhax complete -- \"${COMP_WORDS[@]:1:COMP_CWORD-1}\" 2>/dev/null)\"\n    \
+             [[ -n $values ]] || return 1\n    \
+             local IFS=$'\\n'\n    \
+             COMPREPLY=($(compgen -W \"$values\" -- \"$cur\"))\n\
+             }\n\n"
+        }
+        CompletionShell::Zsh => {
+            "_protonhax_dynamic() {\n    \
+             [[ $PREFIX == -* ]] && return 1\n    \
+             local -a values\n    \
+             values=(${(f)\"$(protonhax complete --describe -- \"${(@)words[2,CURRENT-1]}\" 2>/dev/null)\"})\n    \
+             (( ${#values} )) || return 1\n    \
+             values=(${values//$'\\t'/:})\n    \
+             _describe -t values value values\n\
+             }\n\n"
+        }
+        CompletionShell::Fish => {
+            let mut script = script;
+            script.extend_from_slice(
+                b"\nfunction __protonhax_dynamic\n    \
+                  protonhax complete --describe -- (commandline -opc)[2..] 2>/dev/null\n\
+                  end\n\
+                  complete -c protonhax -f -n 'test -n \"$(__protonhax_dynamic)\"' -a '(__protonhax_dynamic)'\n",
+            );
+            return script;
+        }
+        _ => return script,
+    };
+    let script = String::from_utf8_lossy(&script);
+    let Some(start) = script.find(COMPLETION_FUNCTION) else {
+        return script.into_owned().into_bytes();
+    };
+    let body = start + COMPLETION_FUNCTION.len();
+    format!(
+        "{}{helper}{COMPLETION_FUNCTION}    _protonhax_dynamic && return 0\n{}",
+        &script[..start],
+        &script[body..]
+    )
+    .into_bytes()
+}
+
+fn generate_with(generator: impl Generator, cmd: &mut Command) -> Vec<u8> {
+    let mut script = Vec::new();
+    generate(generator, cmd, "protonhax", &mut script);
+    script
+}
+
+/// Спецификация carapace: флаги в виде `-s, --long=`, значения — в `completion`.
+fn carapace_spec(cmd: &Command) -> Value {
+    let mut spec = Map::new();
+    spec.insert("name".into(), cmd.get_name().into());
+    if let Some(about) = cmd.get_about() {
+        spec.insert("description".into(), about.to_string().into());
+    }
+    let aliases: Vec<&str> = cmd.get_visible_aliases().collect();
+    if !aliases.is_empty() {
+        spec.insert("aliases".into(), json!(aliases));
+    }
+
+    let mut flags = Map::new();
+    let mut persistent_flags = Map::new();
+    let mut flag_values = Map::new();
+    let mut positional = Vec::new();
+    let mut positional_any = None;
+    for arg in cmd.get_arguments() {
+        if arg.is_hide_set() {
+            continue;
+        }
+        let takes_value = arg.get_action().takes_values();
+        let values = takes_value.then(|| value_completions(arg)).flatten();
+
+        if arg.is_positional() {
+            let values = values.unwrap_or_default();
+            if arg
+                .get_num_args()
+                .is_some_and(|range| range.max_values() > 1)
+            {
+                positional_any = Some(values);
+            } else {
+                positional.push(values);
+            }
+            continue;
+        }
+
+        let mut names: Vec<String> = arg
+            .get_short()
+            .map(|short| format!("-{short}"))
+            .into_iter()
+            .collect();
+        if let Some(long) = arg.get_long() {
+            names.push(format!("--{long}"));
+        }
+        let key = format!("{}{}", names.join(", "), if takes_value { "=" } else { "" });
+        let help = arg.get_help().map(ToString::to_string).unwrap_or_default();
+        if arg.is_global_set() {
+            persistent_flags.insert(key, help.into());
+        } else {
+            flags.insert(key, help.into());
+        }
+
+        if let (Some(values), Some(long)) = (values, arg.get_long()) {
+            flag_values.insert(long.to_string(), json!(values));
+        }
+    }
+
+    if !flags.is_empty() {
+        spec.insert("flags".into(), flags.into());
+    }
+    if !persistent_flags.is_empty() {
+        spec.insert("persistentflags".into(), persistent_flags.into());
+    }
+
+    let mut completion = Map::new();
+    if !flag_values.is_empty() {
+        completion.insert("flag".into(), flag_values.into());
+    }
+    if positional.iter().any(|values| !values.is_empty()) {
+        completion.insert("positional".into(), json!(positional));
+    }
+    if let Some(values) = positional_any.filter(|values| !values.is_empty()) {
+        completion.insert("positionalany".into(), json!(values));
+    }
+    if !completion.is_empty() {
+        spec.insert("completion".into(), completion.into());
+    }
+
+    let commands: Vec<Value> = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(carapace_spec)
+        .collect();
+    if !commands.is_empty() {
+        spec.insert("commands".into(), commands.into());
+    }
+    Value::Object(spec)
+}
+
+fn value_completions(arg: &clap::Arg) -> Option<Vec<String>> {
+    let possible: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !possible.is_empty() {
+        return Some(possible);
+    }
+
+    match arg.get_value_hint() {
+        ValueHint::DirPath => Some(vec!["$directories".to_string()]),
+        ValueHint::FilePath | ValueHint::AnyPath | ValueHint::ExecutablePath => {
+            Some(vec!["$files".to_string()])
+        }
+        _ => None,
+    }
+}
+
+fn detect_shell() -> Option<CompletionShell> {
+    let shell = env::var_os("SHELL")?;
+    let name = PathBuf::from(shell).file_name()?.to_str()?.to_string();
+    match name.as_str() {
+        "bash" => Some(CompletionShell::Bash),
+        "zsh" => Some(CompletionShell::Zsh),
+        "fish" => Some(CompletionShell::Fish),
+        "nu" => Some(CompletionShell::Nushell),
+        _ => None,
+    }
+}
+
+/// Пишет скрипт туда, откуда оболочка подгружает дополнения сама: bash-completion
+/// и fish загружают их лениво, поэтому `phcd` для bash/zsh подключается отдельно.
+fn install_completions(shell: CompletionShell) -> io::Result<()> {
+    let (path, hint) = match shell {
+        CompletionShell::Bash => (
+            data_home().join("bash-completion/completions/protonhax"),
+            "Для phcd добавьте в ~/.bashrc: source <(protonhax completions bash)".to_string(),
+        ),
+        CompletionShell::Zsh => {
+            let dir = data_home().join("zsh/site-functions");
+            let hint = format!(
+                "Добавьте в ~/.zshrc до compinit: fpath=({} $fpath); для phcd — source <(protonhax completions zsh)",
+                dir.display()
+            );
+            (dir.join("_protonhax"), hint)
+        }
+        CompletionShell::Fish => (
+            config_dir_base().join("fish/completions/protonhax.fish"),
+            String::new(),
+        ),
+        // Nushell (0.96+) сам выполняет файлы из vendor/autoload при старте.
+        CompletionShell::Nushell => (
+            data_home().join("nushell/vendor/autoload/protonhax.nu"),
+            String::new(),
+        ),
+        _ => fail(&format!(
+            "Установка для {0} не поддерживается; используйте protonhax completions {0} > <файл>",
+            shell.label()
+        )),
+    };
+
+    let mut script = completion_script(shell)?;
+    // В autoload-файл Nushell функцию phcd можно положить вместе с дополнениями.
+    if shell == CompletionShell::Nushell
+        && let Some(helper) = cd_helper(shell)
+    {
+        script.extend_from_slice(format!("\n{helper}\n").as_bytes());
+    }
+    write_file(&path, &script)?;
+    println!("{} {}", "Установлено:".green().bold(), path.display());
+
+    if shell == CompletionShell::Fish
+        && let Some(helper) = cd_helper(shell)
+    {
+        // Функции fish тоже подгружаются лениво — из functions/<имя>.fish.
+        let helper_path = config_dir_base().join("fish/functions/phcd.fish");
+        write_file(&helper_path, format!("{helper}\n").as_bytes())?;
+        println!(
+            "{} {}",
+            "Установлено:".green().bold(),
+            helper_path.display()
+        );
+    }
+    if !hint.is_empty() {
+        verbosity::info(hint);
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
+
+/// Функция `phcd` для перехода в каталог игры: дочерний процесс не может сменить
+/// текущий каталог оболочки, поэтому `cd` делает сама оболочка.
+fn cd_helper(shell: CompletionShell) -> Option<&'static str> {
+    match shell {
+        CompletionShell::Bash | CompletionShell::Zsh => Some(
+            "# protonhax: phcd <target> [install|pfx|drive_c|save|appdata|documents|...]\n\
+             phcd() {\n    local dir\n    dir=\"$(protonhax cd --print \"$@\")\" && cd -- \"$dir\"\n}",
+        ),
+        CompletionShell::Fish => Some(
+            "# protonhax: phcd <target> [install|pfx|drive_c|save|appdata|documents|...]\n\
+             function phcd\n    set -l dir (protonhax cd --print $argv); and cd $dir\nend",
+        ),
+        CompletionShell::PowerShell => Some(
+            "# protonhax: phcd <target> [install|pfx|drive_c|save|appdata|documents|...]\n\
+             function phcd { $dir = protonhax cd --print @args; if ($?) { Set-Location -LiteralPath $dir } }",
+        ),
+        CompletionShell::Elvish => Some(
+            "# protonhax: phcd <target> [install|pfx|drive_c|save|appdata|documents|...]\n\
+             fn phcd {|@args| cd (protonhax cd --print $@args) }",
+        ),
+        CompletionShell::Nushell => Some(
+            "# protonhax: phcd <target> [install|pfx|drive_c|save|appdata|documents|...]\n\
+             def --env phcd [...args: string] { cd (protonhax cd --print ...$args) }",
+        ),
+        CompletionShell::Carapace => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicValues, carapace_spec, dynamic_values};
+    use crate::cli::Cli;
+    use clap::{Arg, ArgAction, Command, CommandFactory};
+
+    #[test]
+    fn builds_carapace_spec() {
+        let cmd = Command::new("protonhax").subcommand(
+            Command::new("ls")
+                .about("Lists games")
+                .arg(
+                    Arg::new("long")
+                        .short('l')
+                        .long("long")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(Arg::new("api").long("api").value_parser(["dx9", "dx11"])),
+        );
+
+        let spec = carapace_spec(&cmd);
+        let ls = &spec["commands"][0];
+        assert_eq!(ls["name"], "ls");
+        assert_eq!(ls["description"], "Lists games");
+        assert!(ls["flags"].get("-l, --long").is_some());
+        assert!(ls["flags"].get("--api=").is_some());
+        assert_eq!(ls["completion"]["flag"]["api"][1], "dx11");
+    }
+
+    #[test]
+    fn finds_dynamic_positions() {
+        let mut cmd = Cli::command();
+        cmd.build();
+        let values = |line: &str| {
+            let words: Vec<String> = line.split_whitespace().map(String::from).collect();
+            dynamic_values(&cmd, &words)
+        };
+        assert_eq!(values("run"), Some(DynamicValues::Targets));
+        assert_eq!(values("-v run --timeout 5"), Some(DynamicValues::Targets));
+        assert_eq!(values("run --timeout"), None);
+        assert_eq!(values("run 620"), None);
+        assert_eq!(values("tool run 620"), Some(DynamicValues::Tools));
+        assert_eq!(values("nxm install 620 --tool"), Some(DynamicValues::Tools));
+        assert_eq!(values("tool add"), None);
+    }
+}
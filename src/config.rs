@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf, process};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::{
+    discord::DiscordConfig,
+    env_store::{EnvFilter, set_env_var},
+    inject::{DLL_OVERRIDES_ENV, merge_dll_override},
+    logs::LogRetention,
+    priority::Priority,
+    runtime::config_dir,
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Пользовательская конфигурация protonhax (`$XDG_CONFIG_HOME/protonhax/config.toml`).
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, ToolConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nxm: Option<NxmConfig>,
+    /// Какие переменные не сохранять в окружение контекста (`[env] allow/deny`).
+    #[serde(skip_serializing_if = "EnvFilter::is_empty")]
+    pub env: EnvFilter,
+    /// Настройки по умолчанию для отдельных игр (`[apps.<appid>]`, можно `[app.<appid>]`).
+    #[serde(alias = "app", skip_serializing_if = "BTreeMap::is_empty")]
+    pub apps: BTreeMap<String, AppConfig>,
+    /// Сколько хранить логи (`[logs]`).
+    #[serde(skip_serializing_if = "LogRetention::is_default")]
+    pub logs: LogRetention,
+    /// Статус игры в Discord (`[discord]`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discord: Option<DiscordConfig>,
+}
+
+/// Описание зарегистрированного внешнего инструмента.
+#[derive(Serialize, Deserialize)]
+pub struct ToolConfig {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Запускать нативно (как `exec`), а не через proton.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub native: bool,
+    /// Запускать в отдельном scope-юните systemd (как `tool run --scope`).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub scope: bool,
+}
+
+/// Настройки конкретной игры.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Приоритеты для `run`/`exec` (`nice`, `ionice`), если не заданы флагами.
+    #[serde(flatten)]
+    pub priority: Priority,
+    /// Переменные поверх сохранённого окружения игры.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// `dll = "режим"`, дописываются к `WINEDLLOVERRIDES`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dll_overrides: BTreeMap<String, String>,
+    /// Команды перед proton в `run`, например `["gamemoderun", "mangohud"]`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<String>,
+    /// Инструмент для `tool run <target>` без имени.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
+
+impl AppConfig {
+    /// Применяет переменные и переопределения DLL к окружению текущего процесса, откуда
+    /// их унаследуют запускаемые команды.
+    pub fn apply_env(&self) {
+        for (name, value) in &self.env {
+            set_env_var(name, value);
+        }
+        if self.dll_overrides.is_empty() {
+            return;
+        }
+        let mut overrides = env::var(DLL_OVERRIDES_ENV).unwrap_or_default();
+        for (dll, mode) in &self.dll_overrides {
+            overrides = merge_dll_override(&overrides, dll, mode);
+        }
+        set_env_var(DLL_OVERRIDES_ENV, &overrides);
+    }
+}
+
+/// Куда направлять ссылки `nxm://`: селектор контекста и зарегистрированный инструмент.
+#[derive(Serialize, Deserialize)]
+pub struct NxmConfig {
+    pub appid: String,
+    pub tool: String,
+}
+
+impl Config {
+    pub fn load() -> io::Result<Self> {
+        let path = config_path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+
+        toml::from_str(&content).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {err}", path.display()),
+            )
+        })
+    }
+
+    /// Записывает инструменты в `[tools.<name>]`, заменяя одноимённые; остальной файл,
+    /// включая комментарии и незнакомые ключи, не меняется.
+    pub fn save_tools<'a>(
+        tools: impl IntoIterator<Item = (&'a str, &'a ToolConfig)>,
+    ) -> io::Result<()> {
+        edit_config(|document| {
+            let table = document
+                .entry("tools")
+                .or_insert_with(|| {
+                    let mut table = Table::new();
+                    table.set_implicit(true);
+                    Item::Table(table)
+                })
+                .as_table_mut()
+                .ok_or_else(|| invalid_config("tools должен быть таблицей"))?;
+            for (name, tool) in tools {
+                replace_table(table, name, tool)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Записывает `[nxm]`, не трогая остальной файл.
+    pub fn save_nxm(nxm: &NxmConfig) -> io::Result<()> {
+        edit_config(|document| replace_table(document.as_table_mut(), "nxm", nxm))
+    }
+}
+
+/// Правит `config.toml` на месте: файл разбирается с сохранением оформления, и меняются
+/// только таблицы, которых касается `edit`.
+fn edit_config(edit: impl FnOnce(&mut DocumentMut) -> io::Result<()>) -> io::Result<()> {
+    let path = config_path();
+    let mut document = match fs::read_to_string(&path) {
+        Ok(content) => content
+            .parse::<DocumentMut>()
+            .map_err(|err| invalid_config(&format!("{}: {err}", path.display())))?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => DocumentMut::new(),
+        Err(err) => return Err(err),
+    };
+    edit(&mut document)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Пишем во временный файл и переименовываем, чтобы не оставить конфиг полузаписанным;
+    // имя с pid, чтобы параллельные запуски не писали в один файл.
+    let tmp_path = path.with_extension(format!("toml.{}.tmp", process::id()));
+    fs::write(&tmp_path, document.to_string())?;
+    fs::rename(tmp_path, path)
+}
+
+/// Ставит `value` таблицей `key`; у заменяемой таблицы сохраняются место в файле и
+/// комментарии над заголовком.
+fn replace_table(parent: &mut Table, key: &str, value: &impl Serialize) -> io::Result<()> {
+    let serialized = toml::to_string(value).map_err(io::Error::other)?;
+    let mut table = serialized
+        .parse::<DocumentMut>()
+        .map_err(io::Error::other)?
+        .into_table();
+    if let Some(old) = parent.get(key).and_then(Item::as_table) {
+        table.set_position(old.position());
+        *table.decor_mut() = old.decor().clone();
+    }
+    parent.insert(key, Item::Table(table));
+    Ok(())
+}
+
+fn invalid_config(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join(CONFIG_FILE)
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ToolConfig, replace_table};
+    use toml_edit::DocumentMut;
+
+    #[test]
+    fn parses_tool_definitions() {
+        let config: Config = toml::from_str(
+            r#"
+                [tools.ce]
+                path = "/opt/ce/cheatengine-x86_64.exe"
+                args = ["--table", "{install_dir}/game.CT"]
+
+                [tools.mo2]
+                path = "{install_dir}/MO2/ModOrganizer.exe"
+            "#,
+        )
+        .unwrap();
+
+        let ce = &config.tools["ce"];
+        assert_eq!(ce.path, "/opt/ce/cheatengine-x86_64.exe");
+        assert_eq!(ce.args, ["--table", "{install_dir}/game.CT"]);
+        assert!(!ce.native);
+        assert!(config.tools["mo2"].args.is_empty());
+    }
+
+    #[test]
+    fn replaces_tool_tables_in_place() {
+        let mut document: DocumentMut = r#"# мои настройки
+[apps.620]
+wrappers = ["gamemoderun"] # режим игры
+
+# старый путь
+[tools.ce]
+path = "/old/ce.exe"
+
+[discord]
+client_id = "1"
+"#
+        .parse()
+        .unwrap();
+        let tool = ToolConfig {
+            path: "/opt/ce.exe".into(),
+            args: Vec::new(),
+            native: false,
+            scope: false,
+        };
+        let tools = document["tools"].as_table_mut().unwrap();
+        replace_table(tools, "ce", &tool).unwrap();
+        replace_table(tools, "mo2", &tool).unwrap();
+        let saved = document.to_string();
+        assert!(saved.starts_with(
+            "# мои настройки\n[apps.620]\nwrappers = [\"gamemoderun\"] # режим игры\n\n\
+             # старый путь\n[tools.ce]\npath = \"/opt/ce.exe\"\n"
+        ));
+        assert!(saved.contains("[discord]\nclient_id = \"1\"\n"));
+        assert!(saved.contains("[tools.mo2]\npath = \"/opt/ce.exe\"\n"));
+    }
+}
@@ -0,0 +1,718 @@
+use colored::Colorize;
+use std::{
+    env, fs, io,
+    os::unix::fs::DirBuilderExt,
+    panic,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::{Mutex, Once},
+};
+
+use crate::{
+    cli::{fail, print_subcommand_usage_error},
+    config::Config,
+    env_store::{load_env, set_env_var},
+    external::{ExternalGame, external_games, find_external},
+    lock::ContextLock,
+    names::NameSource,
+    prompt::CONTEXT_ENV,
+    proton::context_proton_version,
+    runtime::{data_home, parallel_map},
+    signals::process_alive,
+    steam::{AppMeta, resolve_app_meta},
+    verbosity,
+    wine::is_windows_path,
+};
+
+pub const EXE_FILE: &str = "exe";
+pub const PFX_FILE: &str = "pfx";
+pub const STARTED_AT_FILE: &str = "started_at";
+pub const PID_FILE: &str = "pid";
+/// Пишутся `init --keep` после выхода игры: время завершения и код выхода.
+pub const ENDED_AT_FILE: &str = "ended_at";
+pub const EXIT_CODE_FILE: &str = "exit_code";
+/// Бинарник Wine для контекстов лаунчеров без Proton; `run` использует его вместо `exe`.
+pub const WINE_FILE: &str = "wine";
+/// Отметка `init` для игры без Proton: в контексте только окружение для `exec`.
+pub const NATIVE_FILE: &str = "native";
+/// Зависимости, поставленные `install-deps`, по имени на строку.
+pub const DEPS_FILE: &str = "deps";
+pub const STEAM_APP_ID_ENV: &str = "SteamAppId";
+pub const STEAM_COMPAT_DATA_PATH_ENV: &str = "STEAM_COMPAT_DATA_PATH";
+/// Задают некоторые обёртки без `SteamAppId`.
+pub const STEAM_COMPAT_APP_ID_ENV: &str = "STEAM_COMPAT_APP_ID";
+const LATEST_SELECTOR: &str = "latest";
+/// Повторные сессии одной игры хранятся в каталогах `<appid>.<pid>`.
+const INSTANCE_SEPARATOR: char = '.';
+/// Селектор игры, в каталоге установки или compatdata которой находится `$PWD`.
+const CWD_SELECTOR: &str = ".";
+
+pub struct RunningApp {
+    /// Имя каталога контекста: `<appid>` или `<appid>.<pid>` для повторной сессии.
+    pub id: String,
+    pub appid: String,
+    pub path: PathBuf,
+    pub name: Option<String>,
+    pub install_path: Option<String>,
+    /// Сборка игры в Steam (`buildid` манифеста).
+    pub build_id: Option<String>,
+    pub started_at: Option<u64>,
+    /// Игра завершилась, контекст сохранён `init --keep`.
+    pub ended_at: Option<u64>,
+    pub exit_code: Option<i32>,
+    /// Pid `init`; контексты старых версий его не записывали.
+    pub pid: Option<libc::pid_t>,
+    /// Сборка Proton (`Proton 9.0-4`, `GE-Proton9-20`); читается вместе с метаданными.
+    pub proton: Option<String>,
+    /// Результат дешёвой проверки при сборе списка; пусто — контекст исправен.
+    pub problems: Vec<HealthProblem>,
+}
+
+/// Что мешает запуску в контексте.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HealthProblem {
+    /// Нет скрипта proton (или бинарника Wine): сборку удалили или обновили.
+    MissingExe,
+    MissingPfx,
+    /// `init` не жив, а отметки о завершении нет.
+    DeadPid,
+}
+
+impl HealthProblem {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingExe => "exe missing",
+            Self::MissingPfx => "pfx missing",
+            Self::DeadPid => "pid not alive",
+        }
+    }
+}
+
+/// Состояние контекста в `ls`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ContextStatus {
+    Running,
+    /// Игра завершилась, контекст сохранён `init --keep`.
+    Ended,
+    /// `init` не жив, а отметки о завершении нет: остаток после падения или `kill -9`.
+    Stale,
+    /// Нет proton или префикса: `run` в таком контексте не сработает.
+    Broken,
+    /// Постоянный контекст `register`/`prefix create`.
+    Registered,
+}
+
+impl ContextStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Ended => "ended",
+            Self::Stale => "stale",
+            Self::Broken => "broken",
+            Self::Registered => "registered",
+        }
+    }
+}
+
+impl RunningApp {
+    /// Состояние runtime-контекста; зарегистрированные контексты помечает вызывающий.
+    pub fn status(&self) -> ContextStatus {
+        if self.ended_at.is_some() {
+            ContextStatus::Ended
+        } else if self
+            .problems
+            .iter()
+            .any(|problem| *problem != HealthProblem::DeadPid)
+        {
+            ContextStatus::Broken
+        } else if self.problems.contains(&HealthProblem::DeadPid) {
+            ContextStatus::Stale
+        } else {
+            ContextStatus::Running
+        }
+    }
+}
+
+pub struct TargetApp {
+    pub appid: String,
+    pub app_dir: PathBuf,
+}
+
+impl TargetApp {
+    pub fn exe(&self) -> io::Result<String> {
+        self.read_runner_file(EXE_FILE)
+    }
+
+    pub fn pfx(&self) -> io::Result<String> {
+        self.read_runner_file(PFX_FILE)
+    }
+
+    /// Игра запущена нативно: ни Proton, ни префикса у контекста нет.
+    pub fn is_native(&self) -> bool {
+        self.app_dir.join(NATIVE_FILE).exists()
+    }
+
+    /// Прекращает команду, которой нужен Proton, если контекст нативный.
+    pub fn require_proton(&self) {
+        if self.is_native() {
+            fail(&format!(
+                "{} запущена без Proton: в её контексте доступен только exec",
+                self.appid
+            ));
+        }
+    }
+
+    fn read_runner_file(&self, file: &str) -> io::Result<String> {
+        if self.is_native() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}: контекст нативной игры, {file} нет", self.appid),
+            ));
+        }
+        read_trimmed(self.app_dir.join(file))
+    }
+
+    pub fn wine(&self) -> Option<String> {
+        read_trimmed(self.app_dir.join(WINE_FILE))
+            .ok()
+            .filter(|wine| !wine.is_empty())
+    }
+
+    pub fn meta(&self) -> AppMeta {
+        // Один контекст: ради имени можно подождать магазин.
+        resolve_app_meta(&self.app_dir, &self.appid, NameSource::Store)
+    }
+}
+
+/// Находит контекст по селектору и применяет его окружение к текущему процессу.
+pub fn prepare_context(phd: &Path, selector: &str) -> io::Result<TargetApp> {
+    let target = resolve_target_app(phd, selector)?;
+    verbosity::verbose(format_args!(
+        "\"{selector}\" -> контекст {} ({})",
+        target.appid,
+        target.app_dir.display()
+    ));
+    // Не читаем контекст, который `init` сейчас пишет или удаляет.
+    let _lock = ContextLock::shared(&target.app_dir, &target.appid)?;
+    set_env_var(STEAM_APP_ID_ENV, &target.appid);
+    load_env(&target.app_dir)?;
+    set_env_var(CONTEXT_ENV, &target.appid);
+    if let Some(app) = Config::load()?.apps.get(&target.appid) {
+        verbosity::verbose(format_args!("применены настройки [apps.{}]", target.appid));
+        app.apply_env();
+    }
+    Ok(target)
+}
+
+/// Каталог постоянных контекстов (`protonhax register`): та же структура, что у
+/// runtime-каталога, но без привязки к запущенной игре.
+pub fn registered_root() -> PathBuf {
+    data_home().join("protonhax").join("contexts")
+}
+
+pub fn resolve_target_app(phd: &Path, selector: &str) -> io::Result<TargetApp> {
+    if selector.eq_ignore_ascii_case(LATEST_SELECTOR) {
+        return resolve_latest_app(phd);
+    }
+    if selector == CWD_SELECTOR {
+        return resolve_app_from_cwd(phd);
+    }
+
+    let app_dir = phd.join(selector);
+    if app_dir.is_dir() {
+        verbosity::debug(format_args!(
+            "\"{selector}\": каталог контекста в {}",
+            phd.display()
+        ));
+        return Ok(TargetApp {
+            appid: context_appid(selector).to_string(),
+            app_dir,
+        });
+    }
+
+    let apps = collect_running_apps(phd, false)?;
+    let instances: Vec<&RunningApp> = apps.iter().filter(|app| app.appid == selector).collect();
+    match instances.as_slice() {
+        [] => {
+            // Живого контекста нет — пробуем зарегистрированный с тем же appid.
+            let registered = registered_root().join(selector);
+            if registered.is_dir() {
+                verbosity::debug(format_args!(
+                    "\"{selector}\": живых сессий нет, зарегистрированный контекст"
+                ));
+                return Ok(TargetApp {
+                    appid: selector.to_string(),
+                    app_dir: registered,
+                });
+            }
+            // Затем игры сторонних лаунчеров (`heroic-<appName>`, `lutris-<slug>`).
+            if let Some(game) = find_external(selector) {
+                verbosity::debug(format_args!("\"{selector}\": игра {}", game.launcher));
+                return Ok(TargetApp {
+                    appid: game.id.clone(),
+                    app_dir: game.materialize()?,
+                });
+            }
+            verbosity::debug(format_args!("\"{selector}\": не appid, ищем по имени"));
+            resolve_app_by_name(phd, selector)
+        }
+        [app] => Ok(TargetApp {
+            appid: app.appid.clone(),
+            app_dir: app.path.clone(),
+        }),
+        _ => {
+            print_ambiguous_matches(selector, &instances);
+            process::exit(2);
+        }
+    }
+}
+
+/// Есть ли хотя бы одна сессия (основная или повторная) или зарегистрированный контекст
+/// с таким селектором.
+pub fn has_context(phd: &Path, selector: &str) -> bool {
+    phd.join(selector).is_dir()
+        || registered_root().join(selector).is_dir()
+        || fs::read_dir(phd)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|entry| {
+                entry.path().is_dir()
+                    && context_appid(&entry.file_name().to_string_lossy()) == selector
+            })
+}
+
+/// Каталог контекста, который `init` ещё не дописал: его убирает panic hook.
+static PENDING_CONTEXT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Создаваемый `init` контекст. Пока не вызван [`ContextGuard::commit`], каталог
+/// удаляется при выходе из области видимости (ошибка через `?`) и при панике: в release
+/// `panic = "abort"`, деструкторы не выполняются, поэтому нужен ещё и panic hook.
+pub struct ContextGuard {
+    dir: PathBuf,
+    committed: bool,
+}
+
+impl ContextGuard {
+    pub fn create(dir: PathBuf) -> io::Result<Self> {
+        install_cleanup_hook();
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)?;
+        set_pending(Some(dir.clone()));
+        Ok(Self {
+            dir,
+            committed: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Контекст записан целиком: дальше его судьбу решает сам `init`.
+    pub fn commit(mut self) {
+        self.committed = true;
+        set_pending(None);
+    }
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        if !self.committed {
+            set_pending(None);
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+fn set_pending(dir: Option<PathBuf>) {
+    if let Ok(mut pending) = PENDING_CONTEXT.lock() {
+        *pending = dir;
+    }
+}
+
+fn install_cleanup_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let pending = PENDING_CONTEXT.lock().ok().and_then(|mut dir| dir.take());
+            if let Some(dir) = pending {
+                let _ = fs::remove_dir_all(dir);
+            }
+            default_hook(info);
+        }));
+    });
+}
+
+/// Каталог для новой сессии: `<appid>`, если он свободен или остался от завершившейся
+/// сессии, иначе `<appid>.<pid>`.
+pub fn allocate_context_dir(phd: &Path, appid: &str, pid: u32) -> io::Result<PathBuf> {
+    let primary = phd.join(appid);
+    if !primary.exists() {
+        return Ok(primary);
+    }
+    if !is_context_alive(&primary) {
+        fs::remove_dir_all(&primary)?;
+        return Ok(primary);
+    }
+    Ok(phd.join(format!("{appid}{INSTANCE_SEPARATOR}{pid}")))
+}
+
+/// Жив ли процесс `init`, создавший контекст. Контексты без `pid` (от старых версий)
+/// считаем живыми, чтобы не удалить чужую сессию.
+fn is_context_alive(app_dir: &Path) -> bool {
+    let Ok(pid) = read_trimmed(app_dir.join(PID_FILE)) else {
+        return true;
+    };
+    pid.parse::<u32>().map_or(true, |pid| {
+        Path::new("/proc").join(pid.to_string()).exists()
+    })
+}
+
+pub fn context_appid(id: &str) -> &str {
+    id.split_once(INSTANCE_SEPARATOR)
+        .map_or(id, |(appid, _)| appid)
+}
+
+fn resolve_latest_app(phd: &Path) -> io::Result<TargetApp> {
+    // Сохранённые `init --keep` контексты завершившихся игр не считаются последними.
+    let mut apps = collect_running_apps(phd, false)?;
+    apps.retain(|app| app.ended_at.is_none());
+    if apps.is_empty() {
+        eprintln!(
+            "{} Нет активных контекстов. Сначала запустите игру через Steam.",
+            "Ошибка:".bold().red()
+        );
+        process::exit(2);
+    }
+
+    if let Some(app) = apps
+        .iter()
+        .filter_map(|app| app.started_at.map(|started_at| (started_at, app)))
+        .max_by_key(|(started_at, _)| *started_at)
+        .map(|(_, app)| app)
+    {
+        return Ok(TargetApp {
+            appid: app.appid.clone(),
+            app_dir: app.path.clone(),
+        });
+    }
+
+    if apps.len() == 1 {
+        let app = &apps[0];
+        return Ok(TargetApp {
+            appid: app.appid.clone(),
+            app_dir: app.path.clone(),
+        });
+    }
+
+    eprintln!(
+        "{} Невозможно определить latest: нет started_at у активных контекстов.",
+        "Ошибка:".bold().red()
+    );
+    eprintln!("Укажите appid явно (см. `protonhax ls -l`).");
+    process::exit(2);
+}
+
+/// Для `run ./Tool.exe` без appid clap отдаёт путь как селектор: в этом случае
+/// возвращаем его в команду, а игру определяем по текущему каталогу. Одинокий селектор
+/// (`run latest`, `run 1245620` без команды) — ошибка использования, а не программа.
+pub fn split_implicit_target(
+    phd: &Path,
+    subcommand: &str,
+    appid: &str,
+    cmd: &[String],
+) -> (String, Vec<String>) {
+    let is_selector = appid == CWD_SELECTOR
+        || appid.eq_ignore_ascii_case(LATEST_SELECTOR)
+        || has_context(phd, appid);
+    let looks_like_path =
+        appid.contains(['/', '\\']) || is_windows_path(appid) || Path::new(appid).is_file();
+    if !is_selector && looks_like_path {
+        let mut full_cmd = vec![appid.to_string()];
+        full_cmd.extend_from_slice(cmd);
+        return (CWD_SELECTOR.to_string(), full_cmd);
+    }
+    if cmd.is_empty() {
+        print_subcommand_usage_error(subcommand, "Не указана команда для запуска");
+    }
+    (appid.to_string(), cmd.to_vec())
+}
+
+fn resolve_app_from_cwd(phd: &Path) -> io::Result<TargetApp> {
+    let cwd = env::current_dir()?;
+    let cwd = fs::canonicalize(&cwd).unwrap_or(cwd);
+    let apps = collect_running_apps(phd, true)?;
+
+    let best = apps
+        .iter()
+        .filter_map(|app| {
+            let compat_data = read_trimmed(app.path.join(PFX_FILE))
+                .ok()
+                .and_then(|pfx| Path::new(&pfx).parent().map(Path::to_path_buf));
+            let install_path = app.install_path.as_deref().map(PathBuf::from);
+            let depth = [install_path, compat_data]
+                .into_iter()
+                .flatten()
+                .filter_map(|root| match_depth(&cwd, &root))
+                .max()?;
+            Some((depth, app))
+        })
+        .max_by_key(|(depth, _)| *depth);
+
+    let Some((_, app)) = best else {
+        eprintln!(
+            "{} Текущий каталог {} не относится ни к одной запущенной игре.",
+            "Ошибка:".bold().red(),
+            cwd.display()
+        );
+        eprintln!("Укажите appid явно (см. `protonhax ls -l`).");
+        process::exit(2);
+    };
+    Ok(TargetApp {
+        appid: app.appid.clone(),
+        app_dir: app.path.clone(),
+    })
+}
+
+/// Глубина корня `root`, если `cwd` лежит внутри него; вложенные корни точнее.
+fn match_depth(cwd: &Path, root: &Path) -> Option<usize> {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    cwd.starts_with(&root).then(|| root.components().count())
+}
+
+fn resolve_app_by_name(phd: &Path, query: &str) -> io::Result<TargetApp> {
+    let name_matches = |apps: Vec<RunningApp>| -> Vec<RunningApp> {
+        apps.into_iter()
+            .filter(|app| {
+                app.name
+                    .as_deref()
+                    .is_some_and(|name| contains_case_insensitive(name, query))
+            })
+            .collect()
+    };
+    let mut found = name_matches(collect_running_apps(phd, true)?);
+    if found.is_empty() {
+        found = name_matches(collect_running_apps(&registered_root(), true)?);
+    }
+    if found.is_empty() {
+        let mut games: Vec<ExternalGame> = external_games()
+            .into_iter()
+            .filter(|game| contains_case_insensitive(&game.name, query))
+            .collect();
+        if games.len() == 1 {
+            let game = games.remove(0);
+            return Ok(TargetApp {
+                app_dir: game.materialize()?,
+                appid: game.id,
+            });
+        }
+        found = games
+            .into_iter()
+            .map(|game| RunningApp {
+                appid: game.id.clone(),
+                id: game.id,
+                path: PathBuf::new(),
+                name: Some(game.name),
+                install_path: game.install_path,
+                build_id: None,
+                started_at: None,
+                ended_at: None,
+                exit_code: None,
+                pid: None,
+                proton: None,
+                problems: Vec::new(),
+            })
+            .collect();
+    }
+    let matches: Vec<&RunningApp> = found.iter().collect();
+
+    match matches.as_slice() {
+        [app] => Ok(TargetApp {
+            appid: app.appid.clone(),
+            app_dir: app.path.clone(),
+        }),
+        [] => {
+            eprintln!(
+                "{} Нет запущенного приложения с appid \"{query}\" и нет совпадений по имени.",
+                "Ошибка:".bold().red()
+            );
+            process::exit(2);
+        }
+        _ => {
+            print_ambiguous_matches(query, &matches);
+            process::exit(2);
+        }
+    }
+}
+
+fn print_ambiguous_matches(query: &str, matches: &[&RunningApp]) {
+    eprintln!(
+        "{} Несколько совпадений по имени \"{query}\":",
+        "Ошибка:".bold().red()
+    );
+    for app in matches {
+        let name = app.name.as_deref().unwrap_or("<без названия>");
+        eprintln!("  {}  {}", app.id.green(), name.yellow());
+    }
+    eprintln!("Уточните appid через `protonhax ls -l`.");
+}
+
+pub fn collect_running_apps(phd: &Path, with_meta: bool) -> io::Result<Vec<RunningApp>> {
+    if !phd.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(phd)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            paths.push(path);
+        }
+    }
+
+    // Без метаданных читать почти нечего: потоки дороже самих чтений.
+    let mut apps: Vec<RunningApp> = if with_meta {
+        parallel_map(&paths, |path| read_running_app(path.clone(), true))
+    } else {
+        paths
+            .into_iter()
+            .map(|path| read_running_app(path, false))
+            .collect()
+    };
+
+    apps.sort_by(|left, right| left.id.cmp(&right.id));
+    Ok(apps)
+}
+
+/// Контекст из каталога `<root>/<id>`.
+pub fn read_running_app(path: PathBuf, with_meta: bool) -> RunningApp {
+    let id = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let appid = context_appid(&id).to_string();
+    let (meta, proton) = if with_meta {
+        // Списки и поиск по имени сеть не ждут.
+        let meta = resolve_app_meta(&path, &appid, NameSource::Local);
+        // Контексты старых версий сборку при `init` не записывали.
+        let proton = meta.proton.clone().or_else(|| proton_version(&path));
+        (meta, proton)
+    } else {
+        (AppMeta::default(), None)
+    };
+    let started_at = read_number(&path.join(STARTED_AT_FILE));
+    let ended_at = read_number(&path.join(ENDED_AT_FILE));
+    let exit_code = read_number(&path.join(EXIT_CODE_FILE));
+    let pid = read_number(&path.join(PID_FILE));
+    let mut problems = check_health(&path);
+    // У завершившейся сессии pid и не должен быть жив.
+    if ended_at.is_none() && pid.is_some_and(|pid| !process_alive(pid)) {
+        problems.push(HealthProblem::DeadPid);
+    }
+
+    RunningApp {
+        id,
+        appid,
+        path,
+        name: meta.name,
+        install_path: meta.install_path,
+        build_id: meta.build_id,
+        started_at,
+        ended_at,
+        exit_code,
+        pid,
+        proton,
+        problems,
+    }
+}
+
+/// У контекстов на чистом Wine сборки Proton нет.
+fn proton_version(app_dir: &Path) -> Option<String> {
+    if app_dir.join(WINE_FILE).exists() {
+        return None;
+    }
+    let exe = read_trimmed(app_dir.join(EXE_FILE)).ok()?;
+    let pfx = read_trimmed(app_dir.join(PFX_FILE)).ok()?;
+    let compat_data = Path::new(&pfx).parent()?;
+    context_proton_version(Path::new(&exe), compat_data)
+}
+
+/// Только проверки `stat`: список строится и для `ls`, и при каждом поиске цели.
+fn check_health(app_dir: &Path) -> Vec<HealthProblem> {
+    let non_empty = |file: &str| {
+        read_trimmed(app_dir.join(file))
+            .ok()
+            .filter(|value| !value.is_empty())
+    };
+    let mut problems = Vec::new();
+    if app_dir.join(NATIVE_FILE).exists() {
+        return problems;
+    }
+    let launcher = non_empty(WINE_FILE).or_else(|| non_empty(EXE_FILE));
+    if launcher.is_none_or(|path| !Path::new(&path).is_file()) {
+        problems.push(HealthProblem::MissingExe);
+    }
+    if non_empty(PFX_FILE).is_none_or(|pfx| !Path::new(&pfx).is_dir()) {
+        problems.push(HealthProblem::MissingPfx);
+    }
+    problems
+}
+
+pub fn read_trimmed<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    Ok(fs::read_to_string(path)?.trim().to_string())
+}
+
+pub fn read_number<T: FromStr>(path: &Path) -> Option<T> {
+    let val = fs::read_to_string(path).ok()?;
+    val.trim().parse::<T>().ok()
+}
+
+fn contains_case_insensitive(text: &str, query: &str) -> bool {
+    if text.is_ascii() && query.is_ascii() {
+        return contains_ascii_case_insensitive(text.as_bytes(), query.as_bytes());
+    }
+
+    text.to_lowercase().contains(&query.to_lowercase())
+}
+
+fn contains_ascii_case_insensitive(text: &[u8], query: &[u8]) -> bool {
+    query.is_empty()
+        || text
+            .windows(query.len())
+            .any(|window| window.eq_ignore_ascii_case(query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contains_case_insensitive, context_appid, match_depth};
+    use std::path::Path;
+
+    #[test]
+    fn case_insensitive_search() {
+        assert!(contains_case_insensitive("Gunfire Reborn", "gunfire"));
+        assert!(contains_case_insensitive("GUNFIRE REBORN", "reborn"));
+        assert!(!contains_case_insensitive("Gunfire Reborn", "helldivers"));
+    }
+
+    #[test]
+    fn matches_cwd_inside_root() {
+        let root = Path::new("/nonexistent/steamapps/common/Game");
+        assert_eq!(match_depth(&root.join("bin"), root), Some(5));
+        assert_eq!(match_depth(root, root), Some(5));
+        assert_eq!(
+            match_depth(Path::new("/nonexistent/steamapps/common/GameTwo"), root),
+            None
+        );
+    }
+
+    #[test]
+    fn splits_instance_suffix() {
+        assert_eq!(context_appid("1217060"), "1217060");
+        assert_eq!(context_appid("1217060.4242"), "1217060");
+    }
+}
@@ -0,0 +1,200 @@
+use colored::Colorize;
+use std::{
+    cmp::Reverse,
+    env,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
+
+use crate::{
+    cli::fail,
+    config::Config,
+    env_store::{ENV_FILE, redact_env_content},
+    runtime::{log_dir, proton_log_path, unix_now_secs},
+    wine::{PrefixContext, locate_prefix},
+};
+
+/// Сколько последних файлов из каталога логов protonhax попадает в архив.
+const RECENT_LOGS_LIMIT: usize = 5;
+
+/// Собирает логи Proton, бэктрейсы падений, окружение (без секретов) и сведения
+/// о системе в `protonhax-crashdump-<appid>-<time>.tar.gz`.
+pub fn handle_crashdump(phd: &Path, selector: &str, output: Option<PathBuf>) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let stamp = unix_now_secs();
+    let name = format!("protonhax-crashdump-{}-{stamp}", prefix.appid);
+    let staging = env::temp_dir().join(&name);
+    fs::create_dir_all(&staging)?;
+
+    let collected = collect(&prefix, &staging);
+    let archive = output.unwrap_or_else(|| PathBuf::from(format!("{name}.tar.gz")));
+    let packed = collected.and_then(|()| pack(&staging, &archive));
+    let _ = fs::remove_dir_all(&staging);
+    packed?;
+
+    println!(
+        "{} crash report written to {}",
+        "OK".green().bold(),
+        archive.display()
+    );
+    Ok(())
+}
+
+fn collect(prefix: &PrefixContext, staging: &Path) -> io::Result<()> {
+    let proton_log = proton_log_path(&prefix.appid);
+    if let Ok(content) = fs::read_to_string(&proton_log) {
+        fs::write(staging.join("proton.log"), &content)?;
+        let backtraces = extract_backtraces(&content);
+        if !backtraces.is_empty() {
+            fs::write(staging.join("backtraces.txt"), backtraces.join("\n"))?;
+        }
+    }
+
+    let logs = staging.join("logs");
+    for log in recent_files(&log_dir(&prefix.appid), RECENT_LOGS_LIMIT) {
+        fs::create_dir_all(&logs)?;
+        if let Some(file_name) = log.file_name() {
+            fs::copy(&log, logs.join(file_name))?;
+        }
+    }
+
+    // Минидампы Windows Error Reporting внутри префикса.
+    let dumps = staging.join("crashdumps");
+    let crash_dir = prefix
+        .pfx
+        .join("drive_c/users/steamuser/AppData/Local/CrashDumps");
+    for dump in recent_files(&crash_dir, RECENT_LOGS_LIMIT) {
+        fs::create_dir_all(&dumps)?;
+        if let Some(file_name) = dump.file_name() {
+            fs::copy(&dump, dumps.join(file_name))?;
+        }
+    }
+
+    if let Some(app_dir) = &prefix.app_dir
+        && let Ok(env_content) = fs::read_to_string(app_dir.join(ENV_FILE))
+    {
+        let filter = Config::load().map(|config| config.env).unwrap_or_default();
+        fs::write(
+            staging.join("env.txt"),
+            redact_env_content(&env_content, &filter),
+        )?;
+    }
+
+    fs::write(staging.join("system.txt"), system_info(prefix))
+}
+
+fn pack(staging: &Path, archive: &Path) -> io::Result<()> {
+    let (Some(parent), Some(name)) = (staging.parent(), staging.file_name()) else {
+        return Err(io::Error::other("некорректный каталог для архива"));
+    };
+    let status = process::Command::new("tar")
+        .arg("-czf")
+        .arg(archive)
+        .arg("-C")
+        .arg(parent)
+        .arg(name)
+        .status()
+        .map_err(|err| {
+            if err.kind() == io::ErrorKind::NotFound {
+                io::Error::new(err.kind(), "tar не найден в PATH")
+            } else {
+                err
+            }
+        })?;
+    if !status.success() {
+        fail(&format!("tar завершился с ошибкой ({status})"));
+    }
+    Ok(())
+}
+
+fn system_info(prefix: &PrefixContext) -> String {
+    let mut info = String::new();
+    let _ = writeln!(info, "protonhax {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(info, "appid: {}", prefix.appid);
+    let _ = writeln!(info, "proton: {}", prefix.proton.display());
+    if let Ok(version) = fs::read_to_string(prefix.proton_dir().join("version")) {
+        let _ = writeln!(info, "proton version: {}", version.trim());
+    }
+    if let Ok(output) = process::Command::new("uname").arg("-a").output() {
+        let _ = write!(info, "uname: {}", String::from_utf8_lossy(&output.stdout));
+    }
+    if let Ok(os_release) = fs::read_to_string("/etc/os-release") {
+        let _ = write!(info, "\n{os_release}");
+    }
+    info
+}
+
+/// Самые свежие по времени изменения файлы каталога.
+fn recent_files(dir: &Path, limit: usize) -> Vec<PathBuf> {
+    let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file()
+                .then(|| Some((meta.modified().ok()?, entry.path())))
+                .flatten()
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| Reverse(*modified));
+    files
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Блоки `Backtrace:`, которые winedbg печатает в лог при падении, до первой пустой
+/// строки или списка модулей.
+fn extract_backtraces(log: &str) -> Vec<String> {
+    let mut backtraces = Vec::new();
+    let mut current: Option<String> = None;
+    for line in log.lines() {
+        if line.trim_start().starts_with("Backtrace:") {
+            if let Some(block) = current.take() {
+                backtraces.push(block);
+            }
+            current = Some(String::new());
+        }
+        let Some(block) = current.as_mut() else {
+            continue;
+        };
+        if line.trim().is_empty() || line.starts_with("Modules:") {
+            backtraces.extend(current.take());
+            continue;
+        }
+        block.push_str(line);
+        block.push('\n');
+    }
+    backtraces.extend(current);
+    backtraces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract_backtraces;
+
+    #[test]
+    fn extracts_winedbg_backtraces() {
+        let log = "\
+info: game started
+Unhandled exception: page fault on read access to 0x00000000
+Backtrace:
+=>0 0x0140001234 in game (+0x1234)
+  1 0x0140005678 in game (+0x5678)
+Modules:
+Module\tAddress
+";
+        assert_eq!(
+            extract_backtraces(log),
+            vec![
+                "Backtrace:\n=>0 0x0140001234 in game (+0x1234)\n  1 0x0140005678 in game (+0x5678)\n"
+            ]
+        );
+        assert!(extract_backtraces("no crash here").is_empty());
+    }
+}
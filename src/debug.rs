@@ -0,0 +1,59 @@
+use std::{io, path::Path, process};
+
+use crate::{cli::fail, context::prepare_context, runtime::exit_with_status};
+
+/// Что отлаживать: уже запущенный Windows-процесс или новую программу.
+pub enum DebugTarget {
+    /// Интерактивный winedbg: `info process` покажет процессы префикса.
+    Shell,
+    Attach(String),
+    Launch(Vec<String>),
+}
+
+/// Запускает `winedbg` через proton с окружением игры; `--gdb` поднимает gdb-прокси winedbg.
+pub fn handle_debug(phd: &Path, selector: &str, target: DebugTarget, gdb: bool) -> io::Result<()> {
+    let app = prepare_context(phd, selector)?;
+    let mut cmd = process::Command::new(app.exe()?);
+    cmd.args(["run", "winedbg"]);
+    if gdb {
+        cmd.arg("--gdb");
+    }
+
+    match target {
+        DebugTarget::Shell => {}
+        DebugTarget::Attach(pid) => {
+            if parse_windows_pid(&pid).is_none() {
+                fail(&format!(
+                    "Некорректный pid Windows-процесса: {pid} (ожидается десятичное число или 0x…)"
+                ));
+            }
+            cmd.arg(pid);
+        }
+        DebugTarget::Launch(program) => {
+            cmd.args(program);
+        }
+    }
+
+    let status = cmd.status()?;
+    exit_with_status(status);
+}
+
+/// winedbg принимает pid в десятичном виде или с префиксом `0x` (как в `info process`).
+fn parse_windows_pid(pid: &str) -> Option<u32> {
+    match pid.strip_prefix("0x").or_else(|| pid.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => pid.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_windows_pid;
+
+    #[test]
+    fn parses_decimal_and_hex_pids() {
+        assert_eq!(parse_windows_pid("292"), Some(292));
+        assert_eq!(parse_windows_pid("0x0124"), Some(0x124));
+        assert_eq!(parse_windows_pid("game.exe"), None);
+    }
+}
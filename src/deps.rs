@@ -0,0 +1,243 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{fs, io, path::Path, process};
+
+use crate::{
+    cli::fail,
+    context::DEPS_FILE,
+    output::print_json,
+    verbosity,
+    wine::{PrefixContext, resolve_prefix},
+};
+
+/// Журнал winetricks в префиксе: по verb на строку.
+const WINETRICKS_LOG: &str = "winetricks.log";
+
+/// Понятное имя зависимости и verbs winetricks, которые её ставят.
+struct Dependency {
+    name: &'static str,
+    verbs: &'static [&'static str],
+    description: &'static str,
+}
+
+const DEPENDENCIES: &[Dependency] = &[
+    Dependency {
+        name: "corefonts",
+        verbs: &["corefonts"],
+        description: "шрифты Microsoft (Arial, Times New Roman, ...)",
+    },
+    Dependency {
+        name: "fonts",
+        verbs: &["corefonts", "tahoma", "lucida"],
+        description: "corefonts, Tahoma и Lucida — для лаунчеров и мод-менеджеров",
+    },
+    Dependency {
+        name: "vcrun2022",
+        verbs: &["vcrun2022"],
+        description: "Visual C++ 2015–2022",
+    },
+    Dependency {
+        name: "vcrun2013",
+        verbs: &["vcrun2013"],
+        description: "Visual C++ 2013",
+    },
+    Dependency {
+        name: "vcrun2010",
+        verbs: &["vcrun2010"],
+        description: "Visual C++ 2010",
+    },
+    Dependency {
+        name: "vcrun2008",
+        verbs: &["vcrun2008"],
+        description: "Visual C++ 2008",
+    },
+    Dependency {
+        name: "vcrun",
+        verbs: &["vcrun2008", "vcrun2010", "vcrun2013", "vcrun2022"],
+        description: "все распространённые Visual C++",
+    },
+    Dependency {
+        name: "dotnet48",
+        verbs: &["dotnet48"],
+        description: ".NET Framework 4.8",
+    },
+    Dependency {
+        name: "dotnet472",
+        verbs: &["dotnet472"],
+        description: ".NET Framework 4.7.2",
+    },
+    Dependency {
+        name: "dotnetdesktop6",
+        verbs: &["dotnetdesktop6"],
+        description: ".NET Desktop Runtime 6",
+    },
+    Dependency {
+        name: "dotnetdesktop8",
+        verbs: &["dotnetdesktop8"],
+        description: ".NET Desktop Runtime 8",
+    },
+    Dependency {
+        name: "d3dx9",
+        verbs: &["d3dx9"],
+        description: "D3DX9 из DirectX June 2010",
+    },
+    Dependency {
+        name: "d3dcompiler",
+        verbs: &["d3dcompiler_43", "d3dcompiler_47"],
+        description: "компиляторы шейдеров D3D",
+    },
+    Dependency {
+        name: "xact",
+        verbs: &["xact"],
+        description: "XACT (звук старых игр)",
+    },
+    Dependency {
+        name: "physx",
+        verbs: &["physx"],
+        description: "NVIDIA PhysX",
+    },
+];
+
+/// Ставит зависимости по понятным именам через winetricks в префикс игры; без имён —
+/// список доступных с отметкой установленных.
+pub fn handle_install_deps(
+    phd: &Path,
+    selector: &str,
+    names: &[String],
+    json_output: bool,
+) -> io::Result<()> {
+    let prefix = resolve_prefix(phd, selector)?;
+    let installed = installed_verbs(&prefix);
+    if names.is_empty() {
+        return list_dependencies(&installed, json_output);
+    }
+
+    let deps: Vec<&Dependency> = names.iter().map(|name| find_dependency(name)).collect();
+    let mut verbs: Vec<&str> = Vec::new();
+    for verb in deps.iter().flat_map(|dep| dep.verbs) {
+        if installed.iter().any(|done| done == verb) {
+            verbosity::info(format_args!("{verb} уже установлен, пропускаем"));
+        } else if !verbs.contains(verb) {
+            verbs.push(verb);
+        }
+    }
+    if !verbs.is_empty() {
+        run_winetricks(&prefix, &verbs)?;
+    }
+
+    match &prefix.app_dir {
+        Some(app_dir) => record_installed(app_dir, &deps)?,
+        None => verbosity::info(format_args!(
+            "Игра {} не запущена: установленное видно только в {WINETRICKS_LOG} префикса",
+            prefix.appid
+        )),
+    }
+    println!(
+        "{} installed: {}",
+        "OK".green().bold(),
+        deps.iter()
+            .map(|dep| dep.name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+fn find_dependency(name: &str) -> &'static Dependency {
+    DEPENDENCIES
+        .iter()
+        .find(|dep| dep.name.eq_ignore_ascii_case(name))
+        .unwrap_or_else(|| {
+            let known: Vec<&str> = DEPENDENCIES.iter().map(|dep| dep.name).collect();
+            fail(&format!(
+                "Неизвестная зависимость \"{name}\". Доступны: {}; другие verbs winetricks — через `protonhax pt`",
+                known.join(", ")
+            ))
+        })
+}
+
+fn list_dependencies(installed: &[String], json_output: bool) -> io::Result<()> {
+    let is_installed = |dep: &Dependency| {
+        dep.verbs
+            .iter()
+            .all(|verb| installed.iter().any(|done| done == verb))
+    };
+    if json_output {
+        let entries: Vec<_> = DEPENDENCIES
+            .iter()
+            .map(|dep| {
+                json!({
+                    "name": dep.name,
+                    "verbs": dep.verbs,
+                    "description": dep.description,
+                    "installed": is_installed(dep),
+                })
+            })
+            .collect();
+        return print_json(&entries);
+    }
+    for dep in DEPENDENCIES {
+        let mark = if is_installed(dep) {
+            "✓".green()
+        } else {
+            " ".normal()
+        };
+        println!(
+            "{mark} {:<16} {}",
+            dep.name.green(),
+            dep.description.dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Verbs, которые winetricks уже ставил в этот префикс.
+fn installed_verbs(prefix: &PrefixContext) -> Vec<String> {
+    fs::read_to_string(prefix.pfx.join(WINETRICKS_LOG))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn run_winetricks(prefix: &PrefixContext, verbs: &[&str]) -> io::Result<()> {
+    if prefix.wine_bin_dir().is_none() {
+        fail(&format!(
+            "В сборке Proton {} не найден wine",
+            prefix.proton_dir().display()
+        ));
+    }
+    let mut child = process::Command::new("winetricks");
+    child.arg("-q").args(verbs);
+    prefix.apply_wine_env(&mut child);
+    verbosity::verbose(format_args!("winetricks -q {}", verbs.join(" ")));
+    let status = child.status().map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            io::Error::new(err.kind(), "winetricks не найден в PATH")
+        } else {
+            err
+        }
+    })?;
+    if !status.success() {
+        fail(&format!("winetricks завершился с ошибкой ({status})"));
+    }
+    Ok(())
+}
+
+/// Дописывает имена в `deps` контекста, чтобы `info` показывал, что ставилось.
+fn record_installed(app_dir: &Path, deps: &[&Dependency]) -> io::Result<()> {
+    let path = app_dir.join(DEPS_FILE);
+    let mut recorded: Vec<String> = fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    for dep in deps {
+        if !recorded.iter().any(|name| name == dep.name) {
+            recorded.push(dep.name.to_string());
+        }
+    }
+    fs::write(path, recorded.join("\n") + "\n")
+}
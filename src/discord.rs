@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    process,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::verbosity;
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+const OP_CLOSE: u32 = 2;
+/// Заголовок кадра IPC: код операции и длина JSON, оба little-endian.
+const FRAME_HEADER_LEN: usize = 8;
+/// Сколько ждать ответа клиента Discord: зависший клиент не должен задерживать игру.
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+/// Номера сокетов `discord-ipc-N`, которые пробует официальный SDK.
+const IPC_SOCKETS: u32 = 10;
+/// Где лежат сокеты относительно `$XDG_RUNTIME_DIR`: обычный клиент, Flatpak, Snap.
+const IPC_SUBDIRS: &[&str] = &["", "app/com.discordapp.Discord", "snap.discord"];
+
+/// Статус «Играет в …» в Discord (`[discord]` в конфиге); без секции выключен.
+#[derive(Serialize, Deserialize)]
+pub struct DiscordConfig {
+    /// Application ID приложения из Discord Developer Portal: его название Discord
+    /// показывает как игру, а название из манифеста идёт в строку `details`.
+    pub client_id: String,
+}
+
+/// Соединение с клиентом Discord, через которое опубликован статус игры. Discord сам
+/// убирает статус, когда соединение закрывается.
+pub struct Presence {
+    stream: UnixStream,
+}
+
+impl Presence {
+    /// Публикует статус; ошибки только в отладочный вывод: Discord может быть не запущен.
+    pub fn publish(config: &DiscordConfig, name: &str, started_at: u64) -> Option<Self> {
+        let result = Self::connect(config).and_then(|mut presence| {
+            presence.set_activity(Some(&json!({
+                "details": name,
+                "timestamps": { "start": started_at },
+            })))?;
+            Ok(presence)
+        });
+        match result {
+            Ok(presence) => {
+                verbosity::verbose(format_args!("статус Discord: {name}"));
+                Some(presence)
+            }
+            Err(err) => {
+                verbosity::debug(format_args!("статус Discord не опубликован: {err}"));
+                None
+            }
+        }
+    }
+
+    /// `publish` в фоновом потоке: подключение и ответы Discord (до `IPC_TIMEOUT` на
+    /// каждый) не должны задерживать игру.
+    pub fn publish_in_background(
+        config: &DiscordConfig,
+        name: &str,
+        started_at: u64,
+    ) -> JoinHandle<Option<Self>> {
+        let config = DiscordConfig {
+            client_id: config.client_id.clone(),
+        };
+        let name = name.to_owned();
+        thread::spawn(move || Self::publish(&config, &name, started_at))
+    }
+
+    /// Явно убирает статус: `init` завершается через `process::exit`, и закрытия
+    /// сокета Discord может заметить не сразу.
+    pub fn clear(mut self) {
+        let _ = self.set_activity(None);
+    }
+
+    fn connect(config: &DiscordConfig) -> io::Result<Self> {
+        let stream = ipc_socket_paths()
+            .into_iter()
+            .find_map(|path| UnixStream::connect(path).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "сокет Discord не найден"))?;
+        stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+        stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+        let mut presence = Self { stream };
+        presence.request(
+            OP_HANDSHAKE,
+            &json!({ "v": 1, "client_id": config.client_id }),
+        )?;
+        Ok(presence)
+    }
+
+    fn set_activity(&mut self, activity: Option<&Value>) -> io::Result<()> {
+        self.request(
+            OP_FRAME,
+            &json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": process::id(), "activity": activity },
+                "nonce": format!("protonhax-{}", process::id()),
+            }),
+        )
+    }
+
+    /// Отправляет кадр и дожидается ответа; закрытие или `ERROR` от Discord — ошибка.
+    fn request(&mut self, op: u32, payload: &Value) -> io::Result<()> {
+        self.stream.write_all(&encode_frame(op, payload))?;
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        self.stream.read_exact(&mut header)?;
+        let (op, len) = decode_header(header);
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body)?;
+        let response: Value = serde_json::from_slice(&body).map_err(io::Error::other)?;
+        if op == OP_CLOSE || response["evt"] == "ERROR" {
+            let message = response["message"]
+                .as_str()
+                .or_else(|| response["data"]["message"].as_str())
+                .unwrap_or("отказ без описания");
+            return Err(io::Error::other(format!("Discord: {message}")));
+        }
+        Ok(())
+    }
+}
+
+fn ipc_socket_paths() -> Vec<PathBuf> {
+    let roots = ["XDG_RUNTIME_DIR", "TMPDIR"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(PathBuf::from)
+        .chain([PathBuf::from("/tmp")]);
+    let mut paths = Vec::new();
+    for root in roots {
+        for subdir in IPC_SUBDIRS {
+            let dir = root.join(subdir);
+            paths.extend((0..IPC_SOCKETS).map(|index| dir.join(format!("discord-ipc-{index}"))));
+        }
+    }
+    paths
+}
+
+fn encode_frame(op: u32, payload: &Value) -> Vec<u8> {
+    let body = payload.to_string();
+    let len = u32::try_from(body.len()).expect("IPC payload fits into u32");
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend(op.to_le_bytes());
+    frame.extend(len.to_le_bytes());
+    frame.extend(body.into_bytes());
+    frame
+}
+
+fn decode_header(header: [u8; FRAME_HEADER_LEN]) -> (u32, usize) {
+    let (op, len) = header.split_at(4);
+    let field = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+    (field(op), field(len) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FRAME_HEADER_LEN, OP_HANDSHAKE, decode_header, encode_frame};
+    use serde_json::json;
+
+    #[test]
+    fn frames_round_trip() {
+        let frame = encode_frame(OP_HANDSHAKE, &json!({ "v": 1 }));
+        let header: [u8; FRAME_HEADER_LEN] = frame[..FRAME_HEADER_LEN].try_into().unwrap();
+        assert_eq!(decode_header(header), (OP_HANDSHAKE, 7));
+        assert_eq!(&frame[FRAME_HEADER_LEN..], br#"{"v":1}"#);
+    }
+}
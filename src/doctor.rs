@@ -0,0 +1,548 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    context::{
+        EXE_FILE, PFX_FILE, RunningApp, STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV,
+        collect_running_apps, read_trimmed,
+    },
+    env_store::{ENV_FILE, get_env_var, permission_problem},
+    launch_options::{INIT_LAUNCH_OPTION, LaunchSetup, proton_games},
+    output::print_json,
+    proton::{find_proton, installed_protons},
+    runtime::{data_home, find_in_path, format_duration_ago},
+    steam::steamapps_dirs,
+};
+
+/// Семейство дистрибутива для подсказок по установке пакетов.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Distro {
+    Arch,
+    Debian,
+    Fedora,
+    Suse,
+    Other,
+}
+
+impl Distro {
+    /// Определяет семейство по `ID` и `ID_LIKE` из `/etc/os-release`.
+    fn from_os_release(content: &str) -> Self {
+        let ids: Vec<&str> = content
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("ID=")
+                    .or_else(|| line.strip_prefix("ID_LIKE="))
+            })
+            .flat_map(|value| value.trim_matches('"').split_whitespace())
+            .collect();
+        let has = |names: &[&str]| ids.iter().any(|id| names.contains(id));
+        if has(&["arch", "manjaro", "endeavouros", "steamos", "cachyos"]) {
+            Self::Arch
+        } else if has(&["debian", "ubuntu"]) {
+            Self::Debian
+        } else if has(&["fedora", "rhel", "nobara", "bazzite"]) {
+            Self::Fedora
+        } else if has(&["suse", "opensuse"]) {
+            Self::Suse
+        } else {
+            Self::Other
+        }
+    }
+
+    fn install_hint(self, package: &str) -> String {
+        match self {
+            Self::Arch => format!("sudo pacman -S {package}"),
+            Self::Debian => format!("sudo apt install {package}"),
+            Self::Fedora => format!("sudo dnf install {package}"),
+            Self::Suse => format!("sudo zypper install {package}"),
+            Self::Other => format!("установите пакет {package}"),
+        }
+    }
+}
+
+/// Внешняя программа, которую используют подкоманды protonhax.
+struct Dependency {
+    binary: &'static str,
+    package: &'static str,
+    /// Аргументы для печати версии; `None`, если программа её не сообщает.
+    version_args: Option<&'static [&'static str]>,
+    /// Отсутствие — предупреждение, а не просто информация.
+    required: bool,
+    purpose: &'static str,
+}
+
+const DEPENDENCIES: &[Dependency] = &[
+    Dependency {
+        binary: "winetricks",
+        package: "winetricks",
+        version_args: Some(&["--version"]),
+        required: true,
+        purpose: "pt",
+    },
+    Dependency {
+        binary: "cabextract",
+        package: "cabextract",
+        version_args: Some(&["--version"]),
+        required: true,
+        purpose: "нужен winetricks для большинства verbs",
+    },
+    Dependency {
+        binary: "protontricks",
+        package: "protontricks",
+        version_args: Some(&["--version"]),
+        required: false,
+        purpose: "альтернатива pt",
+    },
+    Dependency {
+        binary: "vulkaninfo",
+        package: "vulkan-tools",
+        version_args: None,
+        required: false,
+        purpose: "диагностика Vulkan",
+    },
+    Dependency {
+        binary: "gamemoded",
+        package: "gamemode",
+        version_args: Some(&["--version"]),
+        required: false,
+        purpose: "gamemoderun",
+    },
+    Dependency {
+        binary: "mangohud",
+        package: "mangohud",
+        version_args: None,
+        required: false,
+        purpose: "оверлей MangoHud",
+    },
+];
+
+/// Каталоги манифестов Vulkan ICD (драйверов).
+const VULKAN_ICD_DIRS: &[&str] = &[
+    "/usr/share/vulkan/icd.d",
+    "/etc/vulkan/icd.d",
+    "/usr/local/share/vulkan/icd.d",
+];
+/// Системные каталоги implicit-слоёв Vulkan; пользовательский — в `$XDG_DATA_HOME`.
+const VULKAN_LAYER_DIRS: &[&str] = &[
+    "/usr/share/vulkan/implicit_layer.d",
+    "/etc/vulkan/implicit_layer.d",
+    "/usr/local/share/vulkan/implicit_layer.d",
+];
+const LAUNCH_CLIENT: &str = "steam-runtime-launch-client";
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Ok,
+    Info,
+    Warn,
+    Err,
+}
+
+#[derive(Serialize)]
+struct Check {
+    section: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    level: Level,
+    message: String,
+}
+
+/// Результаты проверок; печатаются разом, чтобы тот же отчёт можно было отдать в JSON.
+#[derive(Default, Serialize)]
+pub struct Report {
+    checks: Vec<Check>,
+    warnings: usize,
+    pub errors: usize,
+    #[serde(skip)]
+    section: &'static str,
+    #[serde(skip)]
+    context: Option<String>,
+}
+
+impl Report {
+    pub fn section(&mut self, section: &'static str) {
+        self.section = section;
+        self.context = None;
+    }
+
+    fn push(&mut self, level: Level, message: impl Into<String>) {
+        match level {
+            Level::Warn => self.warnings += 1,
+            Level::Err => self.errors += 1,
+            Level::Ok | Level::Info => {}
+        }
+        self.checks.push(Check {
+            section: self.section,
+            context: self.context.clone(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    pub fn ok(&mut self, message: impl Into<String>) {
+        self.push(Level::Ok, message);
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message);
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.push(Level::Warn, message);
+    }
+
+    pub fn err(&mut self, message: impl Into<String>) {
+        self.push(Level::Err, message);
+    }
+
+    pub fn print(&self, title: &str) {
+        println!("{}", title.bold());
+
+        let mut section = "";
+        let mut context: Option<&str> = None;
+        for check in &self.checks {
+            if check.section != section {
+                section = check.section;
+                context = None;
+                println!("\n{section}:");
+            }
+            if check.context.is_some() && check.context.as_deref() != context {
+                context = check.context.as_deref();
+                println!("  {} {}", "•".cyan().bold(), context.unwrap_or_default());
+            }
+
+            let label = match check.level {
+                Level::Ok => "OK".green().bold(),
+                Level::Info => "INFO".cyan().bold(),
+                Level::Warn => "WARN".yellow().bold(),
+                Level::Err => "ERR".red().bold(),
+            };
+            println!("    {label} {}", check.message);
+        }
+
+        println!(
+            "\nSummary: {} warning(s), {} error(s)",
+            self.warnings.to_string().yellow(),
+            self.errors.to_string().red()
+        );
+    }
+}
+
+pub fn handle_doctor(phd: &Path, json_output: bool) -> io::Result<()> {
+    let mut report = Report::default();
+
+    report.section("Environment");
+    if let Ok(steam_app_id) = env::var(STEAM_APP_ID_ENV) {
+        report.ok(format!("{STEAM_APP_ID_ENV}={steam_app_id}"));
+    } else {
+        report.info("SteamAppId не установлен (это нормально вне запуска через Steam)");
+    }
+
+    match env::var(STEAM_COMPAT_DATA_PATH_ENV) {
+        Ok(path) => {
+            if Path::new(&path).exists() {
+                report.ok(format!("{STEAM_COMPAT_DATA_PATH_ENV}={path}"));
+            } else {
+                report.warn(format!(
+                    "{STEAM_COMPAT_DATA_PATH_ENV} установлен, но путь не найден: {path}"
+                ));
+            }
+        }
+        Err(_) => {
+            report.info("STEAM_COMPAT_DATA_PATH не установлен (это нормально вне запуска игры)");
+        }
+    }
+
+    report.section("Runtime");
+    if phd.exists() {
+        report.ok(format!("runtime root: {}", phd.display()));
+    } else {
+        report.warn(format!(
+            "runtime root отсутствует: {} (ещё не было активных контекстов)",
+            phd.display()
+        ));
+    }
+
+    report.section("Dependencies");
+    inspect_dependencies(&mut report);
+
+    report.section("Launch options");
+    inspect_launch_options(&mut report);
+
+    report.section("Contexts");
+    let apps = collect_running_apps(phd, true)?;
+    if apps.is_empty() {
+        report.warn("активных контекстов не найдено");
+    }
+
+    for app in &apps {
+        inspect_context(app, &mut report);
+    }
+
+    if json_output {
+        print_json(&report)?;
+    } else {
+        report.print("protonhax doctor");
+    }
+
+    if report.errors > 0 {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Какие игры на Proton передают запуск protonhax, а какие — нет.
+fn inspect_launch_options(report: &mut Report) {
+    let games = match proton_games() {
+        Ok(Some(games)) => games,
+        Ok(None) => {
+            report.info("localconfig.vdf не найден: Steam на этой машине ещё не входил");
+            return;
+        }
+        Err(err) => {
+            report.warn(format!("параметры запуска не прочитаны: {err}"));
+            return;
+        }
+    };
+    if games.is_empty() {
+        report.info("игр, запускавшихся через Proton, не найдено");
+        return;
+    }
+    let mut missing = Vec::new();
+    for game in &games {
+        match game.setup {
+            LaunchSetup::Installed => report.ok(game.label()),
+            LaunchSetup::Legacy => report.warn(format!(
+                "{}: protonhax вызывается по-старому ({}), нужно «{INIT_LAUNCH_OPTION}» (protonhax setup --migrate {})",
+                game.label(),
+                game.options,
+                game.appid
+            )),
+            LaunchSetup::Missing => missing.push(game.label()),
+        }
+    }
+    if missing.len() == games.len() {
+        report.warn(format!(
+            "ни в одной игре нет «{INIT_LAUNCH_OPTION}» в параметрах запуска: без него у игры не будет контекста (protonhax setup --interactive)"
+        ));
+    }
+    if !missing.is_empty() {
+        report.info(format!(
+            "без protonhax ({}): {}",
+            missing.len(),
+            missing.join(", ")
+        ));
+    }
+}
+
+fn inspect_dependencies(report: &mut Report) {
+    let distro =
+        Distro::from_os_release(&fs::read_to_string("/etc/os-release").unwrap_or_default());
+
+    for dep in DEPENDENCIES {
+        let Some(path) = find_in_path(dep.binary) else {
+            let message = format!(
+                "{} не найден ({}) — {}",
+                dep.binary,
+                dep.purpose,
+                distro.install_hint(dep.package)
+            );
+            if dep.required {
+                report.warn(message);
+            } else {
+                report.info(message);
+            }
+            continue;
+        };
+        let version = dep
+            .version_args
+            .and_then(|args| program_version(&path, args))
+            .map(|version| format!(" ({version})"))
+            .unwrap_or_default();
+        report.ok(format!("{}{version}: {}", dep.binary, path.display()));
+    }
+
+    let icds: Vec<String> = VULKAN_ICD_DIRS
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        })
+        .collect();
+    if icds.is_empty() {
+        report.warn(format!(
+            "Vulkan ICD не найдены в {} — установите Vulkan-драйвер (mesa-vulkan-drivers / vulkan-radeon / nvidia-utils)",
+            VULKAN_ICD_DIRS.join(", ")
+        ));
+    } else {
+        report.ok(format!("Vulkan ICD: {}", icds.join(", ")));
+    }
+
+    inspect_vkbasalt(report, distro);
+
+    let protons = installed_protons();
+    if protons.is_empty() {
+        report.warn("сборки Proton не найдены — установите Proton в Steam или GE-Proton в compatibilitytools.d");
+    } else {
+        let default = find_proton(None)
+            .map(|build| format!(", по умолчанию {}", build.name))
+            .unwrap_or_default();
+        report.ok(format!("сборок Proton: {}{default}", protons.len()));
+    }
+
+    match find_launch_client() {
+        Some(path) => report.ok(format!("{LAUNCH_CLIENT}: {}", path.display())),
+        None => report.info(format!(
+            "{LAUNCH_CLIENT} не найден — установите «Steam Linux Runtime 3.0 (sniper)» в Steam"
+        )),
+    }
+}
+
+/// Слой vkBasalt для `run --vkbasalt`: без манифеста переменные из флага ничего не включат.
+fn inspect_vkbasalt(report: &mut Report, distro: Distro) {
+    let user_dir = data_home().join("vulkan/implicit_layer.d");
+    let manifest = VULKAN_LAYER_DIRS
+        .iter()
+        .map(PathBuf::from)
+        .chain([user_dir])
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .find(|path| {
+            let is_json = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+            let is_vkbasalt = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("vkBasalt"));
+            is_json && is_vkbasalt
+        });
+    match manifest {
+        Some(path) => report.ok(format!("слой vkBasalt: {}", path.display())),
+        None => report.info(format!(
+            "слой vkBasalt не найден (нужен для run --vkbasalt) — {}",
+            distro.install_hint("vkbasalt")
+        )),
+    }
+}
+
+/// `steam-runtime-launch-client` обычно лежит не в `$PATH`, а внутри Steam Linux Runtime.
+fn find_launch_client() -> Option<PathBuf> {
+    find_in_path(LAUNCH_CLIENT).or_else(|| {
+        steamapps_dirs().into_iter().find_map(|steamapps| {
+            let path = steamapps
+                .join("common/SteamLinuxRuntime_sniper/pressure-vessel/bin")
+                .join(LAUNCH_CLIENT);
+            path.is_file().then_some(path)
+        })
+    })
+}
+
+/// Первая непустая строка вывода `<program> <args>` (часть программ пишет версию в stderr).
+fn program_version(program: &Path, args: &[&str]) -> Option<String> {
+    let output = process::Command::new(program)
+        .args(args)
+        .stdin(process::Stdio::null())
+        .output()
+        .ok()?;
+    [&output.stdout, &output.stderr]
+        .into_iter()
+        .find_map(|stream| {
+            String::from_utf8_lossy(stream)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string)
+        })
+}
+
+fn inspect_context(app: &RunningApp, report: &mut Report) {
+    report.context = Some(match app.name.as_deref() {
+        Some(name) => format!("{} ({name})", app.id),
+        None => app.id.clone(),
+    });
+
+    if let Ok(exe) = read_trimmed(app.path.join(EXE_FILE)) {
+        if Path::new(&exe).exists() {
+            report.ok(format!("exe: {exe}"));
+        } else {
+            report.err(format!("exe путь не существует: {exe}"));
+        }
+    } else {
+        report.err("файл exe отсутствует или не читается");
+    }
+
+    if let Ok(pfx) = read_trimmed(app.path.join(PFX_FILE)) {
+        if Path::new(&pfx).exists() {
+            report.ok(format!("pfx: {pfx}"));
+        } else {
+            report.warn(format!("pfx путь не существует: {pfx}"));
+        }
+    } else {
+        report.warn("файл pfx отсутствует или не читается");
+    }
+
+    let loose = [(app.path.clone(), 0o700), (app.path.join(ENV_FILE), 0o600)]
+        .into_iter()
+        .filter_map(|(path, mode)| permission_problem(&path, mode));
+    for problem in loose {
+        report.warn(problem);
+    }
+
+    if let Ok(env_content) = fs::read_to_string(app.path.join(ENV_FILE)) {
+        report.ok("env: файл окружения прочитан");
+        match get_env_var(&env_content, STEAM_COMPAT_DATA_PATH_ENV) {
+            Some(compat_data) if Path::new(&compat_data).exists() => {
+                report.ok(format!("env.STEAM_COMPAT_DATA_PATH: {compat_data}"));
+            }
+            Some(compat_data) => {
+                report.warn(format!(
+                    "env.STEAM_COMPAT_DATA_PATH указывает на отсутствующий путь: {compat_data}"
+                ));
+            }
+            None => {
+                report.warn("env: отсутствует STEAM_COMPAT_DATA_PATH");
+            }
+        }
+    } else {
+        report.err("файл env отсутствует или не читается");
+    }
+
+    if let Some(started_at) = app.started_at {
+        report.ok(format!(
+            "started_at: {started_at} ({})",
+            format_duration_ago(started_at)
+        ));
+    } else {
+        report.warn("started_at отсутствует или повреждён");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Distro;
+
+    #[test]
+    fn detects_distro_family() {
+        assert_eq!(
+            Distro::from_os_release(
+                "NAME=\"Linux Mint\"\nID=linuxmint\nID_LIKE=\"ubuntu debian\"\n"
+            ),
+            Distro::Debian
+        );
+        assert_eq!(
+            Distro::from_os_release("ID=steamos\nID_LIKE=arch\n"),
+            Distro::Arch
+        );
+        assert_eq!(Distro::from_os_release("ID=nixos\n"), Distro::Other);
+    }
+}
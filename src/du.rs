@@ -0,0 +1,98 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    fs, io,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    output::print_json,
+    runtime::format_size,
+    steam::{find_app_meta, find_shader_cache},
+    wine::locate_prefix,
+};
+
+struct UsageRow {
+    label: &'static str,
+    path: Option<PathBuf>,
+    bytes: Option<u64>,
+}
+
+pub fn handle_du(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let compat_data = prefix.compat_data().to_path_buf();
+    let drive_c = prefix.pfx.join("drive_c");
+    let install_dir = find_app_meta(&prefix.appid).install_path.map(PathBuf::from);
+
+    let rows = [
+        ("compatdata", Some(compat_data)),
+        ("drive_c", Some(drive_c.clone())),
+        ("users", Some(drive_c.join("users"))),
+        ("windows", Some(drive_c.join("windows"))),
+        ("shadercache", find_shader_cache(&prefix.appid)),
+        ("install", install_dir),
+    ]
+    .map(|(label, path)| {
+        let bytes = path.as_deref().and_then(|path| disk_usage(path).ok());
+        UsageRow { label, path, bytes }
+    });
+
+    if json_output {
+        return print_du_json(&prefix.appid, &rows);
+    }
+
+    for row in &rows {
+        let size = row.bytes.map_or_else(|| "-".to_string(), format_size);
+        let path = row
+            .path
+            .as_deref()
+            .map_or_else(String::new, |path| path.display().to_string());
+        println!(
+            "{:<12} {:>10}  {}",
+            row.label.green(),
+            size.yellow(),
+            path.dimmed()
+        );
+    }
+    Ok(())
+}
+
+fn print_du_json(appid: &str, rows: &[UsageRow]) -> io::Result<()> {
+    let entries: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "name": row.label,
+                "path": row.path.as_deref().map(Path::to_string_lossy),
+                "bytes": row.bytes,
+            })
+        })
+        .collect();
+
+    print_json(&json!({ "appid": appid, "entries": entries }))
+}
+
+/// Занятое место на диске (как `du`): блоки, жёсткие ссылки считаются один раз,
+/// по симлинкам не переходим (dosdevices указывают на `/`).
+pub fn disk_usage(path: &Path) -> io::Result<u64> {
+    let mut seen = HashSet::new();
+    let mut total = 0;
+    let mut stack = vec![path.to_path_buf()];
+
+    while let Some(path) = stack.pop() {
+        let meta = fs::symlink_metadata(&path)?;
+        if meta.nlink() > 1 && !seen.insert((meta.dev(), meta.ino())) {
+            continue;
+        }
+        total += meta.blocks() * 512;
+
+        if meta.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                stack.push(entry?.path());
+            }
+        }
+    }
+    Ok(total)
+}
@@ -1,15 +1,97 @@
-use std::{env, fs, io, path::Path};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt},
+    path::Path,
+};
 
-use crate::shell::{is_env_name, un_shell_escape};
+use crate::{
+    cli::fail,
+    runtime::current_uid,
+    shell::{is_env_name, shell_escape, un_shell_escape, validate_shell_value},
+    verbosity,
+};
 
 pub const ENV_FILE: &str = "env";
+/// Имена переменных (шаблоны с `*`, без учёта регистра), которые не сохраняются в контекст:
+/// токены, пароли, сессионные ключи и логин Steam.
+const DEFAULT_DENY_PATTERNS: &[&str] = &[
+    "*TOKEN*",
+    "*SECRET*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*API_KEY*",
+    "*AUTH*",
+    "*COOKIE*",
+    "*CREDENTIAL*",
+    "*SESSION*",
+    "SteamUser",
+];
+/// Исключения из списка выше, без которых не запускаются графические программы.
+const DEFAULT_ALLOW_PATTERNS: &[&str] = &[
+    "XAUTHORITY",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "XDG_SESSION_*",
+    "DESKTOP_SESSION",
+    "SESSION_MANAGER",
+];
+const REDACTED: &str = "<redacted>";
 
 pub fn load_env<P: AsRef<Path>>(app_dir: P) -> Result<(), io::Error> {
-    let env_content = fs::read_to_string(app_dir.as_ref().join(ENV_FILE))?;
-    apply_env_content(&env_content);
+    let env_path = app_dir.as_ref().join(ENV_FILE);
+    for path in [app_dir.as_ref(), env_path.as_path()] {
+        let meta = fs::metadata(path)?;
+        if let Some(reason) = untrusted_reason(meta.uid(), meta.mode(), current_uid()) {
+            fail(&format!(
+                "{} {reason}: окружение из него не загружается",
+                path.display()
+            ));
+        }
+    }
+    let env_content = fs::read_to_string(&env_path)?;
+    let applied = apply_env_content(&env_content);
+    verbosity::verbose(format_args!(
+        "окружение: {applied} переменных из {}",
+        env_path.display()
+    ));
     Ok(())
 }
 
+/// Почему окружению контекста нельзя доверять: оно попадает в запускаемые программы
+/// (вплоть до `LD_PRELOAD`), поэтому менять его может только владелец.
+fn untrusted_reason(owner: u32, mode: u32, uid: u32) -> Option<String> {
+    if owner != uid {
+        Some(format!("принадлежит другому пользователю (uid {owner})"))
+    } else if mode & 0o022 != 0 {
+        Some(format!(
+            "доступен на запись группе или всем (права {:o})",
+            mode & 0o777
+        ))
+    } else {
+        None
+    }
+}
+
+/// Замечание `doctor` к правам каталога контекста или файла env: снимок окружения
+/// содержит переменные аккаунта, читать его может только владелец.
+pub fn permission_problem(path: &Path, expected_mode: u32) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    if let Some(reason) = untrusted_reason(meta.uid(), meta.mode(), current_uid()) {
+        return Some(format!("{} {reason}", path.display()));
+    }
+    let mode = meta.mode() & 0o777;
+    (mode & 0o077 != 0).then(|| {
+        format!(
+            "{} доступен другим (права {mode:o}): chmod {expected_mode:o} {}",
+            path.display(),
+            path.display()
+        )
+    })
+}
+
 pub fn get_env_var(env_content: &str, key: &str) -> Option<String> {
     for line in env_content.lines() {
         if let Some((name, value_str)) = parse_export_line(line)
@@ -21,6 +103,48 @@ pub fn get_env_var(env_content: &str, key: &str) -> Option<String> {
     None
 }
 
+/// Все переменные сохранённого окружения.
+pub fn parse_env_content(env_content: &str) -> BTreeMap<String, String> {
+    env_content
+        .lines()
+        .filter_map(parse_export_line)
+        .map(|(name, value_str)| (name.to_string(), un_shell_escape(value_str)))
+        .collect()
+}
+
+/// Записывает (или заменяет) переменную в сохранённом окружении контекста.
+pub fn set_env_file_var<P: AsRef<Path>>(app_dir: P, key: &str, value: &str) -> io::Result<()> {
+    let env_content = fs::read_to_string(app_dir.as_ref().join(ENV_FILE))?;
+    replace_env_file(app_dir.as_ref(), &replace_env_var(&env_content, key, value))
+}
+
+/// Атомарно заменяет файл окружения контекста.
+pub fn replace_env_file(app_dir: &Path, content: &str) -> io::Result<()> {
+    let env_path = app_dir.join(ENV_FILE);
+    let tmp_path = env_path.with_extension("tmp");
+    write_private(&tmp_path, content)?;
+    fs::rename(tmp_path, env_path)
+}
+
+/// Проверяет, что каждая непустая строка — `declare -x NAME=value` с корректным
+/// экранированием; в ошибке номер строки с единицы. Строки `#` — комментарии.
+pub fn validate_env_content(env_content: &str) -> Result<(), String> {
+    for (idx, line) in env_content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((_, value)) = parse_export_line(line) else {
+            return Err(format!(
+                "строка {}: ожидается `declare -x NAME=value`",
+                idx + 1
+            ));
+        };
+        validate_shell_value(value).map_err(|err| format!("строка {}: {err}", idx + 1))?;
+    }
+    Ok(())
+}
+
 pub fn set_env_var(name: &str, value: &str) {
     // SAFETY: the CLI is single-threaded and mutates the process environment
     // only during command setup, before waiting on child processes.
@@ -29,12 +153,155 @@ pub fn set_env_var(name: &str, value: &str) {
     }
 }
 
-fn apply_env_content(env_content: &str) {
+/// Пользовательские шаблоны `[env]` из конфига; важнее встроенных списков.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+impl EnvFilter {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Похоже ли имя переменной на секрет: пользовательский allow, затем deny,
+    /// затем встроенные списки.
+    pub fn is_secret(&self, name: &str) -> bool {
+        if matches_any(&self.allow, name) {
+            return false;
+        }
+        if matches_any(&self.deny, name) {
+            return true;
+        }
+        !matches_any(DEFAULT_ALLOW_PATTERNS, name) && matches_any(DEFAULT_DENY_PATTERNS, name)
+    }
+}
+
+/// Сохраняет окружение процесса без секретов в файл, доступный только владельцу.
+pub fn write_env_file(app_dir: &Path, filter: &EnvFilter) -> io::Result<()> {
+    write_env_vars(app_dir, env::vars(), filter)
+}
+
+/// Как [`write_env_file`], но для переменных другого процесса (`reinit`).
+pub fn write_env_vars<I>(app_dir: &Path, vars: I, filter: &EnvFilter) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut vars: Vec<_> = vars
+        .into_iter()
+        .filter(|(key, _)| is_env_name(key) && !filter.is_secret(key))
+        .collect();
+    vars.sort_unstable_by(|left, right| left.0.cmp(&right.0));
+
+    let mut content = String::new();
+    for (key, value) in vars {
+        content.push_str("declare -x ");
+        content.push_str(&key);
+        content.push('=');
+        content.push_str(&shell_escape(&value));
+        content.push('\n');
+    }
+    write_private(&app_dir.join(ENV_FILE), &content)
+}
+
+/// Копия сохранённого окружения, в которой значения секретов заменены заглушкой.
+pub fn redact_env_content(env_content: &str, filter: &EnvFilter) -> String {
+    let mut res = String::with_capacity(env_content.len());
+    for line in env_content.lines() {
+        match parse_export_line(line) {
+            Some((name, _)) if filter.is_secret(name) => {
+                res.push_str("declare -x ");
+                res.push_str(name);
+                res.push('=');
+                res.push_str(REDACTED);
+            }
+            _ => res.push_str(line),
+        }
+        res.push('\n');
+    }
+    res
+}
+
+/// Пишет файл с правами 0600, в том числе если он уже существовал с более широкими.
+pub fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(content.as_bytes())
+}
+
+fn matches_any<S: AsRef<str>>(patterns: &[S], name: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| wildcard_match(pattern.as_ref(), name))
+}
+
+/// Сопоставление с шаблоном, где `*` — любая последовательность символов; без учёта регистра.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_uppercase();
+    let text = text.to_ascii_uppercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        let Some(idx) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[idx + part.len()..];
+    }
+    rest.ends_with(last)
+}
+
+fn apply_env_content(env_content: &str) -> usize {
+    let mut applied = 0;
     for line in env_content.lines() {
         if let Some((name, value_str)) = parse_export_line(line) {
-            set_env_var(name, &un_shell_escape(value_str));
+            let value = un_shell_escape(value_str);
+            verbosity::debug(format_args!("  {name}={value}"));
+            set_env_var(name, &value);
+            applied += 1;
         }
     }
+    applied
+}
+
+fn replace_env_var(env_content: &str, key: &str, value: &str) -> String {
+    let new_line = format!("declare -x {key}={}", shell_escape(value));
+    let mut replaced = false;
+    let mut res = String::with_capacity(env_content.len() + new_line.len() + 1);
+
+    for line in env_content.lines() {
+        if parse_export_line(line).is_some_and(|(name, _)| name == key) {
+            if !replaced {
+                res.push_str(&new_line);
+                res.push('\n');
+                replaced = true;
+            }
+            continue;
+        }
+        res.push_str(line);
+        res.push('\n');
+    }
+
+    if !replaced {
+        res.push_str(&new_line);
+        res.push('\n');
+    }
+    res
 }
 
 fn parse_export_line(line: &str) -> Option<(&str, &str)> {
@@ -47,7 +314,19 @@ fn parse_export_line(line: &str) -> Option<(&str, &str)> {
 
 #[cfg(test)]
 mod tests {
-    use super::{get_env_var, parse_export_line};
+    use super::{
+        EnvFilter, get_env_var, parse_export_line, redact_env_content, replace_env_var,
+        untrusted_reason, validate_env_content, wildcard_match,
+    };
+
+    #[test]
+    fn distrusts_foreign_or_shared_writable_env() {
+        assert_eq!(untrusted_reason(1000, 0o100_600, 1000), None);
+        assert_eq!(untrusted_reason(1000, 0o40_755, 1000), None);
+        assert!(untrusted_reason(0, 0o100_600, 1000).is_some());
+        assert!(untrusted_reason(1000, 0o100_620, 1000).is_some());
+        assert!(untrusted_reason(1000, 0o40_777, 1000).is_some());
+    }
 
     #[test]
     fn parses_export_line_with_valid_name() {
@@ -67,4 +346,64 @@ mod tests {
         let env_content = r#"declare -x KEY="a b\$c""#;
         assert_eq!(get_env_var(env_content, "KEY").as_deref(), Some("a b$c"));
     }
+
+    #[test]
+    fn replaces_or_appends_env_var() {
+        let env_content = "declare -x A=1\ndeclare -x B=2\n";
+        assert_eq!(
+            replace_env_var(env_content, "B", "x y"),
+            "declare -x A=1\ndeclare -x B=\"x y\"\n"
+        );
+        assert_eq!(
+            replace_env_var(env_content, "C", "3"),
+            "declare -x A=1\ndeclare -x B=2\ndeclare -x C=3\n"
+        );
+    }
+
+    #[test]
+    fn redacts_secret_values() {
+        let env_content =
+            "declare -x HOME=/home/u\ndeclare -x SteamUser=alice\ndeclare -x GH_TOKEN=abc\n";
+        assert_eq!(
+            redact_env_content(env_content, &EnvFilter::default()),
+            "declare -x HOME=/home/u\ndeclare -x SteamUser=<redacted>\ndeclare -x GH_TOKEN=<redacted>\n"
+        );
+    }
+
+    #[test]
+    fn user_patterns_override_defaults() {
+        let filter = EnvFilter {
+            allow: vec!["MY_TOKEN".to_string()],
+            deny: vec!["XDG_SESSION_ID".to_string(), "private_*".to_string()],
+        };
+        assert!(!filter.is_secret("MY_TOKEN"));
+        assert!(filter.is_secret("STEAM_TOKEN"));
+        assert!(filter.is_secret("XDG_SESSION_ID"));
+        assert!(!filter.is_secret("XDG_SESSION_TYPE"));
+        assert!(!filter.is_secret("DBUS_SESSION_BUS_ADDRESS"));
+        assert!(filter.is_secret("PRIVATE_KEY"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(wildcard_match("*token*", "GITHUB_TOKEN"));
+        assert!(wildcard_match("a*b*c", "AxxBxxC"));
+        assert!(!wildcard_match("a*b*c", "AxxC"));
+        assert!(!wildcard_match("XDG_SESSION_*", "XDG_SESSION"));
+        assert!(wildcard_match("HOME", "home"));
+    }
+
+    #[test]
+    fn validates_env_lines() {
+        assert_eq!(
+            validate_env_content("# comment\n\ndeclare -x A=\"a b\"\n"),
+            Ok(())
+        );
+        assert!(
+            validate_env_content("declare -x A=1\nexport B=2\n")
+                .unwrap_err()
+                .starts_with("строка 2:")
+        );
+        assert!(validate_env_content("declare -x A=a b\n").is_err());
+    }
 }
@@ -0,0 +1,376 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process,
+};
+
+use crate::{
+    cli::{EnvCommands, fail},
+    config::Config,
+    context::{prepare_context, resolve_target_app},
+    env_store::{
+        ENV_FILE, parse_env_content, replace_env_file, validate_env_content, write_private,
+    },
+    output::print_json,
+    shell::{fish_quote, is_env_name, sh_quote},
+    verbosity,
+};
+
+/// Переменные самой оболочки и терминала: из окружения Steam в сессию их не переносим.
+const SHELL_SESSION_VARS: &[&str] = &[
+    "PWD",
+    "OLDPWD",
+    "SHLVL",
+    "_",
+    "SHELL",
+    "TERM",
+    "COLORTERM",
+    "TERM_PROGRAM",
+    "PS1",
+    "HISTFILE",
+];
+/// direnv выставляет её, пока выполняет `.envrc` в bash, какой бы ни была `$SHELL`.
+const DIRENV_ENVRC_ENV: &str = "DIRENV_IN_ENVRC";
+
+/// Синтаксис `env source`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnvShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl EnvShell {
+    /// По `$SHELL`; внутри `.envrc` — всегда bash.
+    fn detect() -> Self {
+        if env::var_os(DIRENV_ENVRC_ENV).is_some() {
+            return Self::Bash;
+        }
+        let shell = env::var("SHELL").unwrap_or_default();
+        match Path::new(&shell).file_name().and_then(|name| name.to_str()) {
+            Some("zsh") => Self::Zsh,
+            Some("fish") => Self::Fish,
+            _ => Self::Bash,
+        }
+    }
+
+    /// Переменные, которые оболочка не даст присвоить или понимает по-своему: в zsh
+    /// строчные `path`/`fpath` связаны с `PATH`/`FPATH`.
+    fn reserved(self) -> &'static [&'static str] {
+        match self {
+            Self::Bash => &[
+                "BASHOPTS",
+                "BASH_VERSINFO",
+                "EUID",
+                "PPID",
+                "SHELLOPTS",
+                "UID",
+            ],
+            Self::Zsh => &[
+                "EGID",
+                "EUID",
+                "GID",
+                "PPID",
+                "UID",
+                "ZSH_PATCHLEVEL",
+                "ZSH_VERSION",
+                "cdpath",
+                "fpath",
+                "mailpath",
+                "manpath",
+                "module_path",
+                "path",
+                "pipestatus",
+                "psvar",
+                "status",
+            ],
+            Self::Fish => &[
+                "FISH_VERSION",
+                "fish_kill_signal",
+                "fish_pid",
+                "history",
+                "hostname",
+                "pipestatus",
+                "status",
+                "status_generation",
+                "umask",
+                "version",
+            ],
+        }
+    }
+
+    /// `None` — переменную в этой оболочке не присвоить (в том числе экспортированные
+    /// функции bash вроде `BASH_FUNC_x%%`).
+    fn assignment(self, name: &str, value: &str) -> Option<String> {
+        if !is_env_name(name) || self.reserved().contains(&name) {
+            return None;
+        }
+        Some(match self {
+            Self::Bash | Self::Zsh => format!("export {name}={}", sh_quote(value)),
+            // Переменные `*PATH` в fish — списки: каждый каталог отдельным словом.
+            Self::Fish if name.ends_with("PATH") && !value.is_empty() => {
+                let items: Vec<String> = value.split(':').map(fish_quote).collect();
+                format!("set -gx {name} {}", items.join(" "))
+            }
+            Self::Fish => format!("set -gx {name} {}", fish_quote(value)),
+        })
+    }
+}
+
+pub fn handle_env(phd: &Path, command: EnvCommands, json_output: bool) -> io::Result<()> {
+    match command {
+        EnvCommands::Diff {
+            appid,
+            other,
+            current: _,
+        } => {
+            let saved = saved_env(phd, &appid)?;
+            let base = match other {
+                Some(other) => saved_env(phd, &other)?,
+                None => current_env()?,
+            };
+            let diff = EnvDiff::between(&base, &saved);
+            if json_output {
+                print_json(&diff)
+            } else {
+                diff.print();
+                Ok(())
+            }
+        }
+        EnvCommands::Edit { appid } => edit_env(phd, &appid),
+        EnvCommands::Source { appid, shell } => {
+            source_env(phd, &appid, shell.unwrap_or_else(EnvShell::detect))
+        }
+    }
+}
+
+/// Печатает присваивания для `eval`: то, что `exec` добавил бы к окружению оболочки
+/// (сохранённое окружение, `[apps.<appid>]` и `PROTONHAX_CONTEXT`).
+fn source_env(phd: &Path, selector: &str, shell: EnvShell) -> io::Result<()> {
+    let before: BTreeMap<String, String> = env::vars().collect();
+    prepare_context(phd, selector)?;
+    let mut script = String::new();
+    let after: BTreeMap<String, String> = env::vars().collect();
+    for (name, value) in &after {
+        if SHELL_SESSION_VARS.contains(&name.as_str()) || before.get(name) == Some(value) {
+            continue;
+        }
+        match shell.assignment(name, value) {
+            Some(line) => {
+                script.push_str(&line);
+                script.push('\n');
+            }
+            None => verbosity::verbose(format_args!("{name} не присвоить в этой оболочке")),
+        }
+    }
+    print!("{script}");
+    Ok(())
+}
+
+/// Открывает копию окружения в `$VISUAL`/`$EDITOR` и после проверки синтаксиса атомарно
+/// записывает её обратно; при ошибке предлагает вернуться в редактор.
+fn edit_env(phd: &Path, selector: &str) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let original = fs::read_to_string(target.app_dir.join(ENV_FILE))?;
+    // Копия лежит в каталоге контекста (0700), а не в общем /tmp, где заранее
+    // подложенная ссылка с предсказуемым именем перенаправила бы запись.
+    let tmp_path = target
+        .app_dir
+        .join(format!("{ENV_FILE}.edit.{}.sh", process::id()));
+    write_private(&tmp_path, &original)?;
+
+    let result = edit_until_valid(&tmp_path);
+    let edited = fs::read_to_string(&tmp_path);
+    let _ = fs::remove_file(&tmp_path);
+    // Ошибки редактора — только после удаления копии: иначе она осталась бы в контексте.
+    if !result.unwrap_or_else(|err| fail(&err.to_string())) {
+        fail("Окружение не изменено: исправления отменены");
+    }
+
+    let edited = edited?;
+    if edited == original {
+        println!("{}", "Без изменений".dimmed());
+        return Ok(());
+    }
+    replace_env_file(&target.app_dir, &edited)?;
+    println!(
+        "{} окружение {} обновлено",
+        "OK".green().bold(),
+        target.appid
+    );
+    Ok(())
+}
+
+/// `Ok(false)`, если пользователь отказался исправлять ошибку.
+fn edit_until_valid(path: &Path) -> io::Result<bool> {
+    loop {
+        run_editor(path)?;
+        let Err(err) = validate_env_content(&fs::read_to_string(path)?) else {
+            return Ok(true);
+        };
+        eprintln!("{} {err}", "Ошибка:".bold().red());
+        eprint!("Открыть редактор снова? [Y/n] ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if answer.trim().eq_ignore_ascii_case("n") {
+            return Ok(false);
+        }
+    }
+}
+
+fn run_editor(path: &Path) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // `$EDITOR` может содержать аргументы, например `code --wait`.
+    let Ok(command) = shell_words::split(&editor) else {
+        return Err(io::Error::other(format!(
+            "Не удалось разобрать $EDITOR: {editor}"
+        )));
+    };
+    let Some((program, args)) = command.split_first() else {
+        return Err(io::Error::other("$EDITOR пуст"));
+    };
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Редактор завершился с ошибкой ({status})"
+        )));
+    }
+    Ok(())
+}
+
+fn saved_env(phd: &Path, selector: &str) -> io::Result<BTreeMap<String, String>> {
+    let target = resolve_target_app(phd, selector)?;
+    let content = fs::read_to_string(target.app_dir.join(ENV_FILE))?;
+    Ok(parse_env_content(&content))
+}
+
+/// Окружение оболочки без секретов: в контекст они не сохраняются и иначе всегда
+/// попадали бы в «удалённые».
+pub fn current_env() -> io::Result<BTreeMap<String, String>> {
+    let filter = Config::load()?.env;
+    Ok(env::vars()
+        .filter(|(key, _)| !filter.is_secret(key))
+        .collect())
+}
+
+#[derive(Serialize)]
+struct Change {
+    from: String,
+    to: String,
+}
+
+/// Разница от базового окружения к окружению контекста.
+#[derive(Default, Serialize)]
+pub struct EnvDiff {
+    added: BTreeMap<String, String>,
+    removed: BTreeMap<String, String>,
+    changed: BTreeMap<String, Change>,
+}
+
+impl EnvDiff {
+    pub fn between(base: &BTreeMap<String, String>, target: &BTreeMap<String, String>) -> Self {
+        let mut diff = Self::default();
+        for (name, value) in target {
+            match base.get(name) {
+                None => {
+                    diff.added.insert(name.clone(), value.clone());
+                }
+                Some(old) if old != value => {
+                    diff.changed.insert(
+                        name.clone(),
+                        Change {
+                            from: old.clone(),
+                            to: value.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, value) in base {
+            if !target.contains_key(name) {
+                diff.removed.insert(name.clone(), value.clone());
+            }
+        }
+        diff
+    }
+
+    pub fn print(&self) {
+        if self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() {
+            println!("{}", "Окружения совпадают".dimmed());
+            return;
+        }
+        for (name, value) in &self.added {
+            println!("{} {name}={value}", "+".green().bold());
+        }
+        for (name, value) in &self.removed {
+            println!("{} {name}={value}", "-".red().bold());
+        }
+        for (name, change) in &self.changed {
+            println!(
+                "{} {name}: {} → {}",
+                "~".yellow().bold(),
+                change.from.red(),
+                change.to.green()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvDiff, EnvShell};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn assignments_per_shell() {
+        let value = "/usr/bin:/opt/my games/bin";
+        assert_eq!(
+            EnvShell::Bash.assignment("PATH", value).unwrap(),
+            "export PATH='/usr/bin:/opt/my games/bin'"
+        );
+        assert_eq!(
+            EnvShell::Fish.assignment("PATH", value).unwrap(),
+            "set -gx PATH /usr/bin '/opt/my games/bin'"
+        );
+        assert_eq!(EnvShell::Zsh.assignment("path", value), None);
+        assert_eq!(
+            EnvShell::Bash.assignment("path", value).unwrap(),
+            "export path='/usr/bin:/opt/my games/bin'"
+        );
+        assert_eq!(
+            EnvShell::Bash.assignment("BASH_FUNC_x%%", "() { :; }"),
+            None
+        );
+    }
+
+    #[test]
+    fn diffs_environments() {
+        let base = BTreeMap::from([
+            ("A".to_string(), "1".to_string()),
+            ("B".to_string(), "2".to_string()),
+        ]);
+        let target = BTreeMap::from([
+            ("B".to_string(), "3".to_string()),
+            ("C".to_string(), "4".to_string()),
+        ]);
+        let diff = EnvDiff::between(&base, &target);
+        assert_eq!(diff.added.keys().collect::<Vec<_>>(), ["C"]);
+        assert_eq!(diff.removed.keys().collect::<Vec<_>>(), ["A"]);
+        assert_eq!(diff.changed["B"].from, "2");
+        assert_eq!(diff.changed["B"].to, "3");
+    }
+}
@@ -0,0 +1,199 @@
+use colored::Colorize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::fail,
+    runtime::{cache_dir, find_in_path},
+    signals::process_alive,
+    verbosity,
+    wine::PrefixContext,
+};
+
+const EPHEMERAL_DIR: &str = "ephemeral";
+const FUSE_OVERLAYFS: &str = "fuse-overlayfs";
+/// Подкаталоги одноразового префикса: слой изменений overlayfs, его рабочий каталог и
+/// compatdata, которую видит proton.
+const UPPER_DIR: &str = "upper";
+const WORK_DIR: &str = "work";
+const MERGED_DIR: &str = "compatdata";
+
+/// Как собрана одноразовая compatdata.
+enum Layer {
+    /// fuse-overlayfs поверх настоящей compatdata; `fusermount` для отключения.
+    Overlay(PathBuf),
+    /// Полная копия (`cp --reflink=auto`): на btrfs и XFS почти бесплатна.
+    Copy,
+}
+
+/// Одноразовая compatdata для `run --ephemeral`: программа видит префикс игры, но все
+/// изменения пишутся в отдельный слой и пропадают после выхода.
+pub struct EphemeralPrefix {
+    /// `<cache>/protonhax/ephemeral/<appid>.<pid>`; pid нужен для уборки после сбоев.
+    root: PathBuf,
+    lower: PathBuf,
+    /// Тот же префикс, но в одноразовой compatdata: через него останавливаем wineserver.
+    prefix: PrefixContext,
+    layer: Option<Layer>,
+}
+
+impl EphemeralPrefix {
+    /// Размещение одноразовой копии для `compat_data`; на диске ничего не создаёт —
+    /// это делает [`EphemeralPrefix::mount`] перед запуском.
+    pub fn plan(appid: &str, compat_data: &Path, proton: &str, wine: Option<String>) -> Self {
+        let root = cache_dir()
+            .join(EPHEMERAL_DIR)
+            .join(format!("{appid}.{}", process::id()));
+        let prefix = PrefixContext {
+            appid: appid.to_string(),
+            proton: PathBuf::from(proton),
+            pfx: root.join(MERGED_DIR).join("pfx"),
+            wine: wine.map(PathBuf::from),
+            app_dir: None,
+        };
+        Self {
+            root,
+            lower: compat_data.to_path_buf(),
+            prefix,
+            layer: None,
+        }
+    }
+
+    /// compatdata, которую получает proton (`STEAM_COMPAT_DATA_PATH`).
+    pub fn compat_data(&self) -> &Path {
+        self.prefix.compat_data()
+    }
+
+    pub fn pfx(&self) -> &Path {
+        &self.prefix.pfx
+    }
+
+    /// Путь внутри настоящей compatdata переводит в одноразовую: программа из `C:\`
+    /// должна писать рядом с собой уже в копию.
+    pub fn rebase(&self, path: &str) -> String {
+        Path::new(path).strip_prefix(&self.lower).map_or_else(
+            |_| path.to_string(),
+            |rest| self.compat_data().join(rest).to_string_lossy().into_owned(),
+        )
+    }
+
+    /// Собирает одноразовую compatdata: overlay, если есть fuse-overlayfs, иначе копия.
+    pub fn mount(&mut self) -> io::Result<()> {
+        remove_stale();
+        let merged = self.compat_data().to_path_buf();
+        fs::create_dir_all(&merged)?;
+        if let (Some(_), Some(fusermount)) = (find_in_path(FUSE_OVERLAYFS), fusermount()) {
+            let upper = self.root.join(UPPER_DIR);
+            let work = self.root.join(WORK_DIR);
+            fs::create_dir_all(&upper)?;
+            fs::create_dir_all(&work)?;
+            let options = format!(
+                "lowerdir={},upperdir={},workdir={}",
+                self.lower.display(),
+                upper.display(),
+                work.display()
+            );
+            verbosity::verbose(format_args!(
+                "{FUSE_OVERLAYFS} -o {options} {}",
+                merged.display()
+            ));
+            let status = process::Command::new(FUSE_OVERLAYFS)
+                .arg("-o")
+                .arg(options)
+                .arg(&merged)
+                .status()?;
+            if !status.success() {
+                let _ = fs::remove_dir_all(&self.root);
+                fail(&format!("{FUSE_OVERLAYFS} завершился с ошибкой ({status})"));
+            }
+            self.layer = Some(Layer::Overlay(fusermount));
+        } else {
+            eprintln!(
+                "{} {FUSE_OVERLAYFS} не найден: префикс копируется целиком, это может занять время",
+                "Предупреждение:".yellow().bold()
+            );
+            // Каталог уже создан, поэтому копируем содержимое, а не сам каталог.
+            let status = process::Command::new("cp")
+                .args(["-a", "--reflink=auto"])
+                .arg(self.lower.join("."))
+                .arg(&merged)
+                .status()?;
+            self.layer = Some(Layer::Copy);
+            if !status.success() {
+                fail(&format!("Не удалось скопировать префикс ({status})"));
+            }
+        }
+        verbosity::info(format_args!(
+            "одноразовый префикс: {}",
+            self.compat_data().display()
+        ));
+        Ok(())
+    }
+}
+
+impl Drop for EphemeralPrefix {
+    fn drop(&mut self) {
+        let Some(layer) = &self.layer else {
+            return;
+        };
+        // Оставшиеся процессы Wine держат файлы копии; префикс одноразовый, их не жалко.
+        if let Some(bin_dir) = self.prefix.wine_bin_dir() {
+            let mut wineserver = process::Command::new(bin_dir.join("wineserver"));
+            wineserver.arg("-k");
+            self.prefix.apply_wine_env(&mut wineserver);
+            let _ = wineserver.stderr(process::Stdio::null()).status();
+        }
+        if let Layer::Overlay(fusermount) = layer {
+            unmount(fusermount, self.compat_data());
+        }
+        if let Err(err) = fs::remove_dir_all(&self.root) {
+            eprintln!(
+                "{} не удалось удалить {}: {err}",
+                "Предупреждение:".yellow().bold(),
+                self.root.display()
+            );
+        }
+        verbosity::info(format_args!("изменения одноразового префикса отброшены"));
+    }
+}
+
+fn fusermount() -> Option<PathBuf> {
+    ["fusermount3", "fusermount"]
+        .iter()
+        .find_map(|name| find_in_path(name))
+}
+
+/// Ленивое отключение: wineserver мог ещё не отпустить файлы.
+fn unmount(fusermount: &Path, merged: &Path) {
+    let _ = process::Command::new(fusermount)
+        .args(["-u", "-z"])
+        .arg(merged)
+        .stderr(process::Stdio::null())
+        .status();
+}
+
+/// Убирает копии запусков, прерванных до уборки (Ctrl+C, `--timeout`).
+fn remove_stale() {
+    let Ok(entries) = fs::read_dir(cache_dir().join(EPHEMERAL_DIR)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let pid = name
+            .to_str()
+            .and_then(|name| name.rsplit_once('.'))
+            .and_then(|(_, pid)| pid.parse::<libc::pid_t>().ok());
+        if pid.is_none_or(process_alive) {
+            continue;
+        }
+        let root = entry.path();
+        if let Some(fusermount) = fusermount() {
+            unmount(&fusermount, &root.join(MERGED_DIR));
+        }
+        verbosity::verbose(format_args!("удаляем {}", root.display()));
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,96 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{env, ffi::OsStr, io, iter, path::Path};
+
+use crate::{
+    environment::{EnvDiff, current_env},
+    handlers::{RunAction, RunOptions, plan_run},
+    output::print_json,
+    wine::host_to_windows,
+};
+
+/// Показывает, как `run` с теми же аргументами запустил бы команду, ничего не выполняя.
+pub fn handle_explain_run(
+    phd: &Path,
+    appid: &str,
+    cmd: &[String],
+    options: &RunOptions,
+    json_output: bool,
+) -> io::Result<()> {
+    // Окружение контекста применяется к самому protonhax, поэтому снимок оболочки
+    // делаем до разбора цели.
+    let shell_env = current_env()?;
+    let plan = plan_run(phd, appid, cmd, options)?;
+    let (child, wrappers) = match plan.action {
+        RunAction::ImportReg(reg_file) => {
+            let windows_path = host_to_windows(Path::new(&plan.target.pfx()?), &reg_file);
+            (
+                plan.launcher.regedit(&windows_path),
+                vec![plan.launcher.describe()],
+            )
+        }
+        RunAction::Spawn {
+            child, wrappers, ..
+        } => (*child, wrappers),
+    };
+
+    let mut child_env = current_env()?;
+    for (name, value) in child.get_envs() {
+        let name = name.to_string_lossy().into_owned();
+        match value {
+            Some(value) => {
+                child_env.insert(name, value.to_string_lossy().into_owned());
+            }
+            None => {
+                child_env.remove(&name);
+            }
+        }
+    }
+    let env_diff = EnvDiff::between(&shell_env, &child_env);
+    let argv: Vec<String> = iter::once(child.get_program())
+        .chain(child.get_args())
+        .map(OsStr::to_string_lossy)
+        .map(String::from)
+        .collect();
+    let workdir = child
+        .get_current_dir()
+        .map(Path::to_path_buf)
+        .or_else(|| env::current_dir().ok());
+
+    if json_output {
+        return print_json(&json!({
+            "appid": plan.target.appid,
+            "context": plan.target.app_dir,
+            "program": plan.launcher.program,
+            "wine": plan.launcher.wine,
+            "wrappers": wrappers,
+            "argv": argv,
+            "cwd": workdir,
+            "env": env_diff,
+        }));
+    }
+
+    let label = |name: &str| format!("{name:<10}").bold();
+    println!(
+        "{} {} ({})",
+        label("Контекст"),
+        plan.target.appid,
+        plan.target.app_dir.display()
+    );
+    println!(
+        "{} {}",
+        label(if plan.launcher.wine { "Wine" } else { "Proton" }),
+        plan.launcher.program
+    );
+    println!("{} {}", label("Обёртки"), wrappers.join(" → "));
+    println!(
+        "{} {}",
+        label("Каталог"),
+        workdir.map_or_else(|| "?".to_string(), |dir| dir.display().to_string())
+    );
+    println!("{} {}", label("argv"), shell_words::join(&argv));
+    println!();
+    println!("{}", "Окружение относительно текущей оболочки:".bold());
+    env_diff.print();
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use colored::Colorize;
+use std::{
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::Path,
+};
+
+use crate::{
+    context::{TargetApp, resolve_target_app},
+    env_store::{ENV_FILE, parse_env_content},
+    shell::shell_escape,
+};
+
+/// Пишет shell-скрипт, воспроизводящий контекст без runtime-каталога: `script run <cmd>`
+/// запускает через proton, `script exec <cmd>` — нативно с окружением игры.
+pub fn handle_export(phd: &Path, selector: &str, output: Option<&Path>) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let script = export_script(&target)?;
+
+    let Some(output) = output else {
+        print!("{script}");
+        return Ok(());
+    };
+    // Скрипт содержит окружение игры, поэтому доступен только владельцу.
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o700)
+        .open(output)?;
+    file.set_permissions(fs::Permissions::from_mode(0o700))?;
+    file.write_all(script.as_bytes())?;
+    println!(
+        "{} context {} exported to {}",
+        "OK".green().bold(),
+        target.appid,
+        output.display()
+    );
+    Ok(())
+}
+
+fn export_script(target: &TargetApp) -> io::Result<String> {
+    let env = parse_env_content(&fs::read_to_string(target.app_dir.join(ENV_FILE))?);
+    let proton = target.exe()?;
+    let pfx = target.pfx()?;
+    let title = match target.meta().name {
+        Some(name) => format!("{} ({name})", target.appid),
+        None => target.appid.clone(),
+    };
+
+    let mut script = String::from("#!/bin/sh\n");
+    let _ = writeln!(script, "# protonhax export: {title}");
+    let _ = writeln!(script, "# Usage: $0 run <cmd...> | $0 exec <cmd...>\n");
+    for (key, value) in &env {
+        let _ = writeln!(script, "export {key}={}", shell_escape(value));
+    }
+    let _ = writeln!(script, "export WINEPREFIX={}", shell_escape(&pfx));
+    let _ = writeln!(script, "PROTON={}\n", shell_escape(&proton));
+    script.push_str(
+        r#"case "$1" in
+    run) shift; exec "$PROTON" run "$@" ;;
+    exec) shift; exec "$@" ;;
+    *) echo "Usage: $0 run <cmd...> | $0 exec <cmd...>" >&2; exit 2 ;;
+esac
+"#,
+    );
+    Ok(script)
+}
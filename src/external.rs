@@ -0,0 +1,199 @@
+use std::{
+    fs, io,
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    context::{EXE_FILE, PFX_FILE, STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV, WINE_FILE},
+    env_store::{ENV_FILE, write_private},
+    filter::NameFilter,
+    heroic, lutris,
+    output::print_json,
+    runtime::{cache_dir, home_dir},
+    shell::shell_escape,
+    steam::{
+        AppMeta, STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV, STEAM_GAME_ID_ENV, steam_roots,
+        write_cached_meta,
+    },
+    wine::WINEPREFIX_ENV,
+};
+
+/// Чем лаунчер запускает игру.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Runner {
+    /// Скрипт `proton` и compatdata (префикс Wine — в `<compat_data>/pfx`).
+    Proton {
+        script: PathBuf,
+        compat_data: PathBuf,
+    },
+    /// Бинарник Wine и сам префикс.
+    Wine { binary: PathBuf, prefix: PathBuf },
+}
+
+impl Runner {
+    pub fn pfx(&self) -> PathBuf {
+        match self {
+            Self::Proton { compat_data, .. } => compat_data.join("pfx"),
+            Self::Wine { prefix, .. } => prefix.clone(),
+        }
+    }
+}
+
+/// Игра, установленная сторонним лаунчером (Heroic, Lutris), с известным префиксом.
+#[derive(Serialize)]
+pub struct ExternalGame {
+    /// Селектор для `run`/`cmd`: `<лаунчер>-<id игры в лаунчере>`.
+    pub id: String,
+    pub launcher: &'static str,
+    pub name: String,
+    pub install_path: Option<String>,
+    pub runner: Runner,
+}
+
+impl ExternalGame {
+    /// Запущена ли игра: есть процесс с окружением её префикса.
+    pub fn is_running(&self) -> bool {
+        let (name, value) = match &self.runner {
+            Runner::Proton { compat_data, .. } => (STEAM_COMPAT_DATA_PATH_ENV, compat_data),
+            Runner::Wine { prefix, .. } => (WINEPREFIX_ENV, prefix),
+        };
+        let needle = format!("{name}={}", value.display());
+        fs::read_dir("/proc")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|entry| {
+                fs::read(entry.path().join("environ")).is_ok_and(|environ| {
+                    environ
+                        .split(|byte| *byte == 0)
+                        .any(|var| var == needle.as_bytes())
+                })
+            })
+    }
+
+    /// Собирает контекст той же структуры, что у `init`, в кэше; пересоздаётся при
+    /// каждом обращении, чтобы следовать за настройками лаунчера.
+    pub fn materialize(&self) -> io::Result<PathBuf> {
+        let app_dir = cache_dir().join("external").join(&self.id);
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&app_dir)?;
+
+        let mut env_content = String::new();
+        let mut push_var = |name: &str, value: &Path| {
+            env_content.push_str("declare -x ");
+            env_content.push_str(name);
+            env_content.push('=');
+            env_content.push_str(&shell_escape(&value.to_string_lossy()));
+            env_content.push('\n');
+        };
+        match &self.runner {
+            Runner::Proton {
+                script,
+                compat_data,
+            } => {
+                fs::write(app_dir.join(EXE_FILE), script.to_string_lossy().as_bytes())?;
+                let _ = fs::remove_file(app_dir.join(WINE_FILE));
+                push_var(STEAM_COMPAT_DATA_PATH_ENV, compat_data);
+                // Игры не из Steam: appid 0, как у umu и Heroic.
+                push_var(STEAM_APP_ID_ENV, Path::new("0"));
+                push_var(STEAM_GAME_ID_ENV, Path::new("0"));
+                let steam_root = steam_roots()
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| home_dir().join(".steam/steam"));
+                push_var(STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV, &steam_root);
+            }
+            Runner::Wine { binary, prefix } => {
+                fs::write(app_dir.join(EXE_FILE), "")?;
+                fs::write(app_dir.join(WINE_FILE), binary.to_string_lossy().as_bytes())?;
+                push_var(WINEPREFIX_ENV, prefix);
+            }
+        }
+        fs::write(
+            app_dir.join(PFX_FILE),
+            self.runner.pfx().to_string_lossy().as_bytes(),
+        )?;
+        write_private(&app_dir.join(ENV_FILE), &env_content)?;
+        write_cached_meta(
+            &app_dir,
+            &AppMeta {
+                name: Some(self.name.clone()),
+                install_path: self.install_path.clone(),
+                ..AppMeta::default()
+            },
+        )?;
+        Ok(app_dir)
+    }
+}
+
+/// `ls --installed`: игры сторонних лаунчеров; запущенные отмечены.
+pub fn handle_ls_installed(
+    long: bool,
+    json_output: bool,
+    filter: Option<&NameFilter>,
+) -> io::Result<()> {
+    let mut games = external_games();
+    if let Some(filter) = filter {
+        games.retain(|game| filter.matches([game.id.as_str(), game.name.as_str()]));
+    }
+    if json_output {
+        let mut data = Vec::with_capacity(games.len());
+        for game in &games {
+            let mut value = serde_json::to_value(game).map_err(io::Error::other)?;
+            value["running"] = json!(game.is_running());
+            data.push(value);
+        }
+        return print_json(&data);
+    }
+
+    for game in games {
+        let mut parts = vec![game.id.green().to_string(), game.name.yellow().to_string()];
+        if game.is_running() {
+            parts.push("running".bold().to_string());
+        }
+        if long {
+            let runner = match &game.runner {
+                Runner::Proton { script, .. } => script,
+                Runner::Wine { binary, .. } => binary,
+            };
+            parts.push(runner.display().to_string().dimmed().to_string());
+            parts.push(game.runner.pfx().display().to_string().dimmed().to_string());
+        }
+        println!("{}", parts.join("  "));
+    }
+    Ok(())
+}
+
+/// Все игры сторонних лаунчеров с префиксами Wine/Proton.
+pub fn external_games() -> Vec<ExternalGame> {
+    let mut games = heroic::installed_games();
+    games.extend(lutris::installed_games());
+    games.sort_by(|left, right| left.id.cmp(&right.id));
+    games
+}
+
+/// Игра по точному селектору (`heroic-<appName>`, `lutris-<slug>`).
+pub fn find_external(selector: &str) -> Option<ExternalGame> {
+    if !selector.contains('-') {
+        return None;
+    }
+    external_games()
+        .into_iter()
+        .find(|game| game.id == selector)
+}
+
+/// Раскрывает `~/` в путях из конфигов лаунчеров.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
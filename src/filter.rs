@@ -0,0 +1,58 @@
+use regex::Regex;
+
+use crate::env_store::wildcard_match;
+
+/// Фильтр `ls` по id, appid и имени игры.
+#[derive(Clone, Debug)]
+pub enum NameFilter {
+    /// Шаблон с `*` без учёта регистра; без `*` — подстрока. Хранится в нижнем регистре.
+    Glob(String),
+    Regex(Regex),
+}
+
+impl NameFilter {
+    pub fn new(pattern: Option<String>, regex: Option<Regex>) -> Option<Self> {
+        regex.map(Self::Regex).or_else(|| {
+            pattern.map(|pattern| {
+                // `wildcard_match` сводит к одному регистру только ASCII, а названия игр
+                // бывают и кириллицей.
+                let pattern = pattern.to_lowercase();
+                if pattern.contains('*') {
+                    Self::Glob(pattern)
+                } else {
+                    Self::Glob(format!("*{pattern}*"))
+                }
+            })
+        })
+    }
+
+    /// Подходит ли хоть одно из значений.
+    pub fn matches<'a>(&self, values: impl IntoIterator<Item = &'a str>) -> bool {
+        values.into_iter().any(|value| match self {
+            Self::Glob(pattern) => wildcard_match(pattern, &value.to_lowercase()),
+            Self::Regex(regex) => regex.is_match(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameFilter;
+    use regex::Regex;
+
+    #[test]
+    fn filters_by_any_value() {
+        let glob = NameFilter::new(Some("elden*".into()), None).unwrap();
+        assert!(glob.matches(["1245620", "ELDEN RING"]));
+        assert!(!glob.matches(["1245620", "The Elden Ring"]));
+        let substring = NameFilter::new(Some("ring".into()), None).unwrap();
+        assert!(substring.matches(["The Elden Ring"]));
+        let cyrillic = NameFilter::new(Some("ведьмак".into()), None).unwrap();
+        assert!(cyrillic.matches(["Ведьмак 3: Дикая Охота"]));
+        let regex = NameFilter::new(None, Some(Regex::new("^12").unwrap())).unwrap();
+        assert!(regex.matches(["1245620"]));
+        let regex = NameFilter::new(None, Some(Regex::new("(?i)souls").unwrap())).unwrap();
+        assert!(regex.matches(["DARK SOULS III"]));
+        assert!(NameFilter::new(None, None).is_none());
+    }
+}
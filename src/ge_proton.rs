@@ -0,0 +1,268 @@
+use colored::Colorize;
+use serde_json::Value;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::fail,
+    context::{EXE_FILE, collect_running_apps, read_trimmed},
+    proton::{CUSTOM_TOOLS_DIR, installed_protons},
+    runtime::cache_dir,
+    steam::steam_roots,
+    ui,
+};
+
+const RELEASES_API_URL: &str =
+    "https://api.github.com/repos/GloriousEggroll/proton-ge-custom/releases";
+const LATEST_RELEASE: &str = "latest";
+const API_TIMEOUT_SECS: &str = "15";
+
+/// Релиз GE-Proton: архив сборки и файл с его SHA-512.
+struct Release {
+    tag: String,
+    tarball_url: String,
+    checksum_url: String,
+}
+
+/// Скачивает релиз GE-Proton, сверяет SHA-512 и распаковывает его в `compatibilitytools.d`
+/// первого корня Steam.
+pub fn install_ge_proton(version: &str) -> io::Result<()> {
+    if version.is_empty() || version.contains('/') {
+        fail(&format!(
+            "Некорректная версия \"{version}\": ожидается GE-Proton<версия> или latest"
+        ));
+    }
+    let Some(steam_root) = steam_roots().into_iter().next() else {
+        fail("Steam не найден: некуда устанавливать сборку (нет ~/.steam/steam)");
+    };
+    let tools_dir = steam_root.join(CUSTOM_TOOLS_DIR);
+    let release = fetch_release(version);
+    let target = tools_dir.join(&release.tag);
+    if target.exists() {
+        fail(&format!(
+            "{} уже установлен: {}",
+            release.tag,
+            target.display()
+        ));
+    }
+
+    let downloads = cache_dir().join("downloads");
+    fs::create_dir_all(&downloads)?;
+    let tarball = downloads.join(format!("{}.tar.gz", release.tag));
+    println!("Скачиваем {}", release.tarball_url);
+    let status = process::Command::new("curl")
+        .args(["-fL", "--progress-bar", "-o"])
+        .arg(&tarball)
+        .arg(&release.tarball_url)
+        .status()?;
+    if !status.success() {
+        let _ = fs::remove_file(&tarball);
+        fail(&format!("Не удалось скачать {}", release.tarball_url));
+    }
+
+    let expected = curl_text(&release.checksum_url)
+        .as_deref()
+        .and_then(parse_checksum)
+        .map(str::to_lowercase);
+    let Some(expected) = expected else {
+        let _ = fs::remove_file(&tarball);
+        fail(&format!(
+            "Не удалось получить контрольную сумму {}",
+            release.checksum_url
+        ));
+    };
+    let actual = sha512(&tarball)?;
+    if actual != expected {
+        let _ = fs::remove_file(&tarball);
+        fail(&format!(
+            "Контрольная сумма {} не совпадает: ожидалась {expected}, получена {actual}",
+            tarball.display()
+        ));
+    }
+    println!("{} SHA-512 совпадает", "OK".green().bold());
+
+    let result = unpack(&tarball, &tools_dir, &release.tag);
+    let _ = fs::remove_file(&tarball);
+    result?;
+    println!(
+        "{} {} установлен в {}; перезапустите Steam, чтобы он появился в списке совместимости",
+        "OK".green().bold(),
+        release.tag,
+        target.display()
+    );
+    Ok(())
+}
+
+/// Удаляет пользовательскую сборку из `compatibilitytools.d`, если её не использует
+/// запущенная игра.
+pub fn remove_custom_proton(phd: &Path, name: &str) -> io::Result<()> {
+    let Some(build) = installed_protons()
+        .into_iter()
+        .find(|build| build.name == name)
+    else {
+        fail(&format!(
+            "Сборка Proton \"{name}\" не найдена (см. `protonhax protons`)"
+        ));
+    };
+    if !build.is_custom() {
+        fail(&format!("{name} — сборка Valve; удалите её через Steam"));
+    }
+
+    let in_use: Vec<String> = collect_running_apps(phd, false)?
+        .into_iter()
+        .filter(|app| {
+            read_trimmed(app.path.join(EXE_FILE))
+                .is_ok_and(|exe| Path::new(&exe).starts_with(&build.dir))
+        })
+        .map(|app| app.id)
+        .collect();
+    if !in_use.is_empty() {
+        fail(&format!(
+            "{name} используется запущенными играми: {}",
+            in_use.join(", ")
+        ));
+    }
+
+    ui::confirm_or_abort(&format!("Удалить {}?", build.dir.display()));
+    fs::remove_dir_all(&build.dir)?;
+    println!("{} {name} удалён", "OK".green().bold());
+    Ok(())
+}
+
+fn fetch_release(version: &str) -> Release {
+    let url = if version == LATEST_RELEASE {
+        format!("{RELEASES_API_URL}/latest")
+    } else {
+        format!("{RELEASES_API_URL}/tags/{version}")
+    };
+    let Some(response) = curl_text(&url) else {
+        fail(&format!(
+            "Не удалось получить релиз {version} GE-Proton (нужны curl и сеть)"
+        ));
+    };
+    let Some(release) = serde_json::from_str::<Value>(&response)
+        .ok()
+        .and_then(|json| parse_release(&json))
+    else {
+        fail(&format!(
+            "В релизе {version} GE-Proton нет архива .tar.gz с .sha512sum"
+        ));
+    };
+    release
+}
+
+fn parse_release(json: &Value) -> Option<Release> {
+    let tag = json.get("tag_name")?.as_str()?.to_string();
+    let assets = json.get("assets")?.as_array()?;
+    let asset_url = |suffix: &str| {
+        assets.iter().find_map(|asset| {
+            let name = asset.get("name")?.as_str()?;
+            name.ends_with(suffix)
+                .then(|| asset.get("browser_download_url")?.as_str())
+                .flatten()
+                .map(str::to_string)
+        })
+    };
+    Some(Release {
+        tarball_url: asset_url(".tar.gz")?,
+        checksum_url: asset_url(".sha512sum")?,
+        tag,
+    })
+}
+
+/// Файл `.sha512sum` в формате `sha512sum`: `<hex>  <имя файла>`.
+fn parse_checksum(content: &str) -> Option<&str> {
+    let hash = content.split_whitespace().next()?;
+    (hash.len() == 128 && hash.bytes().all(|byte| byte.is_ascii_hexdigit())).then_some(hash)
+}
+
+fn curl_text(url: &str) -> Option<String> {
+    let output = process::Command::new("curl")
+        .args(["-fsSL", "--max-time", API_TIMEOUT_SECS])
+        .arg(url)
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn sha512(path: &Path) -> io::Result<String> {
+    let output = process::Command::new("sha512sum").arg(path).output()?;
+    if !output.status.success() {
+        fail(&format!(
+            "sha512sum завершился с ошибкой для {}",
+            path.display()
+        ));
+    }
+    Ok(parse_checksum(&String::from_utf8_lossy(&output.stdout))
+        .unwrap_or_default()
+        .to_lowercase())
+}
+
+/// Распаковывает во временный каталог рядом с целевым и переносит сборку одним `rename`,
+/// чтобы Steam не увидел наполовину распакованный Proton.
+fn unpack(tarball: &Path, tools_dir: &Path, tag: &str) -> io::Result<()> {
+    fs::create_dir_all(tools_dir)?;
+    let staging = tools_dir.join(format!(".{tag}.partial"));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let status = process::Command::new("tar")
+        .arg("-xzf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(&staging)
+        .status()?;
+    let unpacked: Option<PathBuf> = status
+        .success()
+        .then(|| staging.join(tag))
+        .filter(|dir| dir.join("proton").is_file());
+    let Some(unpacked) = unpacked else {
+        let _ = fs::remove_dir_all(&staging);
+        fail(&format!(
+            "Архив {} не содержит сборку {tag}/proton",
+            tarball.display()
+        ));
+    };
+    fs::rename(unpacked, tools_dir.join(tag))?;
+    fs::remove_dir_all(&staging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_checksum, parse_release};
+    use serde_json::json;
+
+    #[test]
+    fn picks_release_assets() {
+        let release = json!({
+            "tag_name": "GE-Proton9-20",
+            "assets": [
+                {"name": "GE-Proton9-20.sha512sum", "browser_download_url": "https://x/sum"},
+                {"name": "GE-Proton9-20.tar.gz", "browser_download_url": "https://x/tar"},
+            ],
+        });
+        let release = parse_release(&release).expect("release");
+        assert_eq!(release.tag, "GE-Proton9-20");
+        assert_eq!(release.tarball_url, "https://x/tar");
+        assert_eq!(release.checksum_url, "https://x/sum");
+    }
+
+    #[test]
+    fn parses_sha512sum_line() {
+        let hash = "a".repeat(128);
+        assert_eq!(
+            parse_checksum(&format!("{hash}  GE-Proton9-20.tar.gz\n")),
+            Some(hash.as_str())
+        );
+        assert_eq!(parse_checksum("deadbeef  file"), None);
+    }
+}
@@ -1,39 +1,56 @@
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
 use serde_json::json;
 use std::{
-    env, fs,
-    io::{self, Write},
+    env,
+    ffi::OsString,
+    fs, io,
     path::{Path, PathBuf},
     process,
+    time::Duration,
 };
 
 use crate::{
-    cli::sub_usage,
-    env_store::{ENV_FILE, get_env_var, load_env, set_env_var},
-    runtime::{format_duration_ago, unix_now_secs},
-    shell::{is_env_assignment, shell_escape, split_env_assignment},
-    steam::{AppMeta, resolve_app_meta},
+    audit::{self, AuditAction},
+    cli::{fail, print_subcommand_usage_error},
+    config::Config,
+    context::{
+        ContextGuard, ContextStatus, ENDED_AT_FILE, EXE_FILE, EXIT_CODE_FILE, NATIVE_FILE,
+        PFX_FILE, PID_FILE, RunningApp, STARTED_AT_FILE, STEAM_APP_ID_ENV, STEAM_COMPAT_APP_ID_ENV,
+        STEAM_COMPAT_DATA_PATH_ENV, TargetApp, allocate_context_dir, collect_running_apps,
+        prepare_context, registered_root, split_implicit_target,
+    },
+    discord::Presence,
+    env_store::{ENV_FILE, set_env_var, write_env_file},
+    ephemeral::EphemeralPrefix,
+    filter::NameFilter,
+    listing_cache,
+    lock::ContextLock,
+    logs::prune_logs,
+    output::print_json,
+    paths::find_in_install_dir,
+    priority::{self, Priority},
+    proton::{ProtonVerb, context_proton_version},
+    registry::{decode_reg_file, parse_reg},
+    runtime::{
+        ensure_runtime_root, exit_with_status, find_in_path, format_duration_ago, run_with_timeout,
+        unix_now_secs,
+    },
+    sandbox::sandboxed_command,
+    scope::{ResourceLimits, command_in_scope, scope_unit_name},
+    shell::{is_env_assignment, split_env_assignment},
+    steam::{LaunchChain, cache_init_meta},
+    trace::{STRACE, strace_args},
+    umu::UmuLaunch,
+    verbosity,
+    wine::{WINEPREFIX_ENV, is_windows_path, to_host_path, to_windows_path},
 };
 
-const EXE_FILE: &str = "exe";
-const PFX_FILE: &str = "pfx";
-const STARTED_AT_FILE: &str = "started_at";
-const STEAM_APP_ID_ENV: &str = "SteamAppId";
-const STEAM_COMPAT_DATA_PATH_ENV: &str = "STEAM_COMPAT_DATA_PATH";
-const LATEST_SELECTOR: &str = "latest";
-
-struct RunningApp {
-    appid: String,
-    path: PathBuf,
-    name: Option<String>,
-    install_path: Option<String>,
-    started_at: Option<u64>,
-}
-
-struct TargetApp {
-    appid: String,
-    app_dir: PathBuf,
-}
+const LD_PRELOAD_ENV: &str = "LD_PRELOAD";
+const VKBASALT_ENABLE_ENV: &str = "ENABLE_VKBASALT";
+const VKBASALT_CONFIG_ENV: &str = "VKBASALT_CONFIG_FILE";
+/// Обёртка obs-vkcapture: включает Vulkan-слой захвата и подгружает GL-захват.
+const OBS_GAMECAPTURE: &str = "obs-gamecapture";
+const OBS_VKCAPTURE_ENV: &str = "OBS_VKCAPTURE";
 
 struct InitCommand {
     tokens: Vec<String>,
@@ -50,516 +67,1005 @@ impl InitCommand {
     }
 }
 
-pub fn handle_init(phd: &Path, cmd: Vec<String>, debug: bool) -> io::Result<()> {
+pub fn handle_init(phd: &Path, cmd: Vec<String>, keep: bool) -> io::Result<()> {
     if cmd.is_empty() {
         print_subcommand_usage_error("init", "Не указана команда для запуска");
     }
 
-    let appid = required_env_var(STEAM_APP_ID_ENV, "init");
-    let app_dir = phd.join(&appid);
-    fs::create_dir_all(&app_dir)?;
-
-    // Сохраняем время старта (unix epoch, секунды).
-    fs::write(app_dir.join(STARTED_AT_FILE), unix_now_secs().to_string())?;
-
     let init_command = parse_init_command(cmd);
     let real_cmd = init_command.command();
-    // Находим путь к proton в аргументах.
-    let Some(proton_path) = real_cmd.iter().find(|arg| arg.contains("/proton")) else {
-        print_subcommand_usage_error("init", "Путь к proton не найден в команде");
+    // Вне Steam (umu-launcher) переменные Steam выводим из окружения umu.
+    let umu = env::var_os(STEAM_COMPAT_DATA_PATH_ENV)
+        .is_none()
+        .then(|| UmuLaunch::detect(real_cmd))
+        .flatten();
+    if let Some(umu) = &umu {
+        umu.export_steam_env();
+    }
+
+    let appid = match &umu {
+        Some(umu) => umu.game_id.clone(),
+        None => init_appid(),
     };
 
-    // Сохраняем данные.
-    fs::write(app_dir.join(EXE_FILE), proton_path)?;
+    // Всё, из-за чего `init` может отказаться запускать игру, проверяем до создания
+    // контекста.
+    let runner = init_runner(real_cmd, umu.as_ref());
 
-    // Сохраняем путь к pfx.
-    let compat_data = required_env_var(STEAM_COMPAT_DATA_PATH_ENV, "init");
-    fs::write(app_dir.join(PFX_FILE), format!("{compat_data}/pfx"))?;
+    if let Err(err) = ensure_runtime_root(phd) {
+        fail(&format!("Не удалось подготовить каталог контекстов: {err}"));
+    }
+    let pid = process::id();
+    // Недописанный контекст удаляется, если `init` упадёт раньше запуска игры.
+    let guard = ContextGuard::create(allocate_context_dir(phd, &appid, pid)?)?;
+    let app_dir = guard.path().to_path_buf();
+    // Пока контекст не записан целиком, `run` и другие читатели ждут.
+    let lock = ContextLock::exclusive(&app_dir)?;
+    fs::write(app_dir.join(PID_FILE), pid.to_string())?;
+
+    // Сохраняем время старта (unix epoch, секунды).
+    let started_at = unix_now_secs();
+    fs::write(app_dir.join(STARTED_AT_FILE), started_at.to_string())?;
 
-    // Сохраняем окружение в формате declare -x.
-    write_env_file(&app_dir)?;
+    // Сохраняем путь к proton и к pfx.
+    if let Some((proton_path, compat_data)) = &runner {
+        fs::write(app_dir.join(EXE_FILE), proton_path)?;
+        fs::write(app_dir.join(PFX_FILE), format!("{compat_data}/pfx"))?;
+    } else {
+        fs::write(app_dir.join(NATIVE_FILE), "")?;
+    }
+
+    // Сохраняем окружение в формате declare -x без секретов. Битый конфиг не должен
+    // мешать запуску игры, поэтому в этом случае остаются встроенные фильтры.
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{} {err}", "Предупреждение:".yellow().bold());
+        Config::default()
+    });
+    write_env_file(&app_dir, &config.env)?;
+    prune_logs(&config.logs);
+
+    // Метаданные из манифеста и сведения о запуске: `ls -l`, `info` и поиск по имени не
+    // будут выводить их заново.
+    let env_content = fs::read_to_string(app_dir.join(ENV_FILE)).unwrap_or_default();
+    let meta = cache_init_meta(
+        &app_dir,
+        &appid,
+        runner.as_ref().and_then(|(proton_path, compat_data)| {
+            context_proton_version(Path::new(proton_path), Path::new(compat_data))
+        }),
+        LaunchChain::parse(real_cmd, &env_content),
+    );
 
     // Выполняем исходную команду, учитывая возможные префиксные VAR=VALUE присваивания.
-    let mut child = process::Command::new(&real_cmd[0]);
-    child.args(&real_cmd[1..]);
+    let mut command = process::Command::new(&real_cmd[0]);
+    command.args(&real_cmd[1..]);
 
     for assign in init_command.env_assignments() {
         let Some((name, value)) = split_env_assignment(assign) else {
             continue;
         };
-        child.env(name, value);
+        command.env(name, value);
     }
 
-    if debug {
-        eprintln!(
-            "{} Executing command (argv): {real_cmd:?}",
-            "DEBUG".bold().cyan()
-        );
+    verbosity::verbose(format_args!("Executing command (argv): {real_cmd:?}"));
+
+    // Контекст фиксируется только после успешного запуска: если игра или обёртка не
+    // запустилась (ENOENT, EACCES), `guard` удалит каталог и в `ls` не останется призрака.
+    let mut child = command.spawn()?;
+    guard.commit();
+    drop(lock);
+    let presence = config.discord.as_ref().map(|discord| {
+        let name = meta.name.as_deref().unwrap_or(&appid);
+        Presence::publish_in_background(discord, name, started_at)
+    });
+    let status = child.wait()?;
+    if let Some(presence) = presence.and_then(|handle| handle.join().ok().flatten()) {
+        presence.clear();
     }
-
-    let status = child.status()?;
-
-    // Удаляем директорию.
-    let _ = fs::remove_dir_all(&app_dir);
+    let _lock = ContextLock::exclusive(&app_dir);
+
+    if keep {
+        // Контекст остаётся до следующего запуска игры: её `init` заменит его, так как
+        // процесс из `pid` уже завершён.
+        let _ = fs::write(app_dir.join(ENDED_AT_FILE), unix_now_secs().to_string());
+        if let Some(code) = status.code() {
+            let _ = fs::write(app_dir.join(EXIT_CODE_FILE), code.to_string());
+        }
+    } else {
+        let _ = fs::remove_dir_all(&app_dir);
+    }
+    audit::record(AuditAction::Init, &appid, started_at, status);
     exit_with_status(status);
 }
 
-pub fn handle_ls(phd: &Path, long: bool, json_output: bool) -> io::Result<()> {
-    let apps = collect_running_apps(phd, long || json_output)?;
+/// Вид вывода `ls`.
+pub struct LsOptions {
+    pub long: bool,
+    pub plain: bool,
+    /// Добавить зарегистрированные контексты и показать состояние каждого
+    /// (running/ended/stale/broken/registered).
+    pub all: bool,
+    /// Только контексты, чей id, appid или имя подходит под фильтр.
+    pub filter: Option<NameFilter>,
+}
 
+pub fn handle_ls(phd: &Path, json_output: bool, options: &LsOptions) -> io::Result<()> {
+    let with_meta = options.long || json_output || options.plain || options.filter.is_some();
+    let apps = ls_entries(phd, with_meta, options.all, options.filter.as_ref())?;
+    if with_meta {
+        let listed: Vec<&RunningApp> = apps.iter().map(|(app, _)| app).collect();
+        listing_cache::update(phd, &listed);
+    }
     if json_output {
         return print_ls_json(&apps);
     }
+    if options.plain {
+        print_ls_plain(&apps);
+        return Ok(());
+    }
+    for (app, status) in apps {
+        println!("{}", ls_line(app, status, options));
+    }
+    Ok(())
+}
 
-    for app in apps {
-        if !long {
-            println!("{}", app.appid.green());
-            continue;
-        }
-
-        let mut parts: Vec<String> = Vec::with_capacity(4);
-        parts.push(app.appid.green().to_string());
+/// Контексты с состоянием; с `all` — и зарегистрированные. Фильтру по имени нужны
+/// метаданные, поэтому с ним они читаются всегда.
+pub fn ls_entries(
+    phd: &Path,
+    with_meta: bool,
+    all: bool,
+    filter: Option<&NameFilter>,
+) -> io::Result<Vec<(RunningApp, ContextStatus)>> {
+    let with_meta = with_meta || filter.is_some();
+    let mut apps: Vec<(RunningApp, ContextStatus)> = collect_running_apps(phd, with_meta)?
+        .into_iter()
+        .map(|app| {
+            let status = app.status();
+            (app, status)
+        })
+        .collect();
+    if all {
+        apps.extend(
+            collect_running_apps(&registered_root(), with_meta)?
+                .into_iter()
+                .map(|app| {
+                    let status = if app.problems.is_empty() {
+                        ContextStatus::Registered
+                    } else {
+                        app.status()
+                    };
+                    (app, status)
+                }),
+        );
+    }
+    if let Some(filter) = filter {
+        apps.retain(|(app, _)| {
+            filter.matches(
+                [app.id.as_str(), app.appid.as_str()]
+                    .into_iter()
+                    .chain(app.name.as_deref()),
+            )
+        });
+    }
+    Ok(apps)
+}
 
-        if let Some(name) = app.name {
-            parts.push(name.yellow().to_string());
-        }
-        if let Some(install_path) = app.install_path {
-            parts.push(install_path.dimmed().to_string());
-        }
-        if let Some(started_at) = app.started_at {
-            parts.push(
-                format!("started {}", format_duration_ago(started_at))
-                    .dimmed()
-                    .to_string(),
-            );
+/// Строка человекочитаемого `ls` для одного контекста.
+pub fn ls_line(app: RunningApp, status: ContextStatus, options: &LsOptions) -> String {
+    let id = match status {
+        ContextStatus::Running | ContextStatus::Registered => app.id.green(),
+        ContextStatus::Ended | ContextStatus::Stale | ContextStatus::Broken => app.id.dimmed(),
+    };
+    let status_label = match status {
+        ContextStatus::Running => status.as_str().green(),
+        ContextStatus::Ended | ContextStatus::Stale | ContextStatus::Broken => {
+            status.as_str().red()
         }
-
-        println!("{}", parts.join("  "));
+        ContextStatus::Registered => status.as_str().cyan(),
+    };
+    if options.long {
+        long_line(app, &id, options.all.then_some(&status_label))
+    } else if options.all {
+        format!("{id} {status_label}")
+    } else if status == ContextStatus::Ended {
+        format!("{id} {}", "(ended)".red())
+    } else {
+        id.to_string()
     }
-
-    Ok(())
 }
 
-pub fn handle_run(phd: &Path, appid: &str, cmd: &[String]) -> io::Result<()> {
-    if cmd.is_empty() {
-        print_subcommand_usage_error("run", "Не указана команда для запуска");
+/// Строка `ls -l`: глиф исправности, id, состояние (с `--all`) и метаданные.
+fn long_line(app: RunningApp, id: &ColoredString, status_label: Option<&ColoredString>) -> String {
+    // Глиф исправности: видно, что `run` упадёт, ещё до запуска.
+    let glyph = if app.problems.is_empty() {
+        "✓".green()
+    } else {
+        "✗".red().bold()
+    };
+    let mut parts: Vec<String> = Vec::with_capacity(8);
+    parts.push(format!("{glyph} {id}"));
+    if let Some(status_label) = status_label {
+        parts.push(status_label.to_string());
     }
 
-    let target = prepare_context(phd, appid)?;
-    let exe = read_trimmed(target.app_dir.join(EXE_FILE))?;
-    let status = process::Command::new(exe).arg("run").args(cmd).status()?;
-    exit_with_status(status);
+    if let Some(name) = app.name {
+        parts.push(name.yellow().to_string());
+    }
+    if let Some(build_id) = app.build_id {
+        parts.push(format!("build {build_id}").dimmed().to_string());
+    }
+    if let Some(install_path) = app.install_path {
+        parts.push(install_path.dimmed().to_string());
+    }
+    if let Some(proton) = app.proton {
+        parts.push(proton.cyan().to_string());
+    }
+    if let Some(started_at) = app.started_at {
+        parts.push(
+            format!("started {}", format_duration_ago(started_at))
+                .dimmed()
+                .to_string(),
+        );
+    }
+    if let Some(ended_at) = app.ended_at {
+        let code = app
+            .exit_code
+            .map(|code| format!(", exit code {code}"))
+            .unwrap_or_default();
+        parts.push(
+            format!("ended {}{code}", format_duration_ago(ended_at))
+                .red()
+                .to_string(),
+        );
+    }
+    if !app.problems.is_empty() {
+        let problems: Vec<&str> = app
+            .problems
+            .iter()
+            .map(|problem| problem.as_str())
+            .collect();
+        parts.push(problems.join(", ").red().to_string());
+    }
+    parts.join("  ")
 }
 
-pub fn handle_cmd(phd: &Path, appid: &str) -> io::Result<()> {
-    let target = prepare_context(phd, appid)?;
-    let exe = read_trimmed(target.app_dir.join(EXE_FILE))?;
-    let pfx = read_trimmed(target.app_dir.join(PFX_FILE))?;
-    let cmd_exe = format!("{pfx}/drive_c/windows/system32/cmd.exe");
-
-    let status = process::Command::new(exe)
-        .arg("run")
-        .arg(cmd_exe)
-        .status()?;
-    exit_with_status(status);
+/// Параметры `run` помимо цели и команды.
+#[allow(clippy::struct_excessive_bools)]
+pub struct RunOptions {
+    pub no_auto: bool,
+    pub timeout: Option<Duration>,
+    /// Фильтр strace; пустая строка — трассировать всё.
+    pub strace: Option<String>,
+    /// Системный Wine вместо proton.
+    pub wine: Option<String>,
+    /// Переводить пути хоста в аргументах в Windows-пути.
+    pub win_paths: bool,
+    /// Библиотеки, дописываемые к `LD_PRELOAD`.
+    pub preload: Vec<String>,
+    /// Включить vkBasalt; непустая строка — путь к его конфигу.
+    pub vkbasalt: Option<String>,
+    /// Захват окна в OBS через obs-vkcapture.
+    pub obs_capture: bool,
+    /// Запуск в одноразовой копии префикса.
+    pub ephemeral: bool,
+    pub verb: ProtonVerb,
+    pub limits: ResourceLimits,
+    pub priority: Priority,
 }
 
-pub fn handle_exec(phd: &Path, appid: &str, cmd: &[String]) -> io::Result<()> {
-    if cmd.is_empty() {
-        print_subcommand_usage_error("exec", "Не указана команда для запуска");
+pub fn handle_run(phd: &Path, appid: &str, cmd: &[String], options: &RunOptions) -> io::Result<()> {
+    let plan = plan_run(phd, appid, cmd, options)?;
+    let mut ephemeral = plan.ephemeral;
+    if let Some(ephemeral) = &mut ephemeral {
+        ephemeral.mount()?;
     }
-
-    let _ = prepare_context(phd, appid)?;
-    let status = process::Command::new(&cmd[0]).args(&cmd[1..]).status()?;
+    let started_at = unix_now_secs();
+    let (mut child, strace_log) = match plan.action {
+        RunAction::ImportReg(reg_file) => {
+            let status = import_reg_file(
+                &plan.launcher,
+                Path::new(&plan.target.pfx()?),
+                &reg_file,
+                options.timeout,
+            )?;
+            audit::record(AuditAction::Run, &plan.target.appid, started_at, status);
+            if !status.success() {
+                drop(ephemeral);
+                exit_with_status(status);
+            }
+            return Ok(());
+        }
+        RunAction::Spawn {
+            child, strace_log, ..
+        } => (*child, strace_log),
+    };
+    if let Some(log) = &strace_log {
+        eprintln!("{} {}", "strace:".bold().cyan(), log.display());
+    }
+    verbosity::verbose(format_args!("argv: {child:?}"));
+    let status = run_with_timeout(&mut child, options.timeout).map_err(|err| {
+        if err.kind() != io::ErrorKind::NotFound {
+            err
+        } else if strace_log.is_some() {
+            io::Error::new(err.kind(), "strace не найден в PATH")
+        } else {
+            plan.launcher.not_found(err)
+        }
+    })?;
+    audit::record(AuditAction::Run, &plan.target.appid, started_at, status);
+    // exit не вызывает деструкторы: одноразовый префикс убираем заранее.
+    drop(ephemeral);
     exit_with_status(status);
 }
 
-pub fn handle_doctor(phd: &Path) -> io::Result<()> {
-    let mut warnings = 0usize;
-    let mut errors = 0usize;
+/// Собранный, но ещё не запущенный `run`: его выполняет `run` и описывает `explain run`.
+/// Окружение контекста к этому моменту уже применено к текущему процессу.
+pub struct RunPlan {
+    pub target: TargetApp,
+    pub launcher: Launcher,
+    /// Одноразовая compatdata для `--ephemeral`; собирается только перед запуском.
+    pub ephemeral: Option<EphemeralPrefix>,
+    pub action: RunAction,
+}
 
-    println!("{}", "protonhax doctor".bold());
+pub enum RunAction {
+    /// Одиночный `.reg`: импорт через `regedit /S`.
+    ImportReg(PathBuf),
+    Spawn {
+        child: Box<process::Command>,
+        /// Обёртки от внешней к внутренней: scope systemd, strace, приоритеты, запускатель.
+        wrappers: Vec<String>,
+        strace_log: Option<PathBuf>,
+    },
+}
 
-    println!("\nEnvironment:");
-    if let Ok(steam_app_id) = env::var(STEAM_APP_ID_ENV) {
-        doctor_ok(&format!("{STEAM_APP_ID_ENV}={steam_app_id}"));
+pub fn plan_run(
+    phd: &Path,
+    appid: &str,
+    cmd: &[String],
+    options: &RunOptions,
+) -> io::Result<RunPlan> {
+    let (appid, mut cmd) = split_implicit_target(phd, "run", appid, cmd);
+    let target = prepare_context(phd, &appid)?;
+    target.require_proton();
+    let launcher = Launcher::for_target(&target, options.wine.as_deref(), options.verb)?;
+
+    convert_path_args(
+        &launcher,
+        Path::new(&target.pfx()?),
+        &mut cmd,
+        options.win_paths,
+    );
+    let ephemeral = if options.ephemeral {
+        Some(plan_ephemeral(&target, &launcher, &mut cmd)?)
     } else {
-        doctor_info("SteamAppId не установлен (это нормально вне запуска через Steam)");
-    }
-
-    match env::var(STEAM_COMPAT_DATA_PATH_ENV) {
-        Ok(path) => {
-            if Path::new(&path).exists() {
-                doctor_ok(&format!("{STEAM_COMPAT_DATA_PATH_ENV}={path}"));
-            } else {
-                warnings += 1;
-                doctor_warn(&format!(
-                    "{STEAM_COMPAT_DATA_PATH_ENV} установлен, но путь не найден: {path}"
-                ));
-            }
-        }
-        Err(_) => {
-            doctor_info("STEAM_COMPAT_DATA_PATH не установлен (это нормально вне запуска игры)");
-        }
-    }
+        None
+    };
 
-    println!("\nRuntime:");
-    if phd.exists() {
-        doctor_ok(&format!("runtime root: {}", phd.display()));
-    } else {
-        warnings += 1;
-        doctor_warn(&format!(
-            "runtime root отсутствует: {} (ещё не было активных контекстов)",
-            phd.display()
-        ));
-    }
+    let install_workdir = locate_in_install_dir(&target, &mut cmd);
+    let cmd = cmd.as_slice();
 
-    println!("\nContexts:");
-    let apps = collect_running_apps(phd, true)?;
-    if apps.is_empty() {
-        warnings += 1;
-        doctor_warn("активных контекстов не найдено");
+    if !options.no_auto
+        && let Some(reg_file) = single_reg_file(cmd)
+    {
+        let action = RunAction::ImportReg(reg_file.to_path_buf());
+        return Ok(RunPlan {
+            target,
+            launcher,
+            ephemeral,
+            action,
+        });
     }
 
-    for app in &apps {
-        inspect_context(app, &mut warnings, &mut errors);
+    // Ограничения ресурсов задаются свойствами scope-юнита systemd; strace оборачивает
+    // обёртки из конфига и сам proton, чтобы в трассу попали все процессы Wine.
+    let config = Config::load()?;
+    let app = config.apps.get(&target.appid);
+    let mut wrappers = Vec::new();
+    let mut chain: Vec<OsString> = Vec::new();
+    let mut strace_log = None;
+    if let Some(filter) = &options.strace {
+        let (log, args) = strace_args(&target.appid, filter)?;
+        wrappers.push(format!("{STRACE} -f -o {}", log.display()));
+        strace_log = Some(log);
+        chain.push(STRACE.into());
+        chain.extend(args);
+    }
+    for wrapper in app.map(|app| app.wrappers.as_slice()).unwrap_or_default() {
+        let Ok(words) = shell_words::split(wrapper) else {
+            fail(&format!(
+                "Не удалось разобрать обёртку \"{wrapper}\" в [apps.{}]",
+                target.appid
+            ));
+        };
+        wrappers.push(wrapper.clone());
+        chain.extend(words.into_iter().map(OsString::from));
     }
+    if options.obs_capture
+        && let Some(gamecapture) = obs_gamecapture()
+    {
+        wrappers.push(OBS_GAMECAPTURE.to_string());
+        chain.push(gamecapture.into());
+    }
+    chain.push(launcher.program.clone().into());
+    let (program, chain_args) = chain.split_first().expect("chain ends with the launcher");
+    let mut child = if options.limits.is_empty() {
+        process::Command::new(program)
+    } else {
+        let unit = scope_unit_name(&target.appid, "run", process::id());
+        wrappers.insert(0, format!("systemd-run --scope ({unit})"));
+        command_in_scope(&unit, &options.limits, program)
+    };
+    child.args(chain_args);
+    let priority = options.priority.clone().or(app.map(|app| &app.priority));
+    wrappers.extend(priority.describe());
+    priority::apply(&priority, &mut child);
+    wrappers.push(launcher.describe());
+    child.args(launcher.args());
+    let workdir = if let Some(auto) = auto_run_command(cmd).filter(|_| !options.no_auto) {
+        wrappers.push(auto.cmd[..auto.cmd.len() - 1].join(" "));
+        child.args(&auto.cmd);
+        auto.workdir
+    } else {
+        child.args(cmd);
+        install_workdir
+    };
+    if let Some(workdir) = workdir {
+        child.current_dir(workdir);
+    }
+    apply_launch_env(&mut child, options)?;
+    let action = RunAction::Spawn {
+        child: Box::new(child),
+        wrappers,
+        strace_log,
+    };
+    Ok(RunPlan {
+        target,
+        launcher,
+        ephemeral,
+        action,
+    })
+}
 
-    println!(
-        "\nSummary: {} warning(s), {} error(s)",
-        warnings.to_string().yellow(),
-        errors.to_string().red()
+/// Переключает запуск на одноразовую compatdata: proton находит префикс по
+/// `STEAM_COMPAT_DATA_PATH`, Wine — по `WINEPREFIX`.
+fn plan_ephemeral(
+    target: &TargetApp,
+    launcher: &Launcher,
+    cmd: &mut [String],
+) -> io::Result<EphemeralPrefix> {
+    let pfx = PathBuf::from(target.pfx()?);
+    let compat_data = pfx.parent().unwrap_or(&pfx);
+    let ephemeral = EphemeralPrefix::plan(
+        &target.appid,
+        compat_data,
+        &target.exe().unwrap_or_default(),
+        target.wine(),
     );
-
-    if errors > 0 {
-        process::exit(1);
+    set_env_var(
+        STEAM_COMPAT_DATA_PATH_ENV,
+        &ephemeral.compat_data().to_string_lossy(),
+    );
+    if launcher.wine {
+        set_env_var(WINEPREFIX_ENV, &ephemeral.pfx().to_string_lossy());
     }
+    if let Some(program) = cmd.first_mut() {
+        *program = ephemeral.rebase(program);
+    }
+    Ok(ephemeral)
+}
 
-    Ok(())
+/// Чем запускать Windows-программы: `proton run` или системный Wine напрямую (`--wine`).
+pub struct Launcher {
+    pub program: String,
+    /// Команда proton; у Wine её нет.
+    verb: Option<ProtonVerb>,
+    pub wine: bool,
 }
 
-fn parse_init_command(cmd: Vec<String>) -> InitCommand {
-    // Steam иногда прокидывает %COMMAND% одной shell-строкой.
-    let tokens = if cmd.len() == 1 && cmd[0].contains(char::is_whitespace) {
-        shell_words::split(&cmd[0]).unwrap_or_else(|err| {
-            print_subcommand_usage_error("init", &format!("Не удалось разобрать команду: {err}"));
+impl Launcher {
+    /// Для Wine выставляет `WINEPREFIX` префикса контекста: обёртка proton делает
+    /// это сама.
+    fn for_target(target: &TargetApp, wine: Option<&str>, verb: ProtonVerb) -> io::Result<Self> {
+        // Контексты лаунчеров на чистом Wine (Heroic, Lutris) хранят свой бинарник.
+        let wine = wine.map(str::to_string).or_else(|| target.wine());
+        let Some(wine) = wine else {
+            return Ok(Self {
+                program: target.exe()?,
+                verb: Some(verb),
+                wine: false,
+            });
+        };
+        set_env_var(WINEPREFIX_ENV, &target.pfx()?);
+        Ok(Self {
+            program: wine,
+            verb: None,
+            wine: true,
         })
-    } else {
+    }
+
+    fn args(&self) -> Option<&'static str> {
+        self.verb.map(ProtonVerb::as_str)
+    }
+
+    fn command(&self) -> process::Command {
+        let mut cmd = process::Command::new(&self.program);
+        cmd.args(self.args());
         cmd
-    };
+    }
 
-    let Some(cmd_start_index) = tokens.iter().position(|arg| !is_env_assignment(arg)) else {
-        print_subcommand_usage_error(
-            "init",
-            "Не указана команда для запуска после присваиваний окружения",
-        );
-    };
+    /// Windows-путь для пути хоста: proton переводит его сам, для Wine — по `dosdevices`.
+    pub fn to_windows_path(&self, pfx: &Path, host_path: &Path) -> String {
+        to_windows_path(self.proton(), pfx, host_path)
+    }
 
-    InitCommand {
-        tokens,
-        cmd_start_index,
+    fn to_host_path(&self, pfx: &Path, windows_path: &str) -> Option<PathBuf> {
+        to_host_path(self.proton(), pfx, windows_path)
     }
-}
 
-fn prepare_context(phd: &Path, selector: &str) -> io::Result<TargetApp> {
-    let target = resolve_target_app(phd, selector)?;
-    set_env_var(STEAM_APP_ID_ENV, &target.appid);
-    load_env(&target.app_dir)?;
-    Ok(target)
-}
+    fn proton(&self) -> Option<&Path> {
+        (!self.wine).then(|| Path::new(&self.program))
+    }
 
-fn resolve_target_app(phd: &Path, selector: &str) -> io::Result<TargetApp> {
-    if selector.eq_ignore_ascii_case(LATEST_SELECTOR) {
-        return resolve_latest_app(phd);
+    /// Тихий импорт `.reg` по Windows-пути.
+    pub fn regedit(&self, windows_path: &str) -> process::Command {
+        let mut cmd = self.command();
+        cmd.args(["regedit", "/S", windows_path]);
+        cmd
     }
 
-    let app_dir = phd.join(selector);
-    if app_dir.is_dir() {
-        return Ok(TargetApp {
-            appid: selector.to_string(),
-            app_dir,
-        });
+    /// Запускатель без полного пути: `proton run` или `wine`.
+    pub fn describe(&self) -> String {
+        let name = Path::new(&self.program).file_name().map_or_else(
+            || self.program.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        [name.as_str()]
+            .into_iter()
+            .chain(self.args())
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
-    resolve_app_by_name(phd, selector)
+    fn not_found(&self, err: io::Error) -> io::Error {
+        if self.wine {
+            io::Error::new(err.kind(), format!("Wine не найден: {}", self.program))
+        } else {
+            err
+        }
+    }
 }
 
-fn resolve_latest_app(phd: &Path) -> io::Result<TargetApp> {
-    let apps = collect_running_apps(phd, false)?;
-    if apps.is_empty() {
+/// Обёртка obs-vkcapture для `--obs-capture`. Без неё остаётся Vulkan-слой захвата:
+/// DXVK и VKD3D-Proton всё равно рисуют через Vulkan.
+fn obs_gamecapture() -> Option<PathBuf> {
+    let found = find_in_path(OBS_GAMECAPTURE);
+    if found.is_none() {
         eprintln!(
-            "{} Нет активных контекстов. Сначала запустите игру через Steam.",
-            "Ошибка:".bold().red()
+            "{} {OBS_GAMECAPTURE} не найден — установите obs-vkcapture; захват только через {OBS_VKCAPTURE_ENV}=1",
+            "Предупреждение:".yellow().bold()
         );
-        process::exit(2);
     }
+    found
+}
 
-    if let Some(app) = apps
-        .iter()
-        .filter_map(|app| app.started_at.map(|started_at| (started_at, app)))
-        .max_by_key(|(started_at, _)| *started_at)
-        .map(|(_, app)| app)
-    {
-        return Ok(TargetApp {
-            appid: app.appid.clone(),
-            app_dir: app.path.clone(),
-        });
+/// Переменные для слоёв и библиотек, включаемых флагами `run` только для этого запуска.
+fn apply_launch_env(child: &mut process::Command, options: &RunOptions) -> io::Result<()> {
+    if !options.preload.is_empty() {
+        child.env(LD_PRELOAD_ENV, preload_value(&options.preload));
     }
-
-    if apps.len() == 1 {
-        let app = &apps[0];
-        return Ok(TargetApp {
-            appid: app.appid.clone(),
-            app_dir: app.path.clone(),
-        });
+    if options.obs_capture {
+        child.env(OBS_VKCAPTURE_ENV, "1");
     }
-
-    eprintln!(
-        "{} Невозможно определить latest: нет started_at у активных контекстов.",
-        "Ошибка:".bold().red()
-    );
-    eprintln!("Укажите appid явно (см. `protonhax ls -l`).");
-    process::exit(2);
+    if let Some(config) = &options.vkbasalt {
+        child.env(VKBASALT_ENABLE_ENV, "1");
+        if !config.is_empty() {
+            child.env(VKBASALT_CONFIG_ENV, vkbasalt_config(config)?);
+        }
+    }
+    Ok(())
 }
 
-fn resolve_app_by_name(phd: &Path, query: &str) -> io::Result<TargetApp> {
-    let apps = collect_running_apps(phd, true)?;
-    let matches: Vec<&RunningApp> = apps
-        .iter()
-        .filter(|app| {
-            app.name
-                .as_deref()
-                .is_some_and(|name| contains_case_insensitive(name, query))
-        })
-        .collect();
-
-    match matches.as_slice() {
-        [app] => Ok(TargetApp {
-            appid: app.appid.clone(),
-            app_dir: app.path.clone(),
-        }),
-        [] => {
+/// `LD_PRELOAD` контекста (там бывает оверлей Steam) с библиотеками `--preload` в конце.
+/// Отсутствующую библиотеку ld.so лишь пропустит с ошибкой в логе, поэтому предупреждаем
+/// заранее.
+fn preload_value(libs: &[String]) -> String {
+    for lib in libs {
+        // Имя без `/` ld.so ищет в системных каталогах библиотек — проверить его нечем.
+        if lib.contains('/') && !Path::new(lib).is_file() {
             eprintln!(
-                "{} Нет запущенного приложения с appid \"{query}\" и нет совпадений по имени.",
-                "Ошибка:".bold().red()
+                "{} библиотека для --preload не найдена: {lib}",
+                "Предупреждение:".yellow().bold()
             );
-            process::exit(2);
-        }
-        _ => {
-            print_ambiguous_matches(query, &matches);
-            process::exit(2);
         }
     }
+    env::var(LD_PRELOAD_ENV)
+        .ok()
+        .filter(|current| !current.trim().is_empty())
+        .into_iter()
+        .chain(libs.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(":")
 }
 
-fn print_ambiguous_matches(query: &str, matches: &[&RunningApp]) {
-    eprintln!(
-        "{} Несколько совпадений по имени \"{query}\":",
-        "Ошибка:".bold().red()
-    );
-    for app in matches {
-        let name = app.name.as_deref().unwrap_or("<без названия>");
-        eprintln!("  {}  {}", app.appid.green(), name.yellow());
+/// Абсолютный путь к конфигу vkBasalt: слой читает его уже из каталога игры, так что
+/// относительный путь там ничего бы не нашёл.
+fn vkbasalt_config(config: &str) -> io::Result<PathBuf> {
+    let path = Path::new(config);
+    if !path.is_file() {
+        fail(&format!("Конфиг vkBasalt не найден: {config}"));
     }
-    eprintln!("Уточните appid через `protonhax ls -l`.");
+    fs::canonicalize(path)
 }
 
-fn collect_running_apps(phd: &Path, with_meta: bool) -> io::Result<Vec<RunningApp>> {
-    if !phd.exists() {
-        return Ok(Vec::new());
-    }
+/// Приоритет из флагов, дополненный значениями `[apps.<appid>]` из конфига.
+fn app_priority(appid: &str, priority: Priority) -> io::Result<Priority> {
+    let config = Config::load()?;
+    Ok(priority.or(config.apps.get(appid).map(|app| &app.priority)))
+}
 
-    let mut apps = Vec::new();
-    for entry in fs::read_dir(phd)? {
-        let entry = entry?;
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
-        }
+fn single_reg_file(cmd: &[String]) -> Option<&Path> {
+    let [file] = cmd else {
+        return None;
+    };
+    let path = Path::new(file);
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("reg"))
+        .then_some(path)
+}
 
-        let appid = entry.file_name().to_string_lossy().to_string();
-        let meta = if with_meta {
-            resolve_app_meta(&path, &appid)
-        } else {
-            AppMeta::default()
-        };
-        let started_at = read_started_at(&path);
-
-        apps.push(RunningApp {
-            appid,
-            path,
-            name: meta.name,
-            install_path: meta.install_path,
-            started_at,
-        });
+/// Импортирует `.reg` через `regedit /S` и печатает список затронутых ключей; при
+/// ошибке regedit возвращает его статус без вывода.
+fn import_reg_file(
+    launcher: &Launcher,
+    pfx: &Path,
+    reg_file: &Path,
+    timeout: Option<Duration>,
+) -> io::Result<process::ExitStatus> {
+    let keys = parse_reg(&decode_reg_file(&fs::read(reg_file)?));
+    let windows_path = launcher.to_windows_path(pfx, reg_file);
+
+    let mut regedit = launcher.regedit(&windows_path);
+    let status = run_with_timeout(&mut regedit, timeout).map_err(|err| launcher.not_found(err))?;
+    if !status.success() {
+        return Ok(status);
     }
 
-    apps.sort_by(|left, right| left.appid.cmp(&right.appid));
-    Ok(apps)
+    println!(
+        "{} {} ({} keys)",
+        "Imported".green().bold(),
+        windows_path,
+        keys.len()
+    );
+    for key in keys {
+        // `[-KEY]` в .reg означает удаление ключа.
+        match key.path.strip_prefix('-') {
+            Some(path) => println!("  {} {path}", "-".red()),
+            None => println!(
+                "  {} {} {}",
+                "+".green(),
+                key.path,
+                format!("({} values)", key.values.len()).dimmed()
+            ),
+        }
+    }
+    Ok(status)
 }
 
-fn print_ls_json(apps: &[RunningApp]) -> io::Result<()> {
-    let data: Vec<_> = apps
-        .iter()
-        .map(|app| {
-            json!({
-                "appid": app.appid,
-                "name": app.name,
-                "install_path": app.install_path,
-                "started_at": app.started_at,
-                "started_ago": app.started_at.map(format_duration_ago),
-            })
-        })
-        .collect();
-
-    let serialized = serde_json::to_string_pretty(&data).map_err(io::Error::other)?;
-    println!("{serialized}");
-    Ok(())
+/// Инструменты вроде `SKSE64_loader.exe` ищем в каталоге игры и запускаем оттуда же:
+/// возвращается каталог найденной программы.
+fn locate_in_install_dir(target: &TargetApp, cmd: &mut [String]) -> Option<PathBuf> {
+    let install_path = target.meta().install_path?;
+    let program = cmd.first_mut()?;
+    let found = find_in_install_dir(Path::new(&install_path), program)?;
+    verbosity::info(format_args!(
+        "{program} найден в каталоге игры: {}",
+        found.display()
+    ));
+    *program = found.to_string_lossy().into_owned();
+    found.parent().map(Path::to_path_buf)
 }
 
-fn inspect_context(app: &RunningApp, warnings: &mut usize, errors: &mut usize) {
-    let title = match app.name.as_deref() {
-        Some(name) => format!("{} ({name})", app.appid),
-        None => app.appid.clone(),
+/// `C:\...` в качестве программы переводится в путь хоста: proton, автозапуск `.msi`/`.bat`
+/// и `.reg` работают с путями хоста. С `--win-paths` существующие пути хоста среди
+/// аргументов программы, наоборот, переводятся в Windows-пути.
+fn convert_path_args(launcher: &Launcher, pfx: &Path, cmd: &mut [String], win_paths: bool) {
+    let Some((program, args)) = cmd.split_first_mut() else {
+        return;
     };
-    println!("  {} {}", "•".cyan().bold(), title);
-
-    if let Ok(exe) = read_trimmed(app.path.join(EXE_FILE)) {
-        if Path::new(&exe).exists() {
-            doctor_ok(&format!("exe: {exe}"));
-        } else {
-            *errors += 1;
-            doctor_err(&format!("exe путь не существует: {exe}"));
-        }
-    } else {
-        *errors += 1;
-        doctor_err("файл exe отсутствует или не читается");
+    if is_windows_path(program)
+        && let Some(host) = launcher
+            .to_host_path(pfx, program)
+            .filter(|host| host.exists())
+    {
+        verbosity::info(format_args!("{program} -> {}", host.display()));
+        *program = host.to_string_lossy().into_owned();
     }
-
-    if let Ok(pfx) = read_trimmed(app.path.join(PFX_FILE)) {
-        if Path::new(&pfx).exists() {
-            doctor_ok(&format!("pfx: {pfx}"));
-        } else {
-            *warnings += 1;
-            doctor_warn(&format!("pfx путь не существует: {pfx}"));
-        }
-    } else {
-        *warnings += 1;
-        doctor_warn("файл pfx отсутствует или не читается");
+    if !win_paths {
+        return;
     }
-
-    if let Ok(env_content) = fs::read_to_string(app.path.join(ENV_FILE)) {
-        doctor_ok("env: файл окружения прочитан");
-        match get_env_var(&env_content, STEAM_COMPAT_DATA_PATH_ENV) {
-            Some(compat_data) if Path::new(&compat_data).exists() => {
-                doctor_ok(&format!("env.STEAM_COMPAT_DATA_PATH: {compat_data}"));
-            }
-            Some(compat_data) => {
-                *warnings += 1;
-                doctor_warn(&format!(
-                    "env.STEAM_COMPAT_DATA_PATH указывает на отсутствующий путь: {compat_data}"
-                ));
-            }
-            None => {
-                *warnings += 1;
-                doctor_warn("env: отсутствует STEAM_COMPAT_DATA_PATH");
-            }
+    for arg in args {
+        if !is_windows_path(arg) && Path::new(arg.as_str()).exists() {
+            *arg = launcher.to_windows_path(pfx, Path::new(arg.as_str()));
         }
-    } else {
-        *errors += 1;
-        doctor_err("файл env отсутствует или не читается");
-    }
-
-    if let Some(started_at) = app.started_at {
-        doctor_ok(&format!(
-            "started_at: {started_at} ({})",
-            format_duration_ago(started_at)
-        ));
-    } else {
-        *warnings += 1;
-        doctor_warn("started_at отсутствует или повреждён");
     }
 }
 
-fn doctor_ok(message: &str) {
-    println!("    {} {message}", "OK".green().bold());
+#[derive(Debug, PartialEq, Eq)]
+struct AutoRunCommand {
+    cmd: Vec<String>,
+    workdir: Option<PathBuf>,
 }
 
-fn doctor_warn(message: &str) {
-    println!("    {} {message}", "WARN".yellow().bold());
+/// Подбирает запускатель для файлов, которые proton не умеет запускать напрямую.
+fn auto_run_command(cmd: &[String]) -> Option<AutoRunCommand> {
+    let [file] = cmd else {
+        return None;
+    };
+    let path = Path::new(file);
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "msi" => Some(AutoRunCommand {
+            cmd: vec!["msiexec".to_string(), "/i".to_string(), file.clone()],
+            workdir: None,
+        }),
+        // Батники обычно ссылаются на файлы рядом с собой относительными путями,
+        // поэтому запускаем их из собственного каталога.
+        "bat" | "cmd" => {
+            let workdir = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf);
+            let script = match workdir {
+                Some(_) => path.file_name()?.to_str()?.to_string(),
+                None => file.clone(),
+            };
+            Some(AutoRunCommand {
+                cmd: vec!["cmd.exe".to_string(), "/c".to_string(), script],
+                workdir,
+            })
+        }
+        _ => None,
+    }
 }
 
-fn doctor_err(message: &str) {
-    println!("    {} {message}", "ERR".red().bold());
+pub fn handle_cmd(phd: &Path, appid: &str, wine: Option<&str>) -> io::Result<()> {
+    let target = prepare_context(phd, appid)?;
+    let launcher = Launcher::for_target(&target, wine, ProtonVerb::Run)?;
+    let pfx = target.pfx()?;
+    let cmd_exe = format!("{pfx}/drive_c/windows/system32/cmd.exe");
+
+    let mut child = launcher.command();
+    child.arg(cmd_exe);
+    verbosity::verbose(format_args!("argv: {child:?}"));
+    let status = child.status().map_err(|err| launcher.not_found(err))?;
+    exit_with_status(status);
 }
 
-fn doctor_info(message: &str) {
-    println!("    {} {message}", "INFO".cyan().bold());
+pub fn handle_exec(
+    phd: &Path,
+    appid: &str,
+    cmd: &[String],
+    timeout: Option<Duration>,
+    priority: Priority,
+    sandbox: bool,
+) -> io::Result<()> {
+    let (appid, cmd) = split_implicit_target(phd, "exec", appid, cmd);
+    let target = prepare_context(phd, &appid)?;
+    let mut child = if sandbox {
+        sandboxed_command(&target, &cmd)?
+    } else {
+        let mut child = process::Command::new(&cmd[0]);
+        child.args(&cmd[1..]);
+        child
+    };
+    priority::apply(&app_priority(&target.appid, priority)?, &mut child);
+    verbosity::verbose(format_args!("argv: {child:?}"));
+    let started_at = unix_now_secs();
+    let status = run_with_timeout(&mut child, timeout)?;
+    audit::record(AuditAction::Exec, &target.appid, started_at, status);
+    exit_with_status(status);
 }
 
-fn write_env_file(app_dir: &Path) -> io::Result<()> {
-    let env_path = app_dir.join(ENV_FILE);
-    let mut env_file = fs::File::create(env_path)?;
-    let mut vars: Vec<_> = env::vars().collect();
-    vars.sort_unstable_by(|left, right| left.0.cmp(&right.0));
+fn parse_init_command(cmd: Vec<String>) -> InitCommand {
+    // Steam иногда прокидывает %COMMAND% одной shell-строкой.
+    let tokens = if cmd.len() == 1 && cmd[0].contains(char::is_whitespace) {
+        shell_words::split(&cmd[0]).unwrap_or_else(|err| {
+            print_subcommand_usage_error("init", &format!("Не удалось разобрать команду: {err}"));
+        })
+    } else {
+        cmd
+    };
 
-    for (key, value) in vars {
-        let escaped_value = shell_escape(&value);
-        writeln!(env_file, "declare -x {key}={escaped_value}")?;
-    }
+    let Some(cmd_start_index) = tokens.iter().position(|arg| !is_env_assignment(arg)) else {
+        print_subcommand_usage_error(
+            "init",
+            "Не указана команда для запуска после присваиваний окружения",
+        );
+    };
 
-    Ok(())
+    InitCommand {
+        tokens,
+        cmd_start_index,
+    }
 }
 
-fn read_trimmed<P: AsRef<Path>>(path: P) -> io::Result<String> {
-    Ok(fs::read_to_string(path)?.trim().to_string())
-}
+fn print_ls_json(apps: &[(RunningApp, ContextStatus)]) -> io::Result<()> {
+    let data: Vec<_> = apps
+        .iter()
+        .map(|(app, status)| {
+            json!({
+                "id": app.id,
+                "appid": app.appid,
+                "name": app.name,
+                "install_path": app.install_path,
+                "build_id": app.build_id,
+                "proton": app.proton,
+                "started_at": app.started_at,
+                "started_ago": app.started_at.map(format_duration_ago),
+                "ended_at": app.ended_at,
+                "exit_code": app.exit_code,
+                "pid": app.pid,
+                "status": status.as_str(),
+                "problems": app.problems.iter().map(|problem| problem.as_str()).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
 
-fn read_started_at(app_dir: &Path) -> Option<u64> {
-    let val = fs::read_to_string(app_dir.join(STARTED_AT_FILE)).ok()?;
-    val.trim().parse::<u64>().ok()
+    print_json(&data)
 }
 
-fn contains_case_insensitive(text: &str, query: &str) -> bool {
-    if text.is_ascii() && query.is_ascii() {
-        return contains_ascii_case_insensitive(text.as_bytes(), query.as_bytes());
+/// Формат `--plain` стабилен: столбцы только добавляются в конец, пустое значение — пустая ячейка.
+fn print_ls_plain(apps: &[(RunningApp, ContextStatus)]) {
+    for (app, status) in apps {
+        let started_at = app.started_at.map(|secs| secs.to_string());
+        let ended_at = app.ended_at.map(|secs| secs.to_string());
+        let columns = [
+            Some(app.id.as_str()),
+            Some(app.appid.as_str()),
+            app.name.as_deref(),
+            app.install_path.as_deref(),
+            started_at.as_deref(),
+            ended_at.as_deref(),
+            Some(status.as_str()),
+            app.proton.as_deref(),
+        ];
+        let line: Vec<String> = columns
+            .iter()
+            .map(|column| plain_cell(column.unwrap_or_default()))
+            .collect();
+        println!("{}", line.join("\t"));
     }
-
-    text.to_lowercase().contains(&query.to_lowercase())
 }
 
-fn contains_ascii_case_insensitive(text: &[u8], query: &[u8]) -> bool {
-    query.is_empty()
-        || text
-            .windows(query.len())
-            .any(|window| window.eq_ignore_ascii_case(query))
+fn plain_cell(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
 }
 
-fn required_env_var(name: &str, command: &str) -> String {
-    match env::var(name) {
-        Ok(value) => value,
-        Err(_) => print_subcommand_usage_error(command, &format!("{name} не установлен")),
+/// Путь к proton и compatdata для `init`; `None` — нативная игра (ни proton в команде,
+/// ни compatdata): она запускается как есть, а в контексте остаётся окружение для `exec`.
+fn init_runner(real_cmd: &[String], umu: Option<&UmuLaunch>) -> Option<(String, String)> {
+    // Путь к proton ищем в аргументах (у umu — по `PROTONPATH`).
+    let proton_path = real_cmd
+        .iter()
+        .find(|arg| arg.contains("/proton"))
+        .cloned()
+        .or_else(|| {
+            umu.and_then(|umu| umu.proton.as_ref())
+                .map(|proton| proton.to_string_lossy().into_owned())
+        });
+    match (proton_path, env::var(STEAM_COMPAT_DATA_PATH_ENV).ok()) {
+        (Some(proton_path), Some(compat_data)) => Some((proton_path, compat_data)),
+        (None, None) => {
+            verbosity::info(format_args!("proton не найден в команде: нативный запуск"));
+            None
+        }
+        (None, Some(_)) => print_subcommand_usage_error(
+            "init",
+            "Путь к proton не найден в команде (для umu задайте PROTONPATH)",
+        ),
+        (Some(_), None) => print_subcommand_usage_error(
+            "init",
+            &format!("{STEAM_COMPAT_DATA_PATH_ENV} не установлен"),
+        ),
     }
 }
 
-fn print_subcommand_usage_error(subcommand: &str, message: &str) -> ! {
-    eprintln!("{} {message}", "Ошибка:".bold().red());
-    sub_usage(subcommand);
-    process::exit(1);
+/// appid для `init`: `SteamAppId`, а если обёртка его не задала — `STEAM_COMPAT_APP_ID`
+/// или имя каталога compatdata (у Steam это и есть appid).
+fn init_appid() -> String {
+    let non_empty = |name| env::var(name).ok().filter(|value| !value.is_empty());
+    let steam_app_id = non_empty(STEAM_APP_ID_ENV);
+    let derived = steam_app_id.is_none();
+    let Some(appid) = derive_appid(
+        steam_app_id,
+        non_empty(STEAM_COMPAT_APP_ID_ENV),
+        non_empty(STEAM_COMPAT_DATA_PATH_ENV).as_deref(),
+    ) else {
+        print_subcommand_usage_error(
+            "init",
+            &format!(
+                "{STEAM_APP_ID_ENV}, {STEAM_COMPAT_APP_ID_ENV} и {STEAM_COMPAT_DATA_PATH_ENV} не установлены"
+            ),
+        );
+    };
+    if derived {
+        verbosity::verbose(format_args!(
+            "{STEAM_APP_ID_ENV} не установлен, appid {appid}"
+        ));
+        // Окружение контекста должно выглядеть так же, как при запуске из Steam.
+        set_env_var(STEAM_APP_ID_ENV, &appid);
+    }
+    appid
 }
 
-fn exit_with_status(status: process::ExitStatus) -> ! {
-    process::exit(status.code().unwrap_or(1));
+fn derive_appid(
+    steam_app_id: Option<String>,
+    compat_app_id: Option<String>,
+    compat_data: Option<&str>,
+) -> Option<String> {
+    steam_app_id.or(compat_app_id).or_else(|| {
+        Path::new(compat_data?)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::contains_case_insensitive;
+    use super::{AutoRunCommand, auto_run_command, derive_appid, plain_cell};
+    use std::path::PathBuf;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
 
     #[test]
-    fn case_insensitive_search() {
-        assert!(contains_case_insensitive("Gunfire Reborn", "gunfire"));
-        assert!(contains_case_insensitive("GUNFIRE REBORN", "reborn"));
-        assert!(!contains_case_insensitive("Gunfire Reborn", "helldivers"));
+    fn wraps_single_msi_into_msiexec() {
+        assert_eq!(
+            auto_run_command(&args(&["/tmp/Setup.MSI"])),
+            Some(AutoRunCommand {
+                cmd: args(&["msiexec", "/i", "/tmp/Setup.MSI"]),
+                workdir: None,
+            })
+        );
+        assert_eq!(auto_run_command(&args(&["setup.msi", "/quiet"])), None);
+        assert_eq!(auto_run_command(&args(&["game.exe"])), None);
+    }
+
+    #[test]
+    fn wraps_batch_scripts_into_cmd() {
+        assert_eq!(
+            auto_run_command(&args(&["/mods/tool/install.BAT"])),
+            Some(AutoRunCommand {
+                cmd: args(&["cmd.exe", "/c", "install.BAT"]),
+                workdir: Some(PathBuf::from("/mods/tool")),
+            })
+        );
+        assert_eq!(
+            auto_run_command(&args(&["run.cmd"])).and_then(|auto| auto.workdir),
+            None
+        );
+    }
+
+    #[test]
+    fn plain_cells_have_no_separators() {
+        assert_eq!(plain_cell("Name\twith\ntabs"), "Name with tabs");
+    }
+
+    #[test]
+    fn derives_appid_without_steam_app_id() {
+        let some = |value: &str| Some(value.to_string());
+        assert_eq!(
+            derive_appid(some("620"), some("1"), Some("/c/2")),
+            some("620")
+        );
+        assert_eq!(derive_appid(None, some("1"), Some("/c/2")), some("1"));
+        assert_eq!(
+            derive_appid(None, None, Some("/steamapps/compatdata/620/")),
+            some("620")
+        );
+        assert_eq!(derive_appid(None, None, None), None);
     }
 }
@@ -0,0 +1,213 @@
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    external::{ExternalGame, Runner, expand_home},
+    runtime::{config_dir_base, home_dir, parallel_map},
+};
+
+const LAUNCHER: &str = "heroic";
+/// Конфиг Heroic из Flatpak (относительно `$HOME`).
+const FLATPAK_CONFIG: &str = ".var/app/com.heroicgameslauncher.hgl/config/heroic";
+/// Установленные игры Epic (legendary) и GOG.
+const EPIC_INSTALLED: &str = "legendaryConfig/legendary/installed.json";
+const GOG_INSTALLED: &str = "gog_store/installed.json";
+const GOG_LIBRARY: &str = "gog_store/library.json";
+const GAMES_CONFIG_DIR: &str = "GamesConfig";
+const GLOBAL_CONFIG: &str = "config.json";
+
+struct InstalledGame {
+    app_name: String,
+    title: Option<String>,
+    install_path: Option<String>,
+}
+
+/// Windows-игры Epic и GOG из всех найденных конфигов Heroic (нативного и Flatpak).
+pub fn installed_games() -> Vec<ExternalGame> {
+    let mut games: Vec<ExternalGame> = Vec::new();
+    for root in config_roots() {
+        let defaults = read_json(&root.join(GLOBAL_CONFIG))
+            .and_then(|config| config.get("defaultSettings").cloned());
+        let mut installed = read_json(&root.join(EPIC_INSTALLED))
+            .map(|json| parse_epic_installed(&json))
+            .unwrap_or_default();
+        installed.extend(
+            read_json(&root.join(GOG_INSTALLED))
+                .map(|json| {
+                    let library = read_json(&root.join(GOG_LIBRARY));
+                    parse_gog_installed(&json, library.as_ref())
+                })
+                .unwrap_or_default(),
+        );
+
+        // Конфиг у каждой игры свой: читаем их параллельно.
+        let resolved = parallel_map(&installed, |game| {
+            let config = read_json(
+                &root
+                    .join(GAMES_CONFIG_DIR)
+                    .join(format!("{}.json", game.app_name)),
+            );
+            let name = game.title.clone().unwrap_or_else(|| game.app_name.clone());
+            let settings = config.as_ref().and_then(|json| json.get(&game.app_name));
+            let runner = game_runner(settings, defaults.as_ref(), &name)?;
+            Some(ExternalGame {
+                id: format!("{LAUNCHER}-{}", game.app_name),
+                launcher: LAUNCHER,
+                name,
+                install_path: game.install_path.clone(),
+                runner,
+            })
+        });
+        for game in resolved.into_iter().flatten() {
+            if !games.iter().any(|known| known.id == game.id) {
+                games.push(game);
+            }
+        }
+    }
+    games
+}
+
+fn config_roots() -> Vec<PathBuf> {
+    [
+        config_dir_base().join("heroic"),
+        home_dir().join(FLATPAK_CONFIG),
+    ]
+    .into_iter()
+    .filter(|root| root.is_dir())
+    .collect()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// `installed.json` legendary: объект `appName -> {title, install_path, platform}`.
+fn parse_epic_installed(json: &Value) -> Vec<InstalledGame> {
+    let Some(games) = json.as_object() else {
+        return Vec::new();
+    };
+    games
+        .iter()
+        .filter(|(_, game)| is_windows(game))
+        .map(|(app_name, game)| InstalledGame {
+            app_name: app_name.clone(),
+            title: string_field(game, "title"),
+            install_path: string_field(game, "install_path"),
+        })
+        .collect()
+}
+
+/// `installed.json` GOG: `{"installed": [{appName, install_path, platform}]}`; названия
+/// берутся из кэша библиотеки.
+fn parse_gog_installed(json: &Value, library: Option<&Value>) -> Vec<InstalledGame> {
+    let Some(games) = json.get("installed").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let title_of = |app_name: &str| {
+        library?
+            .get("games")?
+            .as_array()?
+            .iter()
+            .find(|game| game.get("app_name").and_then(Value::as_str) == Some(app_name))
+            .and_then(|game| string_field(game, "title"))
+    };
+    games
+        .iter()
+        .filter(|game| is_windows(game))
+        .filter_map(|game| {
+            let app_name = string_field(game, "appName")?;
+            Some(InstalledGame {
+                title: title_of(&app_name),
+                install_path: string_field(game, "install_path"),
+                app_name,
+            })
+        })
+        .collect()
+}
+
+fn is_windows(game: &Value) -> bool {
+    game.get("platform")
+        .and_then(Value::as_str)
+        .is_some_and(|platform| platform.eq_ignore_ascii_case("windows"))
+}
+
+fn string_field(json: &Value, key: &str) -> Option<String> {
+    json.get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Префикс и сборка Wine/Proton из настроек игры; без них — из глобальных настроек,
+/// где префикс игры — `<defaultWinePrefix>/<название>`.
+fn game_runner(settings: Option<&Value>, defaults: Option<&Value>, name: &str) -> Option<Runner> {
+    let prefix = settings
+        .and_then(|settings| string_field(settings, "winePrefix"))
+        .map(|prefix| expand_home(&prefix))
+        .or_else(|| {
+            let default_prefix = string_field(defaults?, "defaultWinePrefix")?;
+            Some(expand_home(&default_prefix).join(name))
+        })?;
+    let wine_version = settings
+        .and_then(|settings| settings.get("wineVersion"))
+        .or_else(|| defaults?.get("wineVersion"))?;
+    let binary = expand_home(&string_field(wine_version, "bin")?);
+
+    Some(match wine_version.get("type").and_then(Value::as_str) {
+        Some("proton") => Runner::Proton {
+            script: binary,
+            compat_data: prefix,
+        },
+        _ => Runner::Wine { binary, prefix },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{game_runner, parse_epic_installed, parse_gog_installed};
+    use crate::external::Runner;
+    use serde_json::json;
+    use std::path::Path;
+
+    #[test]
+    fn parses_installed_games() {
+        let epic = json!({
+            "Fortnite": {"title": "Fortnite", "install_path": "/g/Fortnite", "platform": "Windows"},
+            "Mac": {"title": "Mac only", "platform": "Mac"},
+        });
+        let epic = parse_epic_installed(&epic);
+        assert_eq!(epic.len(), 1);
+        assert_eq!(epic[0].app_name, "Fortnite");
+
+        let gog = json!({"installed": [{"appName": "1207658930", "platform": "windows"}]});
+        let library = json!({"games": [{"app_name": "1207658930", "title": "Witcher"}]});
+        let gog = parse_gog_installed(&gog, Some(&library));
+        assert_eq!(gog[0].title.as_deref(), Some("Witcher"));
+    }
+
+    #[test]
+    fn picks_runner_from_game_settings() {
+        let settings = json!({
+            "winePrefix": "/p/Game",
+            "wineVersion": {"bin": "/t/GE-Proton9/proton", "type": "proton"},
+        });
+        let Some(Runner::Proton { compat_data, .. }) = game_runner(Some(&settings), None, "Game")
+        else {
+            panic!("expected proton runner");
+        };
+        assert_eq!(compat_data, Path::new("/p/Game"));
+
+        let defaults = json!({
+            "defaultWinePrefix": "/p",
+            "wineVersion": {"bin": "/usr/bin/wine", "type": "wine"},
+        });
+        let Some(Runner::Wine { prefix, .. }) = game_runner(None, Some(&defaults), "Other") else {
+            panic!("expected wine runner");
+        };
+        assert_eq!(prefix, Path::new("/p/Other"));
+    }
+}
@@ -0,0 +1,329 @@
+use clap::Command;
+use std::env;
+
+/// Язык описаний команд в `--help`, автодополнении и man-страницах.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Ru,
+}
+
+impl Lang {
+    /// Язык из `LC_ALL`, `LC_MESSAGES` или `LANG` (первая непустая), как у gettext;
+    /// всё, кроме русского, — английский.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()));
+        Self::from_locale(locale.as_deref().unwrap_or(""))
+    }
+
+    fn from_locale(locale: &str) -> Self {
+        if locale.starts_with("ru") {
+            Self::Ru
+        } else {
+            Self::En
+        }
+    }
+}
+
+/// Русские описания команд: путь подкоманды через пробел (пустой — сама protonhax).
+/// Английские остаются в doc-комментариях `cli.rs`.
+const RU: &[(&str, &str)] = &[
+    ("", "Запуск сторонних программ внутри Proton игры Steam"),
+    (
+        "init",
+        "Вызывается только Steam через «protonhax init %COMMAND%»",
+    ),
+    ("ls", "Список запущенных игр"),
+    ("run", "Запускает <cmd> через proton в контексте <target>"),
+    ("cmd", "Запускает cmd.exe в контексте <target>"),
+    ("exec", "Запускает <cmd> в контексте <target>"),
+    (
+        "explain",
+        "Показывает, как `run` запустил бы <cmd>: proton, argv, рабочий каталог, обёртки и отличия окружения от текущей оболочки — ничего не запуская",
+    ),
+    ("explain run", "Разобрать `run` с теми же аргументами"),
+    (
+        "batch",
+        "Выполняет команды из файла или stdin по одной в контексте <target>",
+    ),
+    ("completions", "Скрипты автодополнения для оболочек"),
+    (
+        "man",
+        "man-страницы в формате roff (без --dir печатает страницу верхнего уровня)",
+    ),
+    (
+        "wineboot",
+        "Запускает `wineboot` в префиксе игры через proton — например, перезагрузку после импорта реестра или установки DLL",
+    ),
+    (
+        "pause",
+        "Замораживает процессы игры (SIGSTOP), например чтобы освободить процессор",
+    ),
+    ("resume", "Размораживает процессы после `pause` (SIGCONT)"),
+    (
+        "killwin",
+        "Принудительно завершает процесс Windows в префиксе по имени образа (`taskkill /F /IM`)",
+    ),
+    (
+        "debug",
+        "Запускает winedbg в контексте <target> (интерактивно без --attach/--launch)",
+    ),
+    (
+        "crashdump",
+        "Собирает логи Proton, трассировки сбоев, очищенное окружение и сведения о системе в tar.gz",
+    ),
+    (
+        "saves",
+        "Вероятные каталоги сохранений игры (профиль префикса и Steam Cloud), с архивированием",
+    ),
+    ("env", "Просмотр и правка сохранённого окружения контекста"),
+    (
+        "env diff",
+        "Переменные, добавленные (+), удалённые (-) и изменённые (~) относительно другого контекста или текущей оболочки",
+    ),
+    (
+        "env edit",
+        "Открывает сохранённое окружение в `$EDITOR` и записывает обратно после проверки синтаксиса",
+    ),
+    (
+        "env source",
+        "Печатает окружение игры присваиваниями оболочки для `eval` (или direnv)",
+    ),
+    (
+        "audit",
+        "Журнал вызовов init/run/exec/killwin/stop (`$XDG_STATE_HOME/protonhax/audit.log`)",
+    ),
+    ("audit show", "Последние записи, от старых к новым"),
+    (
+        "export",
+        "Пишет самостоятельный скрипт оболочки, воспроизводящий контекст (окружение, proton, префикс)",
+    ),
+    (
+        "stop",
+        "Мягко останавливает игру: просит процессы Windows завершиться, чтобы игра успела сохраниться, затем SIGTERM и SIGKILL",
+    ),
+    (
+        "restart",
+        "Мягко останавливает игру (как `stop`), запускает её снова через Steam и сообщает новый контекст",
+    ),
+    (
+        "reinit",
+        "Перечитывает путь proton и окружение запущенной игры в её контекст",
+    ),
+    (
+        "register",
+        "Сохраняет контекст, чтобы `run`/`cmd`/`exec` работали и после выхода из игры",
+    ),
+    ("prefix", "Отдельные префиксы, не привязанные к игре Steam"),
+    (
+        "prefix new",
+        "Создаёт префикс через `proton run wineboot` и регистрирует его как контекст <name>",
+    ),
+    (
+        "prefix verify",
+        "Проверяет префикс: файлы реестра, версию Windows и битые симлинки",
+    ),
+    (
+        "prefix repair",
+        "Восстанавливает префикс через `wineboot -u` (программы и сохранения остаются) и проверяет его",
+    ),
+    (
+        "protons",
+        "Установленные сборки Proton: от Valve из библиотек Steam и свои из `compatibilitytools.d`",
+    ),
+    (
+        "protons install",
+        "Скачивает GE-Proton, проверяет SHA-512 и распаковывает в `compatibilitytools.d`",
+    ),
+    (
+        "protons remove",
+        "Удаляет свою сборку из `compatibilitytools.d`",
+    ),
+    ("doctor", "Проверяет контексты и окружение"),
+    (
+        "setup",
+        "Прописывает «protonhax init %command%» в параметры запуска игр на Proton, убирает его (--remove) или переводит старые вызовы (--migrate); без аргументов показывает список. Steam должен быть закрыт",
+    ),
+    (
+        "tool",
+        "Зарегистрированные внешние инструменты (Cheat Engine, мод-менеджеры, ...)",
+    ),
+    (
+        "tool add",
+        "Регистрирует (или заменяет) инструмент в конфиге",
+    ),
+    (
+        "tool detect",
+        "Ищет в каталоге игры и в префиксе известные инструменты и предлагает их зарегистрировать",
+    ),
+    ("tool list", "Список зарегистрированных инструментов"),
+    (
+        "tool run",
+        "Запускает зарегистрированный инструмент в контексте <target>",
+    ),
+    (
+        "shortcut",
+        "Ярлыки Steam для запусков protonhax (Big Picture, игровой режим)",
+    ),
+    (
+        "shortcut add",
+        "Добавляет в `shortcuts.vdf` ярлык, запускающий `protonhax run <appid> <cmd>`; Steam должен быть закрыт",
+    ),
+    (
+        "nxm",
+        "Передаёт ссылки nxm:// (Nexus Mods) мод-менеджеру внутри контекста",
+    ),
+    (
+        "nxm install",
+        "Регистрирует protonhax обработчиком x-scheme-handler/nxm",
+    ),
+    (
+        "nxm handle",
+        "Обрабатывает ссылку nxm:// (вызывается из desktop-файла)",
+    ),
+    (
+        "mount",
+        "Показывает каталог хоста в префиксе: симлинк по пути Windows (`C:\\Mods`) или новый диск (`M:`)",
+    ),
+    ("unmount", "Удаляет симлинк, созданный `mount`"),
+    ("inject", "Установка инжекторов (ReShade) в каталог игры"),
+    (
+        "inject reshade",
+        "Копирует ReShade к исполняемому файлу игры и настраивает переопределение DLL",
+    ),
+    (
+        "pt",
+        "Замена protontricks: winetricks или команда оболочки в префиксе игры",
+    ),
+    (
+        "install-deps",
+        "Ставит распространённые зависимости (corefonts, vcrun2022, dotnet48, ...) через winetricks; без имён показывает список",
+    ),
+    (
+        "info",
+        "Всё об одном контексте: имя, каталог установки, префикс, сборка proton, время работы, логи",
+    ),
+    (
+        "monitor",
+        "Поток событий контекстов (создан, удалён); с `--json` — объект JSON на строку",
+    ),
+    (
+        "prompt",
+        "Короткая сводка для приглашения оболочки: контекст оболочки или число запущенных игр",
+    ),
+    (
+        "du",
+        "Место на диске: префикс, кэш шейдеров и каталог установки",
+    ),
+    (
+        "open",
+        "Открывает каталог игры или префикса в файловом менеджере",
+    ),
+    (
+        "cd",
+        "Печатает каталог игры или префикса для `cd \"$(protonhax cd --print ...)\"` (см. `phcd`)",
+    ),
+    (
+        "shortcuts",
+        "Ярлыки меню «Пуск» и рабочего стола в префиксе (`shortcuts run` запускает ярлык)",
+    ),
+    ("shortcuts run", "Запускает ярлык через proton"),
+    (
+        "programs",
+        "Программы Windows, установленные в префикс (ключи реестра Uninstall)",
+    ),
+    (
+        "snapshot",
+        "Создание, просмотр, восстановление и сравнение снимков префикса",
+    ),
+    (
+        "snapshot create",
+        "Снимок префикса (reflink-копия или снимок btrfs для подтомов)",
+    ),
+    ("snapshot list", "Список снимков префикса"),
+    ("snapshot restore", "Заменяет префикс снимком"),
+    (
+        "snapshot diff",
+        "Файлы, изменившиеся между двумя снимками (или снимком и текущим префиксом)",
+    ),
+];
+
+fn catalog(lang: Lang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        Lang::En => &[],
+        Lang::Ru => RU,
+    }
+}
+
+/// Подставляет описания из каталога `lang` в команду и все её подкоманды; команды без
+/// перевода остаются с английскими.
+pub fn localize(cmd: Command, lang: Lang) -> Command {
+    localize_at(cmd, "", catalog(lang))
+}
+
+fn localize_at(mut cmd: Command, path: &str, catalog: &[(&str, &'static str)]) -> Command {
+    if let Some((_, about)) = catalog.iter().find(|(key, _)| *key == path) {
+        // Длинное английское описание перекрыло бы перевод в `--help`.
+        cmd = cmd.about(*about).long_about(None);
+    }
+    let names: Vec<String> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        let sub_path = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{path} {name}")
+        };
+        cmd = cmd.mut_subcommand(&name, |sub| localize_at(sub, &sub_path, catalog));
+    }
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lang, RU, localize};
+    use crate::cli::Cli;
+    use clap::{Command, CommandFactory};
+
+    #[test]
+    fn detects_language_from_locale() {
+        assert_eq!(Lang::from_locale("ru_RU.UTF-8"), Lang::Ru);
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("C"), Lang::En);
+        assert_eq!(Lang::from_locale(""), Lang::En);
+    }
+
+    /// Каждая видимая команда переведена, и в каталоге нет ключей удалённых команд.
+    #[test]
+    fn russian_catalog_matches_commands() {
+        fn visible_paths(cmd: &Command, path: &str, out: &mut Vec<String>) {
+            for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+                let sub_path = if path.is_empty() {
+                    sub.get_name().to_string()
+                } else {
+                    format!("{path} {}", sub.get_name())
+                };
+                visible_paths(sub, &sub_path, out);
+                out.push(sub_path);
+            }
+        }
+        let mut paths = vec![String::new()];
+        visible_paths(&Cli::command(), "", &mut paths);
+        paths.sort();
+        let mut keys: Vec<String> = RU.iter().map(|(key, _)| (*key).to_string()).collect();
+        keys.sort();
+        assert_eq!(keys, paths);
+
+        let cmd = localize(Cli::command(), Lang::Ru);
+        let about = |name: &str| {
+            cmd.find_subcommand(name)
+                .and_then(Command::get_about)
+                .map(ToString::to_string)
+        };
+        assert_eq!(about("ls").as_deref(), Some("Список запущенных игр"));
+    }
+}
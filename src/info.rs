@@ -0,0 +1,202 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{fs, io, path::Path};
+
+use crate::{
+    context::{
+        DEPS_FILE, ENDED_AT_FILE, EXIT_CODE_FILE, PID_FILE, STARTED_AT_FILE, TargetApp,
+        read_number, resolve_target_app,
+    },
+    env_store::{ENV_FILE, parse_env_content},
+    output::print_json,
+    proton::{ProtonBuild, context_proton_version},
+    runtime::{format_duration_ago, format_size, log_dir, proton_log_path},
+    signals::process_alive,
+    steam::AppMeta,
+};
+
+/// Всё, что известно об одном контексте: подробнее `ls -l`.
+pub fn handle_info(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let meta = target.meta();
+    let pfx = target.pfx().ok();
+    let wine = target.wine();
+    let (proton, version) = proton_build(&target, pfx.as_deref(), wine.is_some());
+    let version = meta.proton.clone().or(version);
+    let mut extra_rows = manifest_rows(&meta);
+    extra_rows.extend(launch_rows(&meta));
+    let started_at: Option<u64> = read_number(&target.app_dir.join(STARTED_AT_FILE));
+    let ended_at: Option<u64> = read_number(&target.app_dir.join(ENDED_AT_FILE));
+    let exit_code: Option<i32> = read_number(&target.app_dir.join(EXIT_CODE_FILE));
+    let pid: Option<libc::pid_t> = read_number(&target.app_dir.join(PID_FILE));
+    let alive = pid.is_some_and(process_alive);
+    let env_vars = fs::read_to_string(target.app_dir.join(ENV_FILE))
+        .map(|content| parse_env_content(&content).len())
+        .ok();
+    let deps = recorded_deps(&target.app_dir);
+    let proton_log = proton_log_path(&target.appid);
+    let logs = log_dir(&target.appid);
+
+    if json_output {
+        return print_json(&json!({
+            "appid": target.appid,
+            "context": target.app_dir,
+            "name": meta.name,
+            "install_path": meta.install_path,
+            "library": meta.library,
+            "build_id": meta.build_id,
+            "size_on_disk": meta.size_on_disk,
+            "last_updated": meta.last_updated,
+            "depots": meta.depots,
+            "pfx": pfx,
+            "proton": proton.as_ref().map(|build| build.dir.clone()),
+            "proton_name": proton.as_ref().map(|build| build.name.clone()),
+            "proton_version": version,
+            "wine": wine,
+            "wrappers": meta.wrappers,
+            "container": meta.container,
+            "started_at": started_at,
+            "ended_at": ended_at,
+            "exit_code": exit_code,
+            "pid": pid,
+            "alive": alive,
+            "env_vars": env_vars,
+            "deps": deps,
+            "proton_log": proton_log.exists().then_some(&proton_log),
+            "log_dir": logs,
+        }));
+    }
+
+    let row = |label: &str, value: String| println!("{:<12} {value}", label.green());
+    let missing = || "-".dimmed().to_string();
+    row("appid", target.appid.clone());
+    row("context", target.app_dir.display().to_string());
+    row("name", meta.name.unwrap_or_else(missing));
+    row("install", meta.install_path.unwrap_or_else(missing));
+    row("prefix", pfx.unwrap_or_else(missing));
+    if let Some(wine) = wine {
+        row("wine", wine);
+    } else {
+        let proton = proton.map_or_else(missing, |build| match version {
+            Some(version) => format!("{} ({version})", build.name),
+            None => build.name,
+        });
+        row("proton", proton);
+    }
+    for (label, value) in extra_rows {
+        row(label, value);
+    }
+    row(
+        "started",
+        started_at.map_or_else(missing, format_duration_ago),
+    );
+    if let Some(ended_at) = ended_at {
+        let exit_code = exit_code.map_or_else(missing, |code| code.to_string());
+        row(
+            "ended",
+            format!("{}, exit code {exit_code}", format_duration_ago(ended_at)),
+        );
+    }
+    row(
+        "pid",
+        pid.map_or_else(missing, |pid| {
+            let state = if alive {
+                "running".green()
+            } else {
+                "not running".red()
+            };
+            format!("{pid} ({state})")
+        }),
+    );
+    row(
+        "env",
+        env_vars.map_or_else(missing, |count| count.to_string()),
+    );
+    if !deps.is_empty() {
+        row("deps", deps.join(", "));
+    }
+    row("proton log", existing_path(&proton_log));
+    row("logs", existing_path(&logs));
+    Ok(())
+}
+
+/// Зависимости, поставленные `install-deps`.
+fn recorded_deps(app_dir: &Path) -> Vec<String> {
+    fs::read_to_string(app_dir.join(DEPS_FILE))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Сборка, размер и депо из манифеста Steam.
+fn manifest_rows(meta: &AppMeta) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    if let Some(build_id) = &meta.build_id {
+        let updated = meta
+            .last_updated
+            .map(|secs| format!(", updated {}", format_duration_ago(secs)))
+            .unwrap_or_default();
+        rows.push(("build", format!("{build_id}{updated}")));
+    }
+    if let Some(size) = meta.size_on_disk {
+        rows.push(("size", format_size(size)));
+    }
+    if !meta.depots.is_empty() {
+        let depots: Vec<String> = meta
+            .depots
+            .iter()
+            .map(|depot| match depot.size {
+                Some(size) => format!("{} ({})", depot.id, format_size(size)),
+                None => depot.id.clone(),
+            })
+            .collect();
+        rows.push(("depots", depots.join(", ")));
+    }
+    rows
+}
+
+/// Сведения, записанные `init`: библиотека Steam, обёртки и контейнер.
+fn launch_rows(meta: &AppMeta) -> Vec<(&'static str, String)> {
+    let mut rows = Vec::new();
+    if let Some(library) = &meta.library {
+        rows.push(("library", library.clone()));
+    }
+    if !meta.wrappers.is_empty() {
+        rows.push(("wrappers", meta.wrappers.join(" → ")));
+    }
+    if let Some(container) = &meta.container {
+        rows.push(("container", container.clone()));
+    }
+    rows
+}
+
+/// Сборка — каталог скрипта proton — и её версия; у контекстов на чистом Wine их нет.
+fn proton_build(
+    target: &TargetApp,
+    pfx: Option<&str>,
+    wine: bool,
+) -> (Option<ProtonBuild>, Option<String>) {
+    let proton = (!wine)
+        .then(|| target.exe().ok())
+        .flatten()
+        .and_then(|exe| Path::new(&exe).parent().map(Path::to_path_buf))
+        .and_then(|dir| {
+            let name = dir.file_name()?.to_string_lossy().into_owned();
+            Some(ProtonBuild { name, dir })
+        });
+    let version = proton.as_ref().and_then(|build| {
+        let compat_data = Path::new(pfx?).parent()?;
+        context_proton_version(&build.script(), compat_data)
+    });
+    (proton, version)
+}
+
+/// Путь; отсутствующий помечается, чтобы было видно, куда лог попадёт.
+fn existing_path(path: &Path) -> String {
+    if path.exists() {
+        path.display().to_string()
+    } else {
+        format!("{} {}", path.display(), "(нет)".dimmed())
+    }
+}
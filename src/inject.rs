@@ -0,0 +1,312 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::{InjectCommands, fail},
+    context::resolve_target_app,
+    env_store::{ENV_FILE, get_env_var, set_env_file_var},
+    runtime::data_home,
+};
+
+pub const DLL_OVERRIDES_ENV: &str = "WINEDLLOVERRIDES";
+const RESHADE_DOWNLOAD_URL: &str = "https://reshade.me/downloads";
+const RESHADE64_DLL: &str = "ReShade64.dll";
+const RESHADE32_DLL: &str = "ReShade32.dll";
+const EXE_SEARCH_DEPTH: usize = 4;
+
+/// Исполняемые файлы, которые точно не являются игрой.
+const NON_GAME_EXE_PREFIXES: &[&str] = &[
+    "unins",
+    "crashreport",
+    "crashhandler",
+    "unitycrashhandler",
+    "vc_redist",
+    "vcredist",
+    "dxsetup",
+    "dotnet",
+    "easyanticheat",
+    "ue4prereqsetup",
+    "uninstall",
+];
+
+/// Графический API игры, определяющий имя DLL для ReShade.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphicsApi {
+    Dx9,
+    Dx10,
+    Dx11,
+    Dx12,
+    Opengl,
+}
+
+impl GraphicsApi {
+    fn dll_name(self) -> &'static str {
+        match self {
+            Self::Dx9 => "d3d9",
+            Self::Dx10 | Self::Dx11 | Self::Dx12 => "dxgi",
+            Self::Opengl => "opengl32",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PeArch {
+    X86,
+    X64,
+}
+
+pub fn handle_inject(phd: &Path, command: InjectCommands) -> io::Result<()> {
+    match command {
+        InjectCommands::Reshade {
+            appid,
+            api,
+            exe,
+            from,
+            download,
+        } => handle_inject_reshade(
+            phd,
+            &appid,
+            api,
+            exe.as_deref(),
+            from.as_deref(),
+            download.as_deref(),
+        ),
+    }
+}
+
+fn handle_inject_reshade(
+    phd: &Path,
+    selector: &str,
+    api: GraphicsApi,
+    exe: Option<&str>,
+    from: Option<&Path>,
+    download: Option<&str>,
+) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let Some(install_dir) = target.meta().install_path.map(PathBuf::from) else {
+        fail("Не удалось определить каталог установки игры (нет appmanifest).");
+    };
+
+    let game_exe = match exe {
+        Some(exe) => install_dir.join(exe),
+        None => find_game_exe(&install_dir)?,
+    };
+    let Some(arch) = read_pe_arch(&game_exe)? else {
+        fail(&format!(
+            "{} не похож на Windows-исполняемый файл",
+            game_exe.display()
+        ));
+    };
+
+    let reshade_dir = match (from, download) {
+        (Some(dir), _) => dir.to_path_buf(),
+        (None, Some(version)) => download_reshade(version)?,
+        (None, None) => reshade_cache_dir(),
+    };
+    let source_dll = reshade_dir.join(match arch {
+        PeArch::X64 => RESHADE64_DLL,
+        PeArch::X86 => RESHADE32_DLL,
+    });
+    if !source_dll.is_file() {
+        fail(&format!(
+            "ReShade не найден: {} (используйте --from <dir> или --download <version>)",
+            source_dll.display()
+        ));
+    }
+
+    let Some(game_dir) = game_exe.parent() else {
+        fail("Некорректный путь к исполняемому файлу игры");
+    };
+    let dll = api.dll_name();
+    let dest = game_dir.join(format!("{dll}.dll"));
+    fs::copy(&source_dll, &dest)?;
+    println!(
+        "{} {} -> {}",
+        "OK".green().bold(),
+        source_dll.display(),
+        dest.display()
+    );
+
+    let env_content = fs::read_to_string(target.app_dir.join(ENV_FILE))?;
+    let current = get_env_var(&env_content, DLL_OVERRIDES_ENV).unwrap_or_default();
+    let overrides = merge_dll_override(&current, dll, "n,b");
+    set_env_file_var(&target.app_dir, DLL_OVERRIDES_ENV, &overrides)?;
+    println!(
+        "{} {DLL_OVERRIDES_ENV}=\"{overrides}\"",
+        "OK".green().bold()
+    );
+    println!(
+        "Для самой игры добавьте в Launch Options: {}",
+        format!("WINEDLLOVERRIDES=\"{dll}=n,b\" protonhax init %command%").yellow()
+    );
+
+    Ok(())
+}
+
+fn reshade_cache_dir() -> PathBuf {
+    data_home().join("protonhax").join("reshade")
+}
+
+/// Скачивает установщик ReShade и извлекает из него DLL (установщик — PE с zip-архивом).
+fn download_reshade(version: &str) -> io::Result<PathBuf> {
+    let dir = reshade_cache_dir();
+    fs::create_dir_all(&dir)?;
+    let setup = dir.join(format!("ReShade_Setup_{version}.exe"));
+    let url = format!("{RESHADE_DOWNLOAD_URL}/ReShade_Setup_{version}.exe");
+
+    println!("Скачиваем {url}");
+    let status = process::Command::new("curl")
+        .args(["-fL", "--progress-bar", "-o"])
+        .arg(&setup)
+        .arg(&url)
+        .status()?;
+    if !status.success() {
+        fail("Не удалось скачать установщик ReShade");
+    }
+
+    // unzip возвращает 1 при предупреждении о лишних байтах перед архивом — это ожидаемо.
+    let status = process::Command::new("unzip")
+        .args(["-o", "-j", "-q"])
+        .arg(&setup)
+        .args([RESHADE64_DLL, RESHADE32_DLL, "-d"])
+        .arg(&dir)
+        .status()?;
+    if !matches!(status.code(), Some(0 | 1)) {
+        fail("Не удалось извлечь DLL из установщика ReShade (нужен unzip)");
+    }
+
+    Ok(dir)
+}
+
+fn find_game_exe(install_dir: &Path) -> io::Result<PathBuf> {
+    let mut candidates = Vec::new();
+    collect_exes(install_dir, EXE_SEARCH_DEPTH, &mut candidates)?;
+    candidates.retain(|path| !is_non_game_exe(path));
+
+    match candidates.as_slice() {
+        [exe] => Ok(exe.clone()),
+        [] => fail(&format!(
+            "В {} не найдено исполняемых файлов игры; укажите --exe",
+            install_dir.display()
+        )),
+        _ => {
+            eprintln!(
+                "{} Найдено несколько исполняемых файлов, укажите нужный через --exe:",
+                "Ошибка:".bold().red()
+            );
+            for exe in &candidates {
+                let relative = exe.strip_prefix(install_dir).unwrap_or(exe);
+                eprintln!("  {}", relative.display());
+            }
+            process::exit(2);
+        }
+    }
+}
+
+fn collect_exes(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if depth > 0 {
+                collect_exes(&path, depth - 1, out)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_non_game_exe(path: &Path) -> bool {
+    let Some(name) = path.file_name() else {
+        return true;
+    };
+    let name = name.to_string_lossy().to_ascii_lowercase();
+    NON_GAME_EXE_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn read_pe_arch(path: &Path) -> io::Result<Option<PeArch>> {
+    let mut file = fs::File::open(path)?;
+    let mut dos_header = [0u8; 64];
+    if file.read_exact(&mut dos_header).is_err() {
+        return Ok(None);
+    }
+
+    let pe_offset = u32::from_le_bytes([
+        dos_header[0x3c],
+        dos_header[0x3d],
+        dos_header[0x3e],
+        dos_header[0x3f],
+    ]);
+    let mut pe_header = [0u8; 6];
+    file.seek(SeekFrom::Start(u64::from(pe_offset)))?;
+    if file.read_exact(&mut pe_header).is_err() {
+        return Ok(None);
+    }
+
+    Ok(parse_pe_arch(&dos_header, pe_header))
+}
+
+fn parse_pe_arch(dos_header: &[u8], pe_header: [u8; 6]) -> Option<PeArch> {
+    if !dos_header.starts_with(b"MZ") || !pe_header.starts_with(b"PE\0\0") {
+        return None;
+    }
+
+    match u16::from_le_bytes([pe_header[4], pe_header[5]]) {
+        0x8664 => Some(PeArch::X64),
+        0x014c => Some(PeArch::X86),
+        _ => None,
+    }
+}
+
+/// Добавляет или заменяет `dll=mode` в строке формата `WINEDLLOVERRIDES`.
+pub fn merge_dll_override(current: &str, dll: &str, mode: &str) -> String {
+    let mut entries: Vec<&str> = current
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| {
+            !entry.is_empty()
+                && entry
+                    .split_once('=')
+                    .is_none_or(|(name, _)| !name.eq_ignore_ascii_case(dll))
+        })
+        .collect();
+
+    let new_entry = format!("{dll}={mode}");
+    entries.push(&new_entry);
+    entries.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PeArch, merge_dll_override, parse_pe_arch};
+
+    #[test]
+    fn merges_dll_overrides() {
+        assert_eq!(merge_dll_override("", "dxgi", "n,b"), "dxgi=n,b");
+        assert_eq!(
+            merge_dll_override("d3d11=n;DXGI=b", "dxgi", "n,b"),
+            "d3d11=n;dxgi=n,b"
+        );
+    }
+
+    #[test]
+    fn detects_pe_machine() {
+        let mut dos = [0u8; 64];
+        dos[..2].copy_from_slice(b"MZ");
+        assert_eq!(parse_pe_arch(&dos, *b"PE\0\0\x64\x86"), Some(PeArch::X64));
+        assert_eq!(parse_pe_arch(&dos, *b"PE\0\0\x4c\x01"), Some(PeArch::X86));
+        assert_eq!(parse_pe_arch(&[0u8; 64], *b"PE\0\0\x64\x86"), None);
+    }
+}
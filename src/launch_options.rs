@@ -0,0 +1,382 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    runtime::unix_now_secs,
+    steam::{LOCAL_CONFIG_FILE, find_compat_data, installed_apps, user_config_dir},
+    vdf::{self, Vdf},
+};
+
+/// Параметры запуска, с которыми Steam передаёт игру protonhax.
+pub const INIT_LAUNCH_OPTION: &str = "protonhax init %command%";
+/// Steamworks Common Redistributables: получает compatdata, но это не игра.
+const REDIST_APPID: &str = "228980";
+/// Путь к параметрам запуска в `localconfig.vdf` (ключи без учёта регистра).
+const APPS_PATH: &[&str] = &["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"];
+
+/// Как в параметрах запуска вызван protonhax.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchSetup {
+    /// `protonhax init %command%`.
+    Installed,
+    /// protonhax есть, но в другом виде: `protonhax.sh`, путь к своему скрипту, без `init`.
+    Legacy,
+    Missing,
+}
+
+/// Игра на Proton и её параметры запуска.
+pub struct GameLaunch {
+    pub appid: String,
+    pub name: Option<String>,
+    pub options: String,
+    pub setup: LaunchSetup,
+}
+
+impl GameLaunch {
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({})", self.appid),
+            None => self.appid.clone(),
+        }
+    }
+}
+
+/// `localconfig.vdf` последнего вошедшего аккаунта: разобранный для чтения и исходный
+/// текст, в котором правятся только строки `LaunchOptions`.
+pub struct LocalConfig {
+    pub path: PathBuf,
+    root: Vdf,
+    content: String,
+}
+
+impl LocalConfig {
+    /// `Ok(None)` — файла нет: Steam на этой машине ещё не входил.
+    pub fn load() -> io::Result<Option<Self>> {
+        let Some(path) = user_config_dir().map(|config| config.join(LOCAL_CONFIG_FILE)) else {
+            return Ok(None);
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        match vdf::parse(&content) {
+            Some(root) => Ok(Some(Self {
+                path,
+                root,
+                content,
+            })),
+            None => Err(io::Error::other(format!(
+                "не удалось разобрать {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Установленные игры, уже запускавшиеся через Proton (есть `compatdata/<appid>/pfx`),
+    /// с их параметрами запуска.
+    pub fn proton_games(&self) -> Vec<GameLaunch> {
+        let mut options = launch_options(&self.root);
+        installed_apps()
+            .into_iter()
+            .filter(|(appid, _)| appid != REDIST_APPID && find_compat_data(appid).is_some())
+            .map(|(appid, name)| {
+                let options = options.remove(&appid).unwrap_or_default();
+                GameLaunch {
+                    setup: classify(&options),
+                    appid,
+                    name,
+                    options,
+                }
+            })
+            .collect()
+    }
+
+    /// `false` — на месте блока `apps` строка: файл не похож на настройки Steam. Остальной
+    /// текст файла не меняется, см. `vdf::set_in_text`.
+    pub fn set_launch_options(&mut self, appid: &str, options: &str) -> bool {
+        let path: Vec<&str> = APPS_PATH
+            .iter()
+            .copied()
+            .chain([appid, "LaunchOptions"])
+            .collect();
+        match vdf::set_in_text(&self.content, &path, options) {
+            Some(content) => {
+                self.content = content;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Сохраняет изменения, оставив рядом копию прежнего файла; возвращает путь копии.
+    pub fn save(&self) -> io::Result<PathBuf> {
+        // Две правки за одну секунду (`--remove`, затем `--migrate`) не затирают копию.
+        let stamp = unix_now_secs();
+        let mut backup = self.path.with_extension(format!("vdf.{stamp}.bak"));
+        let mut attempt = 1;
+        while backup.exists() {
+            backup = self
+                .path
+                .with_extension(format!("vdf.{stamp}.{attempt}.bak"));
+            attempt += 1;
+        }
+        fs::copy(&self.path, &backup)?;
+        let tmp_path = self.path.with_extension("vdf.tmp");
+        fs::write(&tmp_path, &self.content)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(backup)
+    }
+}
+
+/// Установленные игры на Proton с параметрами запуска из `localconfig.vdf`. `Ok(None)` —
+/// файла нет: Steam ещё не входил.
+pub fn proton_games() -> io::Result<Option<Vec<GameLaunch>>> {
+    Ok(LocalConfig::load()?.map(|config| config.proton_games()))
+}
+
+/// `appid → LaunchOptions` из разобранного `localconfig.vdf`.
+fn launch_options(config: &Vdf) -> BTreeMap<String, String> {
+    let apps = APPS_PATH
+        .iter()
+        .try_fold(config, |block, key| block.get(key));
+    apps.map(Vdf::entries)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(appid, app)| {
+            let options = app.get("LaunchOptions")?.as_str()?;
+            Some((appid.clone(), options.to_string()))
+        })
+        .collect()
+}
+
+/// Ищет protonhax среди слов параметров запуска.
+pub fn classify(options: &str) -> LaunchSetup {
+    let words: Vec<&str> = options.split_whitespace().collect();
+    let Some(index) = words.iter().position(|word| is_protonhax_word(word)) else {
+        return LaunchSetup::Missing;
+    };
+    // Полный путь к бинарнику (`~/.local/bin/protonhax init %command%`) — тоже текущий вид.
+    let current = file_name(words[index]) == Some("protonhax")
+        && words.get(index + 1) == Some(&"init")
+        && words[index + 2..]
+            .iter()
+            .any(|word| word.eq_ignore_ascii_case("%command%"));
+    if current {
+        LaunchSetup::Installed
+    } else {
+        LaunchSetup::Legacy
+    }
+}
+
+/// Параметры запуска с `protonhax init %command%`: прежний вызов protonhax заменяется,
+/// `%command%` оборачивается, а параметры без него Steam дописывает после команды игры.
+pub fn with_protonhax(options: &str) -> String {
+    if classify(options) == LaunchSetup::Installed {
+        return options.to_string();
+    }
+    let options = without_protonhax(options);
+    let command = words(&options)
+        .into_iter()
+        .find(|(_, word)| word.eq_ignore_ascii_case("%command%"));
+    match command {
+        Some((start, word)) => format!(
+            "{}{INIT_LAUNCH_OPTION}{}",
+            &options[..start],
+            &options[start + word.len()..]
+        ),
+        None if options.is_empty() => INIT_LAUNCH_OPTION.to_string(),
+        None => format!("{INIT_LAUNCH_OPTION} {options}"),
+    }
+}
+
+/// Параметры запуска без вызова protonhax (и его `init`); остальное не трогается. Если
+/// остался один `%command%`, параметры пустые — как у игры, которую не настраивали.
+pub fn without_protonhax(options: &str) -> String {
+    strip_wrapper(options, &[]).unwrap_or_else(|| options.to_string())
+}
+
+/// Переводит старый вызов — `protonhax.sh`, свой скрипт из `wrappers` (путь или имя файла)
+/// — на `protonhax init %command%`. `None` — старого вызова нет.
+pub fn migrated(options: &str, wrappers: &[String]) -> Option<String> {
+    if classify(options) == LaunchSetup::Installed {
+        return None;
+    }
+    strip_wrapper(options, wrappers).map(|options| with_protonhax(&options))
+}
+
+/// Убирает первый вызов protonhax или обёртки из `wrappers` вместе с его `init`; `None` —
+/// такого вызова нет.
+fn strip_wrapper(options: &str, wrappers: &[String]) -> Option<String> {
+    let words = words(options);
+    let index = words.iter().position(|(_, word)| {
+        is_protonhax_word(word)
+            || wrappers
+                .iter()
+                .any(|wrapper| file_name(wrapper).is_some_and(|name| file_name(word) == Some(name)))
+    })?;
+    let start = words[index].0;
+    let last = match words.get(index + 1) {
+        Some((_, "init")) => index + 1,
+        _ => index,
+    };
+    let end = words[last].0 + words[last].1.len();
+    let rest = format!("{}{}", &options[..start], options[end..].trim_start());
+    let rest = rest.trim();
+    if rest.eq_ignore_ascii_case("%command%") {
+        Some(String::new())
+    } else {
+        Some(rest.to_string())
+    }
+}
+
+/// Слова параметров запуска со смещением начала: правка одного слова сохраняет остальную
+/// строку как есть, с кавычками и пробелами.
+fn words(options: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, c) in options.char_indices().chain([(options.len(), ' ')]) {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(index),
+            (true, Some(begin)) => {
+                words.push((begin, &options[begin..index]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// `protonhax`, `~/bin/protonhax.sh`, `/opt/protonhax/protonhax-wrapper` и подобные.
+fn is_protonhax_word(word: &str) -> bool {
+    file_name(word).is_some_and(|name| name.to_ascii_lowercase().contains("protonhax"))
+}
+
+/// Имя файла команды без кавычек и каталога: `"~/bin/run.sh"` → `run.sh`. У присваивания
+/// окружения (`PROTONHAX_DEBUG=1`, `WRAPPER=/opt/protonhax.sh`) — `=` до первого `/` —
+/// имени нет: это не команда.
+fn file_name(word: &str) -> Option<&str> {
+    let word = word.trim_matches(['"', '\'']);
+    if word
+        .split('/')
+        .next()
+        .is_some_and(|head| head.contains('='))
+    {
+        return None;
+    }
+    Path::new(word).file_name()?.to_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        LaunchSetup, classify, launch_options, migrated, with_protonhax, without_protonhax,
+    };
+    use crate::vdf;
+
+    #[test]
+    fn classifies_launch_options() {
+        assert_eq!(classify("protonhax init %command%"), LaunchSetup::Installed);
+        assert_eq!(
+            classify("PROTON_LOG=1 protonhax init %command% -novid"),
+            LaunchSetup::Installed
+        );
+        assert_eq!(
+            classify("~/bin/protonhax.sh init %command%"),
+            LaunchSetup::Legacy
+        );
+        assert_eq!(
+            classify("/home/user/.local/bin/protonhax init %command%"),
+            LaunchSetup::Installed
+        );
+        assert_eq!(
+            classify("PROTONHAX_DEBUG=1 PROTONHAX_KEEP=1 protonhax init %command%"),
+            LaunchSetup::Installed
+        );
+        assert_eq!(
+            classify("PROTONHAX_DEBUG=1 %command%"),
+            LaunchSetup::Missing
+        );
+        assert_eq!(classify("protonhax %command%"), LaunchSetup::Legacy);
+        assert_eq!(classify("gamemoderun %command%"), LaunchSetup::Missing);
+        assert_eq!(classify(""), LaunchSetup::Missing);
+    }
+
+    #[test]
+    fn installs_and_removes_protonhax() {
+        assert_eq!(with_protonhax(""), "protonhax init %command%");
+        assert_eq!(
+            with_protonhax("PROTON_LOG=1 %command% -novid"),
+            "PROTON_LOG=1 protonhax init %command% -novid"
+        );
+        assert_eq!(with_protonhax("-novid"), "protonhax init %command% -novid");
+        assert_eq!(
+            with_protonhax("~/protonhax.sh init %command%"),
+            "protonhax init %command%"
+        );
+        let installed = "gamemoderun protonhax init %command%";
+        assert_eq!(with_protonhax(installed), installed);
+
+        assert_eq!(without_protonhax("protonhax init %command%"), "");
+        assert_eq!(
+            without_protonhax(r#"A="x  y" protonhax init %command% -novid"#),
+            r#"A="x  y" %command% -novid"#
+        );
+        assert_eq!(without_protonhax("~/protonhax.sh %command%"), "");
+        assert_eq!(without_protonhax("-novid"), "-novid");
+
+        // Переменные protonhax в начале — не вызов: второй раз не оборачивается.
+        let debug = "PROTONHAX_DEBUG=1 protonhax init %command%";
+        assert_eq!(with_protonhax(debug), debug);
+        assert_eq!(
+            with_protonhax("PROTONHAX_KEEP=1 %command%"),
+            "PROTONHAX_KEEP=1 protonhax init %command%"
+        );
+        assert_eq!(without_protonhax(debug), "PROTONHAX_DEBUG=1 %command%");
+        assert_eq!(
+            without_protonhax("PROTONHAX_KEEP=1 %command%"),
+            "PROTONHAX_KEEP=1 %command%"
+        );
+    }
+
+    #[test]
+    fn migrates_old_wrappers() {
+        assert_eq!(
+            migrated("~/protonhax.sh init %command% -novid", &[]).as_deref(),
+            Some("protonhax init %command% -novid")
+        );
+        assert_eq!(
+            migrated("DXVK_HUD=1 /opt/protonhax/protonhax-wrapper %command%", &[]).as_deref(),
+            Some("DXVK_HUD=1 protonhax init %command%")
+        );
+        let wrappers = ["/home/user/bin/ph-run.sh".to_string()];
+        assert_eq!(
+            migrated("~/bin/ph-run.sh %command%", &wrappers).as_deref(),
+            Some("protonhax init %command%")
+        );
+        assert_eq!(migrated("~/bin/ph-run.sh %command%", &[]), None);
+        assert_eq!(migrated("protonhax init %command%", &wrappers), None);
+        assert_eq!(migrated("gamemoderun %command%", &[]), None);
+    }
+
+    #[test]
+    fn reads_launch_options_from_localconfig() {
+        let config = vdf::parse(
+            r#""UserLocalConfigStore" { "Software" { "valve" { "Steam" { "apps" {
+                "620" { "LastPlayed" "1700000000" "LaunchOptions" "protonhax init %command%" }
+                "730" { "LastPlayed" "1700000001" }
+            } } } } }"#,
+        )
+        .unwrap();
+        let options = launch_options(&config);
+        assert_eq!(
+            options.get("620").map(String::as_str),
+            Some("protonhax init %command%")
+        );
+        assert!(!options.contains_key("730"));
+    }
+}
@@ -0,0 +1,230 @@
+use colored::Colorize;
+use std::{
+    env,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    audit::{self, AuditAction},
+    cli::fail,
+    context::{
+        ContextStatus, ENDED_AT_FILE, PID_FILE, RunningApp, collect_running_apps, read_number,
+    },
+    runtime::{find_in_path, unix_now_secs},
+    signals::{process_alive, signal_descendants},
+    verbosity,
+    wine::{PrefixContext, resolve_prefix},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Сколько ждать после выхода игры, пока `init` уберёт контекст.
+const CLEANUP_GRACE: Duration = Duration::from_secs(5);
+const STEAM_RUN_URL: &str = "steam://rungameid/";
+
+/// Сколько ждать на каждой ступени остановки игры.
+pub struct StopTimeouts {
+    /// После `wineboot --shutdown`, до SIGTERM.
+    pub shutdown: Duration,
+    /// После SIGTERM, до SIGKILL.
+    pub term: Duration,
+}
+
+impl Default for StopTimeouts {
+    fn default() -> Self {
+        Self {
+            shutdown: Duration::from_secs(15),
+            term: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Аккуратно завершает игру, чтобы она успела сохраниться: в отличие от `killwin`,
+/// SIGTERM и SIGKILL — только если она не вышла сама.
+pub fn handle_stop(phd: &Path, selector: &str, timeouts: &StopTimeouts) -> io::Result<()> {
+    let prefix = resolve_prefix(phd, selector)?;
+    let (app_dir, pid) = running_game(phd, &prefix);
+    let started_at = unix_now_secs();
+    stop_game(&prefix, pid, timeouts)?;
+    wait_for_cleanup(&app_dir);
+    audit::record(
+        AuditAction::Stop,
+        &prefix.appid,
+        started_at,
+        process::ExitStatus::default(),
+    );
+    println!("{} {} остановлена", "OK".green().bold(), prefix.appid);
+    Ok(())
+}
+
+/// Останавливает запущенную игру и ждёт, пока Steam запустит её снова (по умолчанию сам
+/// просит об этом через `steam://rungameid`); печатает новый контекст.
+pub fn handle_restart(
+    phd: &Path,
+    selector: &str,
+    launch: bool,
+    timeout: Duration,
+) -> io::Result<()> {
+    // Steam получает ссылку с окружением оболочки, а не игры.
+    let shell_env: Vec<(OsString, OsString)> = env::vars_os().collect();
+    let prefix = resolve_prefix(phd, selector)?;
+    let (app_dir, pid) = running_game(phd, &prefix);
+    stop_game(&prefix, pid, &StopTimeouts::default())?;
+    wait_for_cleanup(&app_dir);
+    println!("{} {} остановлена", "OK".green().bold(), prefix.appid);
+
+    let is_steam_app = prefix.appid.bytes().all(|byte| byte.is_ascii_digit());
+    if launch && is_steam_app {
+        launch_through_steam(&prefix.appid, shell_env)?;
+    } else {
+        verbosity::info(format_args!(
+            "Запустите {} снова — ждём новый контекст до {} с",
+            prefix.appid,
+            timeout.as_secs()
+        ));
+    }
+
+    let mut restarted = None;
+    wait_until(timeout, || {
+        restarted = new_context(phd, &prefix.appid, pid);
+        restarted.is_some()
+    });
+    let Some(app) = restarted else {
+        fail(&format!(
+            "Игра {} не запустилась за {} с",
+            prefix.appid,
+            timeout.as_secs()
+        ));
+    };
+    println!(
+        "{} {} перезапущена: контекст {}{}",
+        "OK".green().bold(),
+        app.appid,
+        app.id.green(),
+        app.pid
+            .map(|pid| format!(", pid {pid}"))
+            .unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Каталог и pid `init` живого runtime-контекста; иначе ошибка.
+fn running_game(phd: &Path, prefix: &PrefixContext) -> (PathBuf, libc::pid_t) {
+    let Some(app_dir) = prefix.app_dir.clone().filter(|dir| dir.starts_with(phd)) else {
+        fail(&format!(
+            "{} не запущена: нужен контекст живой игры",
+            prefix.appid
+        ));
+    };
+    let Some(pid) =
+        read_number::<libc::pid_t>(&app_dir.join(PID_FILE)).filter(|pid| process_alive(*pid))
+    else {
+        fail(&format!("Игра {} не запущена", prefix.appid));
+    };
+    (app_dir, pid)
+}
+
+/// `wineboot --shutdown` просит Windows-процессы завершиться, как при выключении
+/// Windows; если игра не вышла, SIGTERM потомкам `init`, затем SIGKILL.
+fn stop_game(prefix: &PrefixContext, pid: libc::pid_t, timeouts: &StopTimeouts) -> io::Result<()> {
+    verbosity::info(format_args!(
+        "wineboot -s: завершаем процессы {}",
+        prefix.appid
+    ));
+    let mut wineboot = prefix
+        .wineboot("-s")
+        .stdout(process::Stdio::null())
+        .spawn()?;
+    let escalation = [
+        (timeouts.shutdown, libc::SIGTERM, "SIGTERM"),
+        (timeouts.term, libc::SIGKILL, "SIGKILL"),
+    ];
+    for (grace, signal, name) in escalation {
+        if wait_until(grace, || !process_alive(pid)) {
+            break;
+        }
+        verbosity::info(format_args!(
+            "игра не вышла за {} с, {name}",
+            grace.as_secs()
+        ));
+        signal_descendants(pid, signal)?;
+    }
+    if !wait_until(CLEANUP_GRACE, || !process_alive(pid)) {
+        fail(&format!(
+            "Игра {} не завершилась даже после SIGKILL (pid {pid})",
+            prefix.appid
+        ));
+    }
+    // Зависший wineboot не должен держать stop и restart.
+    if wineboot.try_wait()?.is_none() {
+        let _ = wineboot.kill();
+    }
+    let _ = wineboot.wait();
+    Ok(())
+}
+
+/// `init` убирает контекст (или помечает его `--keep`) сразу после выхода игры.
+fn wait_for_cleanup(app_dir: &Path) {
+    wait_until(CLEANUP_GRACE, || {
+        !app_dir.is_dir() || app_dir.join(ENDED_AT_FILE).exists()
+    });
+}
+
+/// Клиент Steam принимает ссылку и сразу возвращает управление; без `steam` в PATH
+/// (Flatpak) — через обработчик ссылок рабочего стола.
+fn launch_through_steam(appid: &str, shell_env: Vec<(OsString, OsString)>) -> io::Result<()> {
+    let url = format!("{STEAM_RUN_URL}{appid}");
+    let opener = if find_in_path("steam").is_some() {
+        "steam"
+    } else {
+        "xdg-open"
+    };
+    verbosity::verbose(format_args!("{opener} {url}"));
+    let spawned = process::Command::new(opener)
+        .arg(&url)
+        .env_clear()
+        .envs(shell_env)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            eprintln!(
+                "{} ни steam, ни xdg-open не найдены: запустите игру вручную",
+                "Предупреждение:".yellow().bold()
+            );
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Живой контекст игры, созданный не прежним `init`.
+fn new_context(phd: &Path, appid: &str, old_pid: libc::pid_t) -> Option<RunningApp> {
+    collect_running_apps(phd, false)
+        .ok()?
+        .into_iter()
+        .find(|app| {
+            app.appid == appid
+                && app.pid.is_some_and(|pid| pid != old_pid)
+                && app.status() == ContextStatus::Running
+        })
+}
+
+/// Опрашивает `done`, пока не выйдет время; `true`, если дождались.
+fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    context::{RunningApp, collect_running_apps, registered_root},
+    verbosity,
+};
+
+/// Имена целей из последнего `ls` для автодополнения; точка в начале — `monitor` и
+/// `ls --watch` такие файлы не замечают.
+const LISTING_CACHE_FILE: &str = ".listing.json";
+/// Отметка идущего фонового обновления: Tab, нажатый снова, второе не запускает.
+const REFRESH_MARKER_FILE: &str = ".listing.refresh";
+/// Обновление, которое не убрало отметку дольше этого, считаем упавшим.
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Имя игры по id контекста; `None` — имя не нашлось, но id уже разобран.
+#[derive(Default, Serialize, Deserialize)]
+struct ListingCache {
+    names: BTreeMap<String, Option<String>>,
+}
+
+fn cache_path(phd: &Path) -> PathBuf {
+    phd.join(LISTING_CACHE_FILE)
+}
+
+/// Цели для автодополнения: id контекстов (в том числе зарегистрированных) с именами из
+/// кэша. Читаются только каталоги, без манифестов; если кэш знает не все id, отвечаем
+/// без имён и обновляем его в фоне для следующего Tab.
+pub fn cached_targets(phd: &Path) -> Vec<(String, Option<String>)> {
+    let ids = context_ids(phd);
+    let cache = load(phd);
+    if ids.iter().any(|id| !cache.names.contains_key(id)) {
+        spawn_refresh(phd);
+    }
+    ids.into_iter()
+        .map(|id| {
+            let name = cache.names.get(&id).cloned().flatten();
+            (id, name)
+        })
+        .collect()
+}
+
+/// Запоминает имена из готового списка (`ls -l`, `--json`, `--plain`, фильтр); id
+/// исчезнувших контекстов выбрасывает.
+pub fn update(phd: &Path, apps: &[&RunningApp]) {
+    let mut cache = load(phd);
+    for app in apps {
+        cache.names.insert(app.id.clone(), app.name.clone());
+    }
+    let ids = context_ids(phd);
+    cache.names.retain(|id, _| ids.contains(id));
+    if let Err(err) = save(phd, &cache) {
+        verbosity::debug(format_args!(
+            "не удалось записать {}: {err}",
+            cache_path(phd).display()
+        ));
+    }
+}
+
+/// Фоновая часть `complete`: полный список с метаданными заменяет кэш.
+pub fn refresh(phd: &Path) -> io::Result<()> {
+    let mut apps = collect_running_apps(phd, true)?;
+    apps.extend(collect_running_apps(&registered_root(), true)?);
+    let cache = ListingCache {
+        names: apps.into_iter().map(|app| (app.id, app.name)).collect(),
+    };
+    let result = save(phd, &cache);
+    let _ = fs::remove_file(phd.join(REFRESH_MARKER_FILE));
+    result
+}
+
+fn spawn_refresh(phd: &Path) {
+    let marker = phd.join(REFRESH_MARKER_FILE);
+    let running = fs::metadata(&marker)
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|at| {
+            SystemTime::now()
+                .duration_since(at)
+                .is_ok_and(|age| age < REFRESH_TIMEOUT)
+        });
+    if running || fs::write(&marker, "").is_err() {
+        return;
+    }
+    // Потоки закрыты: подстановка `$(protonhax complete ...)` в оболочке не ждёт фоновый
+    // процесс.
+    let spawned = env::current_exe().and_then(|exe| {
+        process::Command::new(exe)
+            .arg("--runtime-dir")
+            .arg(phd)
+            .args(["complete", "--refresh"])
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn()
+    });
+    if spawned.is_err() {
+        let _ = fs::remove_file(marker);
+    }
+}
+
+/// Имена каталогов контекстов: только `readdir`, без чтения файлов внутри.
+fn context_ids(phd: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = [phd.to_path_buf(), registered_root()]
+        .iter()
+        .flat_map(|root| fs::read_dir(root).into_iter().flatten())
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+fn load(phd: &Path) -> ListingCache {
+    fs::read_to_string(cache_path(phd))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(phd: &Path, cache: &ListingCache) -> io::Result<()> {
+    if !phd.is_dir() {
+        return Ok(());
+    }
+    let serialized = serde_json::to_string(cache).map_err(io::Error::other)?;
+    let path = cache_path(phd);
+    // Через переименование: параллельный Tab не прочитает половину файла.
+    let tmp_path = path.with_extension(format!("json.{}.tmp", process::id()));
+    fs::write(&tmp_path, serialized)?;
+    fs::rename(tmp_path, path)
+}
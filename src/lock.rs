@@ -0,0 +1,59 @@
+use std::{
+    fs, io,
+    os::{fd::AsRawFd, unix::fs::MetadataExt},
+    path::Path,
+};
+
+use crate::cli::fail;
+
+/// Advisory-блокировка каталога контекста (`flock` на самом каталоге). `init` держит её
+/// монопольно, пока пишет или удаляет контекст, читатели — разделяемо, пока читают.
+/// Снимается при закрытии дескриптора.
+pub struct ContextLock {
+    dir: fs::File,
+}
+
+impl ContextLock {
+    pub fn exclusive(app_dir: &Path) -> io::Result<Self> {
+        Self::acquire(app_dir, libc::LOCK_EX)
+    }
+
+    /// Разделяемая блокировка для чтения; если `init` успел удалить контекст, пока мы
+    /// ждали, — понятная ошибка вместо чтения полуудалённого каталога.
+    pub fn shared(app_dir: &Path, appid: &str) -> io::Result<Self> {
+        match Self::try_shared(app_dir)? {
+            Some(lock) => Ok(lock),
+            None => fail(&format!(
+                "Контекст {appid} закрывается: игра завершилась, запустите её снова"
+            )),
+        }
+    }
+
+    /// `None`, если контекст удалён до или во время ожидания.
+    pub fn try_shared(app_dir: &Path) -> io::Result<Option<Self>> {
+        let lock = match Self::acquire(app_dir, libc::LOCK_SH) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            result => result?,
+        };
+        Ok((!lock.removed()?).then_some(lock))
+    }
+
+    fn acquire(app_dir: &Path, operation: libc::c_int) -> io::Result<Self> {
+        let dir = fs::File::open(app_dir)?;
+        loop {
+            // SAFETY: дескриптор открыт и живёт, пока жив `dir`.
+            if unsafe { libc::flock(dir.as_raw_fd(), operation) } == 0 {
+                return Ok(Self { dir });
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Каталог удалён, пока мы ждали блокировку: у удалённого каталога нет ссылок.
+    fn removed(&self) -> io::Result<bool> {
+        Ok(self.dir.metadata()?.nlink() == 0)
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    runtime::{SECS_PER_DAY, log_root, unix_now_secs},
+    verbosity,
+};
+
+const BYTES_PER_MIB: u64 = 1024 * 1024;
+
+/// Хранение логов в `$XDG_STATE_HOME/protonhax/logs/<appid>` (`[logs]` в конфиге);
+/// 0 отключает соответствующее ограничение.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRetention {
+    /// Удалять логи старше стольких дней.
+    pub retention_days: u64,
+    /// Сколько последних файлов хранить для одной игры.
+    pub keep_files: usize,
+    /// Предел суммарного размера логов одной игры в мебибайтах.
+    pub max_size_mb: u64,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            retention_days: 14,
+            keep_files: 20,
+            max_size_mb: 512,
+        }
+    }
+}
+
+impl LogRetention {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+struct LogFile {
+    path: PathBuf,
+    modified: u64,
+    size: u64,
+}
+
+/// Применяет `retention` ко всем каталогам логов; вызывается при старте игры и перед
+/// записью нового лога. Ошибки не мешают запуску: не удалось — удалим в следующий раз.
+pub fn prune_logs(retention: &LogRetention) {
+    let Ok(entries) = fs::read_dir(log_root()) else {
+        return;
+    };
+    let now = unix_now_secs();
+    let mut removed = 0;
+    for dir in entries.flatten().map(|entry| entry.path()) {
+        if !dir.is_dir() {
+            continue;
+        }
+        let files = log_files(&dir);
+        for path in files_to_prune(files, now, retention) {
+            if fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        // Каталог без логов больше не нужен; непустой `remove_dir` не удалит.
+        let _ = fs::remove_dir(&dir);
+    }
+    if removed > 0 {
+        verbosity::debug(format_args!("удалено старых логов: {removed}"));
+    }
+}
+
+fn log_files(dir: &Path) -> Vec<LogFile> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+            meta.is_file().then(|| LogFile {
+                path: entry.path(),
+                modified: modified.as_secs(),
+                size: meta.len(),
+            })
+        })
+        .collect()
+}
+
+/// Старые по возрасту, лишние по количеству, затем самые старые, пока логи не уложатся
+/// в предел размера. Самый свежий файл ограничения количества и размера не удаляют:
+/// в него может идти запись.
+fn files_to_prune(mut files: Vec<LogFile>, now: u64, retention: &LogRetention) -> Vec<PathBuf> {
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified));
+    let mut kept_size = 0;
+    let mut prune = Vec::new();
+    for (index, file) in files.into_iter().enumerate() {
+        let too_old = retention.retention_days > 0
+            && now.saturating_sub(file.modified) > retention.retention_days * SECS_PER_DAY;
+        let too_many = retention.keep_files > 0 && index >= retention.keep_files;
+        let too_big = retention.max_size_mb > 0
+            && kept_size + file.size > retention.max_size_mb * BYTES_PER_MIB;
+        if too_old || (index > 0 && (too_many || too_big)) {
+            prune.push(file.path);
+        } else {
+            kept_size += file.size;
+        }
+    }
+    prune
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogFile, LogRetention, SECS_PER_DAY, files_to_prune};
+    use std::path::PathBuf;
+
+    fn log(name: &str, age_days: u64, size_mb: u64) -> LogFile {
+        LogFile {
+            path: PathBuf::from(name),
+            modified: 100 * SECS_PER_DAY - age_days * SECS_PER_DAY,
+            size: size_mb * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn prunes_by_age_count_and_size() {
+        let retention = LogRetention {
+            retention_days: 7,
+            keep_files: 3,
+            max_size_mb: 10,
+        };
+        let files = vec![
+            log("old", 30, 1),
+            log("newest", 0, 1),
+            log("big", 1, 8),
+            log("small", 2, 1),
+            log("fourth", 3, 1),
+        ];
+        let pruned = files_to_prune(files, 100 * SECS_PER_DAY, &retention);
+        assert_eq!(
+            pruned,
+            [PathBuf::from("fourth"), PathBuf::from("old")],
+            "small still fits into 10 MiB; fourth exceeds keep_files"
+        );
+    }
+
+    #[test]
+    fn keeps_newest_even_if_over_limits() {
+        let retention = LogRetention {
+            retention_days: 0,
+            keep_files: 1,
+            max_size_mb: 1,
+        };
+        let pruned = files_to_prune(vec![log("huge", 5, 50)], 100 * SECS_PER_DAY, &retention);
+        assert!(pruned.is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    external::{ExternalGame, Runner, expand_home},
+    proton::installed_protons,
+    runtime::{config_dir_base, data_home, find_in_path, home_dir, parallel_map},
+};
+
+const LAUNCHER: &str = "lutris";
+/// Каталоги Lutris из Flatpak (относительно `$HOME`).
+const FLATPAK_CONFIG: &str = ".var/app/net.lutris.Lutris/config/lutris";
+const FLATPAK_DATA: &str = ".var/app/net.lutris.Lutris/data/lutris";
+/// Сборки Wine, скачанные Lutris: `runners/wine/<version>/bin/wine`.
+const WINE_RUNNERS_DIR: &str = "runners/wine";
+
+/// Игры Lutris с префиксом Wine из YAML-конфигов (`games/<slug>-<id>.yml`). Конфиги
+/// только читаются; название — slug игры, базу `pga.db` не открываем.
+pub fn installed_games() -> Vec<ExternalGame> {
+    let mut games: Vec<ExternalGame> = Vec::new();
+    for (games_dir, data_dir) in game_dirs() {
+        let Ok(entries) = fs::read_dir(&games_dir) else {
+            continue;
+        };
+        let configs: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "yml"))
+            .collect();
+        // Поиск раннера может обходить сборки Proton: YAML разбираем параллельно.
+        let parsed = parallel_map(&configs, |path| parse_game(path, &data_dir));
+        for game in parsed.into_iter().flatten() {
+            if !games.iter().any(|known| known.id == game.id) {
+                games.push(game);
+            }
+        }
+    }
+    games
+}
+
+fn parse_game(path: &Path, data_dir: &Path) -> Option<ExternalGame> {
+    let slug = config_slug(&path.file_stem()?.to_string_lossy());
+    let content = fs::read_to_string(path).ok()?;
+    let runner = game_runner(&content, data_dir)?;
+    let install_path = yaml_value(&content, "game", "exe")
+        .map(|exe| expand_home(&exe))
+        .and_then(|exe| exe.parent().map(|dir| dir.to_string_lossy().into_owned()));
+    Some(ExternalGame {
+        id: format!("{LAUNCHER}-{slug}"),
+        launcher: LAUNCHER,
+        name: slug.replace('-', " "),
+        install_path,
+        runner,
+    })
+}
+
+/// Пары (каталог YAML игр, каталог данных Lutris): старые версии хранят игры в
+/// `~/.config/lutris/games`, новые — в `~/.local/share/lutris/games`.
+fn game_dirs() -> Vec<(PathBuf, PathBuf)> {
+    let native_data = data_home().join("lutris");
+    let flatpak_data = home_dir().join(FLATPAK_DATA);
+    [
+        (native_data.join("games"), native_data.clone()),
+        (config_dir_base().join("lutris/games"), native_data),
+        (flatpak_data.join("games"), flatpak_data.clone()),
+        (home_dir().join(FLATPAK_CONFIG).join("games"), flatpak_data),
+    ]
+    .into_iter()
+    .filter(|(games_dir, _)| games_dir.is_dir())
+    .collect()
+}
+
+/// `the-witcher-3-1700000000` -> `the-witcher-3`: Lutris дописывает к slug время создания.
+fn config_slug(stem: &str) -> String {
+    match stem.rsplit_once('-') {
+        Some((slug, suffix))
+            if !slug.is_empty() && suffix.bytes().all(|byte| byte.is_ascii_digit()) =>
+        {
+            slug.to_string()
+        }
+        _ => stem.to_string(),
+    }
+}
+
+/// Раннер игры: версия из `wine.version` среди сборок Lutris, затем среди сборок Proton
+/// (Lutris запускает их через umu), иначе последняя сборка Lutris или системный Wine.
+fn game_runner(content: &str, data_dir: &Path) -> Option<Runner> {
+    let prefix = expand_home(&yaml_value(content, "game", "prefix")?);
+    let runners = data_dir.join(WINE_RUNNERS_DIR);
+    let version = yaml_value(content, "wine", "version");
+
+    if let Some(version) = version.as_deref() {
+        let binary = runners.join(version).join("bin/wine");
+        if binary.is_file() {
+            return Some(Runner::Wine { binary, prefix });
+        }
+        if let Some(build) = installed_protons()
+            .into_iter()
+            .find(|build| build.name == version)
+        {
+            // umu кладёт в префикс ссылку `pfx -> .`, так что префикс служит и compatdata.
+            return Some(Runner::Proton {
+                script: build.script(),
+                compat_data: prefix,
+            });
+        }
+    }
+
+    let mut downloaded: Vec<PathBuf> = fs::read_dir(&runners)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join("bin/wine"))
+        .filter(|binary| binary.is_file())
+        .collect();
+    downloaded.sort();
+    let binary = downloaded.pop().or_else(|| find_in_path("wine"))?;
+    Some(Runner::Wine { binary, prefix })
+}
+
+/// Значение `key` внутри секции верхнего уровня `section` простого YAML Lutris
+/// (`section:` и вложенные `key: value` с отступом); кавычки снимаются.
+fn yaml_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            in_section = line.trim_end().strip_suffix(':') == Some(section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((name, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        if name.trim() == key {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+                .or_else(|| {
+                    value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                })
+                .unwrap_or(value);
+            return (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{config_slug, yaml_value};
+
+    #[test]
+    fn reads_game_config_values() {
+        let config = "game:\n  arch: win64\n  exe: '/g/Witcher 3/bin/witcher3.exe'\n  prefix: /p/witcher\n\
+                      system:\n  prefix: /wrong\nwine:\n  version: lutris-GE-Proton8-26-x86_64\n";
+        assert_eq!(
+            yaml_value(config, "game", "exe").as_deref(),
+            Some("/g/Witcher 3/bin/witcher3.exe")
+        );
+        assert_eq!(
+            yaml_value(config, "game", "prefix").as_deref(),
+            Some("/p/witcher")
+        );
+        assert_eq!(
+            yaml_value(config, "wine", "version").as_deref(),
+            Some("lutris-GE-Proton8-26-x86_64")
+        );
+        assert_eq!(yaml_value(config, "wine", "dxvk"), None);
+    }
+
+    #[test]
+    fn strips_config_timestamp() {
+        assert_eq!(config_slug("the-witcher-3-1700000000"), "the-witcher-3");
+        assert_eq!(config_slug("quake"), "quake");
+    }
+}
@@ -1,44 +1,317 @@
 #![warn(clippy::pedantic)]
 
+mod audit;
+mod batch;
 mod cli;
+mod companions;
+mod completions;
+mod config;
+mod context;
+mod crashdump;
+mod debug;
+mod deps;
+mod discord;
+mod doctor;
+mod du;
 mod env_store;
+mod environment;
+mod ephemeral;
+mod explain;
+mod export;
+mod external;
+mod filter;
+mod ge_proton;
 mod handlers;
+mod heroic;
+mod i18n;
+mod info;
+mod inject;
+mod launch_options;
+mod lifecycle;
+mod listing_cache;
+mod lock;
+mod logs;
+mod lutris;
+mod man;
+mod monitor;
+mod mount;
+mod names;
+mod nxm;
+mod output;
+mod paths;
+mod persist;
+mod prefix;
+mod priority;
+mod programs;
+mod prompt;
+mod proton;
+mod protontricks;
+mod registry;
+mod reinit;
 mod runtime;
+mod sandbox;
+mod saves;
+mod scope;
+mod setup;
 mod shell;
+mod shortcuts;
+mod signals;
+mod snapshot;
 mod steam;
+mod steam_shortcut;
+mod tools;
+mod trace;
+mod ui;
+mod umu;
+mod vdf;
+mod verbosity;
+mod wine;
 
-use clap::{CommandFactory, Parser};
-use clap_complete::generate;
-use colored::Colorize;
-use std::{env, io};
+use clap::FromArgMatches;
+use std::{env, io, path::Path, time::Duration};
 
-use crate::cli::{Cli, Commands};
-use crate::runtime::{debug_enabled, runtime_root};
+use crate::cli::{AuditCommands, Cli, Commands, ExplainCommands, RunArgs};
+use crate::debug::DebugTarget;
+use crate::filter::NameFilter;
+use crate::handlers::{LsOptions, RunOptions};
+use crate::lifecycle::StopTimeouts;
+use crate::runtime::{debug_enabled, keep_enabled, runtime_root};
+use crate::setup::SetupMode;
+use crate::verbosity::Verbosity;
 
 fn main() -> io::Result<()> {
-    let debug = debug_enabled();
-    if debug {
-        eprintln!(
-            "{} Protonhax started with args: {:?}",
-            "DEBUG".bold().cyan(),
-            env::args().collect::<Vec<String>>()
-        );
+    // PROTONHAX_DEBUG действует ещё до разбора аргументов, чтобы видеть и ошибки clap.
+    if debug_enabled() {
+        Verbosity::Debug.set();
     }
+    verbosity::debug(format_args!(
+        "Protonhax started with args: {:?}",
+        env::args().collect::<Vec<String>>()
+    ));
 
-    let cli = Cli::parse();
-    let phd = runtime_root();
+    let cli = Cli::from_arg_matches(&cli::command().get_matches()).unwrap_or_else(|err| err.exit());
+    Verbosity::from_flags(cli.quiet, cli.verbose, debug_enabled()).set();
+    ui::set_assume_yes(cli.yes);
+    let phd = runtime_root(cli.runtime_dir);
+    dispatch(cli.command, &phd, cli.json)
+}
 
-    match cli.command {
-        Commands::Init { cmd } => handlers::handle_init(&phd, cmd, debug),
-        Commands::Ls { long, json } => handlers::handle_ls(&phd, long, json),
-        Commands::Run { appid, cmd } => handlers::handle_run(&phd, &appid, &cmd),
-        Commands::Cmd { appid } => handlers::handle_cmd(&phd, &appid),
-        Commands::Exec { appid, cmd } => handlers::handle_exec(&phd, &appid, &cmd),
-        Commands::Doctor => handlers::handle_doctor(&phd),
-        Commands::Completions { shell } => {
-            let mut cmd = Cli::command();
-            generate(shell, &mut cmd, "protonhax", &mut io::stdout());
-            Ok(())
+/// Одна ветка на подкоманду: длина растёт вместе с их числом.
+#[allow(clippy::too_many_lines)]
+fn dispatch(command: Commands, phd: &Path, json: bool) -> io::Result<()> {
+    match command {
+        Commands::Init { keep, cmd } => handlers::handle_init(phd, cmd, keep || keep_enabled()),
+        Commands::Ls {
+            pattern,
+            regex,
+            long,
+            plain,
+            all,
+            installed,
+            watch,
+        } => {
+            let filter = NameFilter::new(pattern, regex);
+            if installed {
+                external::handle_ls_installed(long, json, filter.as_ref())
+            } else {
+                let options = LsOptions {
+                    long,
+                    plain,
+                    all,
+                    filter,
+                };
+                match watch {
+                    Some(secs) => {
+                        monitor::handle_ls_watch(phd, &options, Duration::from_secs(secs))
+                    }
+                    None => handlers::handle_ls(phd, json, &options),
+                }
+            }
+        }
+        Commands::Run(args) => {
+            let (appid, cmd, options) = run_options(args);
+            handlers::handle_run(phd, &appid, &cmd, &options)
+        }
+        Commands::Explain {
+            command: ExplainCommands::Run(args),
+        } => {
+            let (appid, cmd, options) = run_options(args);
+            explain::handle_explain_run(phd, &appid, &cmd, &options, json)
+        }
+        Commands::Cmd { wine, appid } => handlers::handle_cmd(phd, &appid, wine.as_deref()),
+        Commands::Exec {
+            timeout,
+            priority,
+            sandbox,
+            appid,
+            cmd,
+        } => handlers::handle_exec(
+            phd,
+            &appid,
+            &cmd,
+            timeout.map(Duration::from_secs),
+            priority,
+            sandbox,
+        ),
+        Commands::Batch {
+            appid,
+            file,
+            keep_going,
+        } => batch::handle_batch(phd, &appid, file.as_deref(), keep_going),
+        Commands::Wineboot { appid, mode } => prefix::handle_wineboot(phd, &appid, &mode),
+        Commands::Pause { appid } => signals::handle_pause(phd, &appid, true),
+        Commands::Resume { appid } => signals::handle_pause(phd, &appid, false),
+        Commands::Killwin { appid, image } => signals::handle_killwin(phd, &appid, &image),
+        Commands::Debug {
+            appid,
+            attach,
+            launch,
+            gdb,
+            args,
+        } => {
+            let target = match (attach, launch) {
+                (Some(pid), _) => DebugTarget::Attach(pid),
+                (None, Some(exe)) => DebugTarget::Launch([vec![exe], args].concat()),
+                (None, None) => DebugTarget::Shell,
+            };
+            debug::handle_debug(phd, &appid, target, gdb)
+        }
+        Commands::Crashdump { appid, output } => crashdump::handle_crashdump(phd, &appid, output),
+        Commands::Saves { appid, backup } => {
+            saves::handle_saves(phd, &appid, backup.as_deref(), json)
+        }
+        Commands::Env { command } => environment::handle_env(phd, command, json),
+        Commands::Audit {
+            command:
+                AuditCommands::Show {
+                    appid,
+                    action,
+                    limit,
+                },
+        } => audit::handle_audit_show(appid.as_deref(), action, limit, json),
+        Commands::Export { appid, output } => export::handle_export(phd, &appid, output.as_deref()),
+        Commands::Stop {
+            appid,
+            grace,
+            kill_after,
+        } => lifecycle::handle_stop(
+            phd,
+            &appid,
+            &StopTimeouts {
+                shutdown: Duration::from_secs(grace),
+                term: Duration::from_secs(kill_after),
+            },
+        ),
+        Commands::Restart {
+            appid,
+            no_launch,
+            timeout,
+        } => lifecycle::handle_restart(phd, &appid, !no_launch, Duration::from_secs(timeout)),
+        Commands::Reinit { appid } => reinit::handle_reinit(phd, &appid),
+        Commands::Register { appid } => persist::handle_register(phd, &appid),
+        Commands::Prefix { command } => prefix::handle_prefix(phd, command, json),
+        Commands::Protons { command } => proton::handle_protons(phd, command, json),
+        Commands::Doctor => doctor::handle_doctor(phd, json),
+        Commands::Man { dir } => man::handle_man(dir.as_deref()),
+        Commands::Tool { command } => tools::handle_tool(phd, command, json),
+        Commands::Nxm { command } => nxm::handle_nxm(phd, command),
+        Commands::Mount {
+            appid,
+            host_dir,
+            target,
+        } => mount::handle_mount(phd, &appid, &host_dir, &target),
+        Commands::Unmount { appid, target } => mount::handle_unmount(phd, &appid, &target),
+        Commands::Inject { command } => inject::handle_inject(phd, command),
+        Commands::Pt {
+            appid,
+            gui,
+            command,
+            args,
+        } => protontricks::handle_pt(phd, &appid, gui, command.as_deref(), &args),
+        Commands::InstallDeps { appid, deps } => {
+            deps::handle_install_deps(phd, &appid, &deps, json)
+        }
+        Commands::Info { appid } => info::handle_info(phd, &appid, json),
+        Commands::Monitor => monitor::handle_monitor(phd, json),
+        Commands::Prompt => prompt::handle_prompt(phd, json),
+        Commands::Du { appid } => du::handle_du(phd, &appid, json),
+        Commands::Open { appid, target } => paths::handle_open(phd, &appid, target),
+        Commands::Cd {
+            print,
+            appid,
+            target,
+        } => paths::handle_cd(phd, &appid, target, print),
+        Commands::Shortcuts { appid, command } => {
+            shortcuts::handle_shortcuts(phd, appid, command, json)
+        }
+        Commands::Shortcut { command } => steam_shortcut::handle_shortcut(command),
+        Commands::Setup {
+            appids,
+            all,
+            interactive,
+            remove,
+            migrate,
+            wrapper,
+        } => {
+            let mode = if interactive {
+                SetupMode::Interactive
+            } else if remove {
+                SetupMode::Remove
+            } else if migrate {
+                SetupMode::Migrate(wrapper)
+            } else {
+                SetupMode::Install
+            };
+            setup::handle_setup(&appids, all, &mode)
+        }
+        Commands::Programs { appid } => programs::handle_programs(phd, &appid, json),
+        Commands::Snapshot { command } => snapshot::handle_snapshot(phd, command, json),
+        Commands::Completions { shell, install } => completions::handle_completions(shell, install),
+        Commands::Complete {
+            describe,
+            refresh,
+            words,
+        } => {
+            if refresh {
+                listing_cache::refresh(phd)
+            } else {
+                completions::handle_complete(phd, &words, describe)
+            }
         }
     }
 }
+
+/// Цель, команда и остальные параметры `run` (их же разбирает `explain run`).
+fn run_options(args: RunArgs) -> (String, Vec<String>, RunOptions) {
+    let RunArgs {
+        no_auto,
+        timeout,
+        strace,
+        wine,
+        win_paths,
+        preload,
+        vkbasalt,
+        obs_capture,
+        ephemeral,
+        verb,
+        limits,
+        priority,
+        appid,
+        cmd,
+    } = args;
+    let options = RunOptions {
+        no_auto,
+        timeout: timeout.map(Duration::from_secs),
+        strace,
+        wine,
+        win_paths,
+        preload,
+        vkbasalt,
+        obs_capture,
+        ephemeral,
+        verb,
+        limits,
+        priority,
+    };
+    (appid, cmd, options)
+}
@@ -0,0 +1,23 @@
+use clap_mangen::Man;
+use colored::Colorize;
+use std::{fs, io, path::Path};
+
+use crate::cli;
+
+/// Без `--dir` печатает страницу верхнего уровня в stdout (`protonhax man | man -l -`),
+/// иначе пишет `protonhax.1` и `protonhax-<subcommand>.1` для всех подкоманд.
+pub fn handle_man(dir: Option<&Path>) -> io::Result<()> {
+    let cmd = cli::command();
+    let Some(dir) = dir else {
+        return Man::new(cmd).render(&mut io::stdout());
+    };
+
+    fs::create_dir_all(dir)?;
+    clap_mangen::generate_to(cmd, dir)?;
+    println!(
+        "{} man pages written to {}",
+        "OK".green().bold(),
+        dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    io::{self, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cli::fail,
+    context::{ContextStatus, RunningApp, context_appid, read_running_app},
+    env_store::ENV_FILE,
+    handlers::{LsOptions, ls_entries, ls_line},
+    lock::ContextLock,
+    runtime::{ensure_runtime_root, unix_now_secs},
+};
+
+/// Заголовок `struct inotify_event`: wd, mask, cookie, len.
+const EVENT_HEADER_LEN: usize = 16;
+const EVENT_BUFFER_LEN: usize = 64 * 1024;
+/// Сколько ждать, пока `init` допишет только что созданный контекст.
+const CONTEXT_READY_ATTEMPTS: u32 = 100;
+const CONTEXT_READY_DELAY: Duration = Duration::from_millis(50);
+/// Сколько `ls --watch` подсвечивает запустившиеся и завершившиеся игры.
+const WATCH_HIGHLIGHT: Duration = Duration::from_secs(10);
+/// Очистка экрана и курсор в начало.
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+struct InotifyEvent {
+    mask: u32,
+    name: String,
+}
+
+/// Следит за каталогом контекстов через inotify и печатает появление и исчезновение
+/// контекстов: строками или NDJSON (`--json`) для статус-баров и скриптов.
+pub fn handle_monitor(phd: &Path, json_output: bool) -> io::Result<()> {
+    ensure_runtime_root(phd)?;
+    let inotify = watch(phd)?;
+    let mut buffer = vec![0u8; EVENT_BUFFER_LEN];
+    loop {
+        let len = read_events(&inotify, &mut buffer)?;
+        for event in parse_events(&buffer[..len]) {
+            if event.mask & libc::IN_IGNORED != 0 {
+                fail(&format!("Каталог контекстов {} удалён", phd.display()));
+            }
+            if event.mask & libc::IN_ISDIR == 0 || event.name.starts_with('.') {
+                continue;
+            }
+            if event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                if let Some(app) = wait_for_context(&phd.join(&event.name))? {
+                    print_created(&app, json_output);
+                }
+            } else {
+                print_removed(&event.name, json_output);
+            }
+        }
+    }
+}
+
+/// Что `ls --watch` помнит о контексте между перерисовками.
+struct Seen {
+    name: Option<String>,
+    status: ContextStatus,
+    /// Когда контекст появился; `None` — был на экране с самого начала.
+    started: Option<Instant>,
+    /// Когда игра перестала работать: контекст исчез или сменил состояние.
+    exited: Option<Instant>,
+}
+
+/// `ls` на весь экран: перерисовывается по событиям inotify в каталоге контекстов
+/// и не реже раза в `interval`, новые игры помечены `+`, завершившиеся — `-`.
+pub fn handle_ls_watch(phd: &Path, options: &LsOptions, interval: Duration) -> io::Result<()> {
+    ensure_runtime_root(phd)?;
+    let inotify = watch(phd)?;
+    let mut buffer = vec![0u8; EVENT_BUFFER_LEN];
+    let mut seen: HashMap<String, Seen> = HashMap::new();
+    let mut first = true;
+    loop {
+        let apps = ls_entries(phd, options.long, options.all, options.filter.as_ref())?;
+        let now = Instant::now();
+        let recent = |at: Option<Instant>| at.is_some_and(|at| now - at < WATCH_HIGHLIGHT);
+
+        let mut current = HashMap::new();
+        let mut lines = Vec::new();
+        for (app, status) in apps {
+            let previous = seen.remove(&app.id);
+            let exited = match &previous {
+                Some(previous) if previous.status == status => previous.exited,
+                Some(previous) if previous.status == ContextStatus::Running => Some(now),
+                _ => None,
+            };
+            let started = match &previous {
+                // Контекст `--keep` переиспользован новым `init`.
+                Some(previous) if status == ContextStatus::Running && previous.status != status => {
+                    Some(now)
+                }
+                Some(previous) => previous.started,
+                None => (!first).then_some(now),
+            };
+            let marker = if recent(exited) {
+                "-".red().bold()
+            } else if recent(started) {
+                "+".green().bold()
+            } else {
+                " ".normal()
+            };
+            let id = app.id.clone();
+            let name = app.name.clone();
+            lines.push(format!("{marker} {}", ls_line(app, status, options)));
+            current.insert(
+                id,
+                Seen {
+                    name,
+                    status,
+                    started,
+                    exited,
+                },
+            );
+        }
+        // Исчезнувшие контексты показываем, пока не истечёт подсветка.
+        let mut gone: Vec<(String, Seen)> = seen
+            .drain()
+            .map(|(id, mut entry)| {
+                entry.exited.get_or_insert(now);
+                (id, entry)
+            })
+            .filter(|(_, entry)| recent(entry.exited))
+            .collect();
+        gone.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let screen = watch_screen(phd, interval, &lines, &gone);
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(screen.as_bytes())?;
+        stdout.flush()?;
+        seen = current;
+        seen.extend(gone);
+        first = false;
+        wait_for_change(&inotify, &mut buffer, interval)?;
+    }
+}
+
+fn watch_screen(
+    phd: &Path,
+    interval: Duration,
+    lines: &[String],
+    gone: &[(String, Seen)],
+) -> String {
+    let header = format!(
+        "protonhax ls — {} (обновление раз в {} с и по событиям, Ctrl+C — выход)",
+        phd.display(),
+        interval.as_secs()
+    );
+    let mut rows = vec![header.dimmed().to_string(), String::new()];
+    rows.extend(lines.iter().cloned());
+    rows.extend(gone.iter().map(|(id, entry)| {
+        let name = entry.name.as_deref().unwrap_or_default();
+        format!(
+            "{} {} {}",
+            "-".red().bold(),
+            id.dimmed(),
+            format!("{name} (exited)").trim_start().red()
+        )
+    }));
+    if lines.is_empty() && gone.is_empty() {
+        rows.push("Нет запущенных игр".dimmed().to_string());
+    }
+    format!("{CLEAR_SCREEN}{}\n", rows.join("\n"))
+}
+
+/// Ждёт событие inotify не дольше `timeout` и вычитывает накопившиеся события.
+fn wait_for_change(inotify: &OwnedFd, buffer: &mut [u8], timeout: Duration) -> io::Result<()> {
+    let mut poll_fd = libc::pollfd {
+        fd: inotify.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+    // SAFETY: poll_fd живёт до конца вызова, передаётся ровно один элемент.
+    let ready = unsafe { libc::poll(&raw mut poll_fd, 1, timeout_ms) };
+    if ready < 0 {
+        let err = io::Error::last_os_error();
+        return if err.kind() == io::ErrorKind::Interrupted {
+            Ok(())
+        } else {
+            Err(err)
+        };
+    }
+    if ready > 0 {
+        // `init` дописывает контекст уже после создания каталога.
+        thread::sleep(CONTEXT_READY_DELAY);
+        read_events(inotify, buffer)?;
+    }
+    Ok(())
+}
+
+fn watch(dir: &Path) -> io::Result<OwnedFd> {
+    // SAFETY: inotify_init1 не принимает указателей; результат проверяется ниже.
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd только что получен и больше нигде не закрывается.
+    let inotify = unsafe { OwnedFd::from_raw_fd(fd) };
+    let path = CString::new(dir.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mask = libc::IN_CREATE
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_ONLYDIR;
+    // SAFETY: path — корректная C-строка, живущая до конца вызова.
+    if unsafe { libc::inotify_add_watch(inotify.as_raw_fd(), path.as_ptr(), mask) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(inotify)
+}
+
+fn read_events(inotify: &OwnedFd, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        // SAFETY: буфер доступен для записи на всю переданную длину.
+        let len = unsafe {
+            libc::read(
+                inotify.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+            )
+        };
+        if let Ok(len) = usize::try_from(len) {
+            return Ok(len);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// Разбирает буфер `read` из inotify; имя дополнено NUL до выравнивания.
+fn parse_events(buffer: &[u8]) -> Vec<InotifyEvent> {
+    let field = |offset: usize| -> Option<u32> {
+        let bytes = buffer.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+    };
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while let (Some(mask), Some(name_len)) = (field(offset + 4), field(offset + 12)) {
+        let name_start = offset + EVENT_HEADER_LEN;
+        let Some(name) = buffer.get(name_start..name_start + name_len as usize) else {
+            break;
+        };
+        let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+        events.push(InotifyEvent {
+            mask,
+            name: String::from_utf8_lossy(name).into_owned(),
+        });
+        offset = name_start + name_len as usize;
+    }
+    events
+}
+
+/// Каталог появляется раньше, чем `init` берёт блокировку и пишет файлы: ждём, пока
+/// в нём окажется окружение. `None` — контекст исчез, не успев появиться.
+fn wait_for_context(app_dir: &Path) -> io::Result<Option<RunningApp>> {
+    for _ in 0..CONTEXT_READY_ATTEMPTS {
+        let Some(lock) = ContextLock::try_shared(app_dir)? else {
+            return Ok(None);
+        };
+        if app_dir.join(ENV_FILE).exists() {
+            return Ok(Some(read_running_app(app_dir.to_path_buf(), true)));
+        }
+        drop(lock);
+        thread::sleep(CONTEXT_READY_DELAY);
+    }
+    Ok(Some(read_running_app(app_dir.to_path_buf(), true)))
+}
+
+fn print_created(app: &RunningApp, json_output: bool) {
+    if json_output {
+        println!(
+            "{}",
+            json!({
+                "event": "created",
+                "time": unix_now_secs(),
+                "id": app.id,
+                "appid": app.appid,
+                "name": app.name,
+                "install_path": app.install_path,
+                "pid": app.pid,
+                "path": app.path,
+            })
+        );
+        return;
+    }
+    let mut parts = vec![format!("{} {}", "+".green().bold(), app.id.green())];
+    if let Some(name) = &app.name {
+        parts.push(name.yellow().to_string());
+    }
+    if let Some(install_path) = &app.install_path {
+        parts.push(install_path.dimmed().to_string());
+    }
+    if let Some(pid) = app.pid {
+        parts.push(format!("pid {pid}").dimmed().to_string());
+    }
+    println!("{}", parts.join("  "));
+}
+
+fn print_removed(id: &str, json_output: bool) {
+    if json_output {
+        println!(
+            "{}",
+            json!({
+                "event": "removed",
+                "time": unix_now_secs(),
+                "id": id,
+                "appid": context_appid(id),
+            })
+        );
+    } else {
+        println!("{} {}", "-".red().bold(), id.dimmed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_events;
+
+    fn raw_event(mask: u32, name: &str) -> Vec<u8> {
+        let mut padded = name.as_bytes().to_vec();
+        padded.resize(name.len().next_multiple_of(16) + 16, 0);
+        let mut event = Vec::new();
+        event.extend(1i32.to_ne_bytes());
+        event.extend(mask.to_ne_bytes());
+        event.extend(0u32.to_ne_bytes());
+        event.extend(u32::try_from(padded.len()).unwrap().to_ne_bytes());
+        event.extend(padded);
+        event
+    }
+
+    #[test]
+    fn parses_padded_events() {
+        let mut buffer = raw_event(libc::IN_CREATE | libc::IN_ISDIR, "620");
+        buffer.extend(raw_event(libc::IN_DELETE | libc::IN_ISDIR, "620.4242"));
+        let events = parse_events(&buffer);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].name, "620");
+        assert_eq!(events[0].mask, libc::IN_CREATE | libc::IN_ISDIR);
+        assert_eq!(events[1].name, "620.4242");
+    }
+}
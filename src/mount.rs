@@ -0,0 +1,96 @@
+use colored::Colorize;
+use std::{
+    fs, io,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::fail,
+    wine::{locate_prefix, split_windows_path, windows_to_host},
+};
+
+/// Диски, которые Wine создаёт сам: `unmount` их не трогает.
+const SYSTEM_DRIVES: &[char] = &['c', 'z'];
+
+/// Делает каталог хоста видимым внутри префикса: симлинк по Windows-пути (`C:\Mods`)
+/// или новый диск в `dosdevices` (`M:`).
+pub fn handle_mount(phd: &Path, selector: &str, host_dir: &Path, target: &str) -> io::Result<()> {
+    if !host_dir.is_dir() {
+        fail(&format!("Каталог не найден: {}", host_dir.display()));
+    }
+    let host_dir = fs::canonicalize(host_dir)?;
+    let prefix = locate_prefix(phd, selector)?;
+    let link = mount_point(&prefix.pfx, target);
+
+    if let Ok(current) = fs::read_link(&link) {
+        if current == host_dir {
+            println!(
+                "{} {target} уже указывает на {}",
+                "OK".green().bold(),
+                host_dir.display()
+            );
+            return Ok(());
+        }
+        fail(&format!(
+            "{target} уже указывает на {} (сначала `protonhax unmount`)",
+            current.display()
+        ));
+    }
+    if link.exists() {
+        fail(&format!(
+            "{target} уже существует в префиксе: {}",
+            link.display()
+        ));
+    }
+    if let Some(parent) = link.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    symlink(&host_dir, &link)?;
+    println!("{} {target} → {}", "OK".green().bold(), host_dir.display());
+    Ok(())
+}
+
+/// Убирает симлинк, созданный `mount`; настоящие каталоги префикса не удаляет.
+pub fn handle_unmount(phd: &Path, selector: &str, target: &str) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let link = mount_point(&prefix.pfx, target);
+    if split_windows_path(target)
+        .is_some_and(|(drive, components)| components.is_empty() && SYSTEM_DRIVES.contains(&drive))
+    {
+        fail(&format!("{target} — системный диск Wine, его не отключить"));
+    }
+    match fs::symlink_metadata(&link) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::remove_file(&link)?,
+        Ok(_) => fail(&format!(
+            "{target} — не симлинк, созданный mount: {}",
+            link.display()
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            fail(&format!("{target} не подключён"));
+        }
+        Err(err) => return Err(err),
+    }
+    println!("{} {target} отключён", "OK".green().bold());
+    Ok(())
+}
+
+/// Путь симлинка на хосте: `dosdevices/<буква>:` для диска, иначе путь внутри диска
+/// (без учёта регистра, последний компонент не раскрывается — он и есть ссылка).
+fn mount_point(pfx: &Path, target: &str) -> PathBuf {
+    let Some((drive, components)) = split_windows_path(target) else {
+        fail(&format!(
+            "Ожидается Windows-путь вроде C:\\Mods или диск вроде M:, получено \"{target}\""
+        ));
+    };
+    if components.is_empty() {
+        return pfx.join("dosdevices").join(format!("{drive}:"));
+    }
+    let Some(link) = windows_to_host(pfx, target) else {
+        fail(&format!(
+            "Диск {}: не найден в префиксе",
+            drive.to_ascii_uppercase()
+        ));
+    };
+    link
+}
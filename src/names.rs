@@ -0,0 +1,453 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    process,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{
+    runtime::{cache_dir, unix_now_secs},
+    steam::steam_roots,
+};
+
+const NAMES_FILE: &str = "names.json";
+const APPINFO_FILE: &str = "appcache/appinfo.vdf";
+const STORE_API_URL: &str = "https://store.steampowered.com/api/appdetails";
+const STORE_API_TIMEOUT_SECS: &str = "3";
+/// Ярлыки сторонних игр получают appid с установленным старшим битом; в Steam их нет.
+const SHORTCUT_APPID_MIN: u64 = 1 << 31;
+/// Steam ответил, что приложения нет: магазин спрашиваем снова через неделю.
+const UNKNOWN_RETRY_SECS: u64 = 7 * 24 * 60 * 60;
+/// Сеть недоступна: без этой паузы каждый `ls -l` ждал бы `curl` по каждому appid.
+const OFFLINE_RETRY_SECS: u64 = 60 * 60;
+/// В `appinfo.vdf` имени нет, а магазин не спрашивали: Steam мог с тех пор обновить файл.
+const LOCAL_MISS_RETRY_SECS: u64 = 24 * 60 * 60;
+
+/// Метаданные читаются параллельно (`parallel_map`): без замка потоки теряли бы записи
+/// друг друга в `names.json`.
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+/// Имена из `appinfo.vdf` (он бывает больше 100 МБ): файл читается один раз за процесс,
+/// а не на каждый промах кэша в каждом потоке.
+static APPINFO_NAMES: OnceLock<HashMap<u32, String>> = OnceLock::new();
+
+const APPINFO_V27: u32 = 0x0756_4427;
+const APPINFO_V28: u32 = 0x0756_4428;
+const APPINFO_V29: u32 = 0x0756_4429;
+
+/// Можно ли ради имени идти в сеть.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameSource {
+    /// Только кэш и `appinfo.vdf`: списки, автодополнение и поиск по имени не ждут сеть.
+    Local,
+    /// Ещё и API магазина: команды об одном контексте (`info`, `export`).
+    Store,
+}
+
+enum NameLookup {
+    Found(String),
+    /// Steam ответил, что такого приложения нет.
+    Unknown,
+    /// Сеть недоступна.
+    Unavailable,
+}
+
+/// Запись `names.json`: строка — найденное имя (как в прежнем формате), объект — промах.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CachedName {
+    Found(String),
+    Missing {
+        /// Unix-время, до которого поиск не повторяется.
+        retry_after: u64,
+        /// Спрашивали ли магазин: промах без него не отвечает поиску с `NameSource::Store`.
+        asked_store: bool,
+    },
+}
+
+/// Название игры без манифеста: из кэша, `appinfo.vdf` клиента Steam или (для
+/// `NameSource::Store`) API магазина. Промахи тоже кэшируются на время.
+pub fn lookup_app_name(appid: &str, source: NameSource) -> Option<String> {
+    let numeric = appid.parse::<u32>().ok()?;
+    if u64::from(numeric) >= SHORTCUT_APPID_MIN {
+        return None;
+    }
+
+    let now = unix_now_secs();
+    if let Some(answer) = cached_answer(with_cache_lock(load_cache).get(appid), source, now) {
+        return answer;
+    }
+
+    let (name, entry) = match appinfo_names().get(&numeric) {
+        Some(name) => (Some(name.clone()), CachedName::Found(name.clone())),
+        None if source == NameSource::Local => (None, missing(now, LOCAL_MISS_RETRY_SECS, false)),
+        None => match name_from_store(appid) {
+            NameLookup::Found(name) => (Some(name.clone()), CachedName::Found(name)),
+            NameLookup::Unknown => (None, missing(now, UNKNOWN_RETRY_SECS, true)),
+            NameLookup::Unavailable => (None, missing(now, OFFLINE_RETRY_SECS, true)),
+        },
+    };
+
+    // Кэш перечитываем: пока шёл поиск, другой поток мог его дополнить.
+    with_cache_lock(|| {
+        let mut cache = load_cache();
+        cache.insert(appid.to_string(), entry);
+        // Кэш — лишь оптимизация: ошибка записи не должна мешать выводу.
+        let _ = save_cache(&cache);
+    });
+    name
+}
+
+fn missing(now: u64, retry_secs: u64, asked_store: bool) -> CachedName {
+    CachedName::Missing {
+        retry_after: now + retry_secs,
+        asked_store,
+    }
+}
+
+/// Ответ из кэша: `Some(name)` — искать заново не нужно, `None` — нужно. Пустая строка
+/// прежнего формата («Steam такого не знает») без срока считается устаревшей.
+#[allow(clippy::option_option)]
+fn cached_answer(
+    entry: Option<&CachedName>,
+    source: NameSource,
+    now: u64,
+) -> Option<Option<String>> {
+    match entry? {
+        CachedName::Found(name) if !name.is_empty() => Some(Some(name.clone())),
+        CachedName::Found(_) => None,
+        CachedName::Missing {
+            retry_after,
+            asked_store,
+        } => (now < *retry_after && (*asked_store || source == NameSource::Local)).then_some(None),
+    }
+}
+
+/// Поиск имени идёт без замка: он может ждать сеть.
+fn with_cache_lock<T>(action: impl FnOnce() -> T) -> T {
+    let _guard = CACHE_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    action()
+}
+
+fn names_path() -> PathBuf {
+    cache_dir().join(NAMES_FILE)
+}
+
+fn load_cache() -> BTreeMap<String, CachedName> {
+    fs::read_to_string(names_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(cache: &BTreeMap<String, CachedName>) -> std::io::Result<()> {
+    let path = names_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let serialized = serde_json::to_string_pretty(cache).map_err(std::io::Error::other)?;
+    // Замок только внутри процесса: фоновый `complete --refresh` и `ls -l` пишут кэш из
+    // разных процессов, поэтому временный файл у каждого свой.
+    let tmp_path = path.with_extension(format!("json.{}.tmp", process::id()));
+    fs::write(&tmp_path, serialized)?;
+    fs::rename(tmp_path, path)
+}
+
+fn appinfo_names() -> &'static HashMap<u32, String> {
+    APPINFO_NAMES.get_or_init(|| {
+        let mut names = HashMap::new();
+        for root in steam_roots() {
+            let Ok(data) = fs::read(root.join(APPINFO_FILE)) else {
+                continue;
+            };
+            for (appid, name) in parse_appinfo_names(&data) {
+                names.entry(appid).or_insert(name);
+            }
+        }
+        names
+    })
+}
+
+fn name_from_store(appid: &str) -> NameLookup {
+    let url = format!("{STORE_API_URL}?appids={appid}&filters=basic");
+    let output = process::Command::new("curl")
+        .args(["-fsS", "--max-time", STORE_API_TIMEOUT_SECS])
+        .arg(url)
+        .stderr(process::Stdio::null())
+        .output();
+    let Ok(output) = output else {
+        return NameLookup::Unavailable;
+    };
+    if !output.status.success() {
+        return NameLookup::Unavailable;
+    }
+    let Ok(response) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+        return NameLookup::Unavailable;
+    };
+
+    match response[appid]["data"]["name"].as_str() {
+        Some(name) => NameLookup::Found(name.to_string()),
+        None if response[appid]["success"] == false => NameLookup::Unknown,
+        None => NameLookup::Unavailable,
+    }
+}
+
+/// Все `appinfo/common/name` из бинарного `appinfo.vdf` (версии 27–29); записи, которые не
+/// разобрались, пропускаются.
+fn parse_appinfo_names(data: &[u8]) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let mut reader = ByteReader { data, pos: 0 };
+    let (Some(magic), Some(_universe)) = (reader.u32(), reader.u32()) else {
+        return names;
+    };
+
+    let (entry_header, string_table) = match magic {
+        APPINFO_V27 => (40, None),
+        APPINFO_V28 => (60, None),
+        APPINFO_V29 => {
+            let table = reader
+                .u64()
+                .and_then(|offset| usize::try_from(offset).ok())
+                .and_then(|offset| read_string_table(data.get(offset..)?));
+            let Some(table) = table else {
+                return names;
+            };
+            (60, Some(table))
+        }
+        _ => return names,
+    };
+
+    while let Some(entry_appid) = reader.u32().filter(|appid| *appid != 0) {
+        let Some(entry) = reader
+            .u32()
+            .and_then(|size| usize::try_from(size).ok())
+            .and_then(|size| reader.take(size))
+        else {
+            break;
+        };
+        let name = entry.get(entry_header..).and_then(|data| {
+            find_common_name(&mut ByteReader { data, pos: 0 }, string_table.as_deref())
+        });
+        if let Some(name) = name {
+            names.insert(entry_appid, name);
+        }
+    }
+    names
+}
+
+fn read_string_table(data: &[u8]) -> Option<Vec<String>> {
+    let mut reader = ByteReader { data, pos: 0 };
+    let count = reader.u32()?;
+    (0..count).map(|_| reader.cstr()).collect()
+}
+
+/// Обходит бинарный `KeyValues`; в v29 ключи — индексы в таблице строк.
+fn find_common_name(reader: &mut ByteReader, string_table: Option<&[String]>) -> Option<String> {
+    const MAP: u8 = 0x00;
+    const STRING: u8 = 0x01;
+    const INT32: u8 = 0x02;
+    const FLOAT: u8 = 0x03;
+    const POINTER: u8 = 0x04;
+    const COLOR: u8 = 0x06;
+    const UINT64: u8 = 0x07;
+    const MAP_END: u8 = 0x08;
+    const INT64: u8 = 0x0A;
+
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        let kind = reader.u8()?;
+        if kind == MAP_END {
+            path.pop()?;
+            continue;
+        }
+
+        let key = match string_table {
+            Some(table) => table.get(usize::try_from(reader.u32()?).ok()?)?.clone(),
+            None => reader.cstr()?,
+        };
+        match kind {
+            MAP => path.push(key),
+            STRING => {
+                let value = reader.cstr()?;
+                if key == "name" && path == ["appinfo", "common"] {
+                    return Some(value);
+                }
+            }
+            INT32 | FLOAT | POINTER | COLOR => {
+                reader.take(4)?;
+            }
+            UINT64 | INT64 => {
+                reader.take(8)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn cstr(&mut self) -> Option<String> {
+        let rest = self.data.get(self.pos..)?;
+        let len = rest.iter().position(|&byte| byte == 0)?;
+        let value = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        APPINFO_V28, APPINFO_V29, CachedName, NameSource, cached_answer, parse_appinfo_names,
+    };
+
+    fn cstr(out: &mut Vec<u8>, value: &str) {
+        out.extend_from_slice(value.as_bytes());
+        out.push(0);
+    }
+
+    fn push_entry(out: &mut Vec<u8>, appid: u32, vdf: &[u8]) {
+        out.extend_from_slice(&appid.to_le_bytes());
+        let size = u32::try_from(60 + vdf.len()).unwrap();
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&[0; 60]);
+        out.extend_from_slice(vdf);
+    }
+
+    #[test]
+    fn reads_name_from_appinfo_v28() {
+        let mut vdf = vec![0x00];
+        cstr(&mut vdf, "appinfo");
+        vdf.push(0x02);
+        cstr(&mut vdf, "appid");
+        vdf.extend_from_slice(&10u32.to_le_bytes());
+        vdf.push(0x00);
+        cstr(&mut vdf, "common");
+        vdf.push(0x01);
+        cstr(&mut vdf, "name");
+        cstr(&mut vdf, "Counter-Strike");
+        vdf.extend_from_slice(&[0x08, 0x08, 0x08]);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&APPINFO_V28.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        push_entry(&mut data, 7, &[0x08]);
+        push_entry(&mut data, 10, &vdf);
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        let names = parse_appinfo_names(&data);
+        assert_eq!(names.get(&10).map(String::as_str), Some("Counter-Strike"));
+        assert_eq!(names.get(&7), None);
+        assert_eq!(names.get(&20), None);
+    }
+
+    #[test]
+    fn reads_name_from_appinfo_v29_string_table() {
+        // Ключи: 0 = appinfo, 1 = common, 2 = name.
+        let mut vdf = vec![0x00];
+        vdf.extend_from_slice(&0u32.to_le_bytes());
+        vdf.push(0x00);
+        vdf.extend_from_slice(&1u32.to_le_bytes());
+        vdf.push(0x01);
+        vdf.extend_from_slice(&2u32.to_le_bytes());
+        cstr(&mut vdf, "Half-Life");
+        vdf.extend_from_slice(&[0x08, 0x08, 0x08]);
+
+        let mut entries = Vec::new();
+        push_entry(&mut entries, 70, &vdf);
+        entries.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&APPINFO_V29.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        let table_offset = u64::try_from(16 + entries.len()).unwrap();
+        data.extend_from_slice(&table_offset.to_le_bytes());
+        data.extend_from_slice(&entries);
+        data.extend_from_slice(&3u32.to_le_bytes());
+        for key in ["appinfo", "common", "name"] {
+            cstr(&mut data, key);
+        }
+
+        assert_eq!(
+            parse_appinfo_names(&data).get(&70).map(String::as_str),
+            Some("Half-Life")
+        );
+    }
+
+    #[test]
+    fn caches_misses_until_retry() {
+        let found = CachedName::Found("Portal 2".into());
+        assert_eq!(
+            cached_answer(Some(&found), NameSource::Store, 0),
+            Some(Some("Portal 2".into()))
+        );
+        // Пустая строка прежнего формата — без срока, ищем заново.
+        assert_eq!(
+            cached_answer(
+                Some(&CachedName::Found(String::new())),
+                NameSource::Local,
+                0
+            ),
+            None
+        );
+
+        let offline = CachedName::Missing {
+            retry_after: 100,
+            asked_store: true,
+        };
+        assert_eq!(
+            cached_answer(Some(&offline), NameSource::Store, 99),
+            Some(None)
+        );
+        assert_eq!(cached_answer(Some(&offline), NameSource::Store, 100), None);
+
+        let local = CachedName::Missing {
+            retry_after: 100,
+            asked_store: false,
+        };
+        assert_eq!(
+            cached_answer(Some(&local), NameSource::Local, 50),
+            Some(None)
+        );
+        assert_eq!(cached_answer(Some(&local), NameSource::Store, 50), None);
+        assert_eq!(cached_answer(None, NameSource::Local, 0), None);
+
+        let cache: std::collections::BTreeMap<String, CachedName> = serde_json::from_str(
+            r#"{"620": "Portal 2", "1": {"retry_after": 5, "asked_store": true}}"#,
+        )
+        .unwrap();
+        assert_eq!(cache["620"], found);
+        assert_eq!(
+            cache["1"],
+            CachedName::Missing {
+                retry_after: 5,
+                asked_store: true
+            }
+        );
+    }
+}
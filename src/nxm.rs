@@ -0,0 +1,124 @@
+use colored::Colorize;
+use std::{env, fs, io, path::Path, process};
+
+use crate::{
+    cli::NxmCommands,
+    config::{Config, NxmConfig},
+    runtime::data_home,
+    tools::run_tool,
+};
+
+const DESKTOP_FILE: &str = "protonhax-nxm.desktop";
+const NXM_MIME: &str = "x-scheme-handler/nxm";
+const NXM_SCHEME: &str = "nxm://";
+
+pub fn handle_nxm(phd: &Path, command: NxmCommands) -> io::Result<()> {
+    match command {
+        NxmCommands::Install { appid, tool } => handle_nxm_install(appid, tool),
+        NxmCommands::Handle { url } => handle_nxm_url(phd, &url),
+    }
+}
+
+fn handle_nxm_install(appid: String, tool: String) -> io::Result<()> {
+    let config = Config::load()?;
+    if !config.tools.contains_key(&tool) {
+        eprintln!(
+            "{} Инструмент \"{tool}\" не зарегистрирован. Сначала выполните `protonhax tool add {tool} <path>`.",
+            "Ошибка:".bold().red()
+        );
+        process::exit(2);
+    }
+
+    let applications_dir = data_home().join("applications");
+    fs::create_dir_all(&applications_dir)?;
+    let desktop_path = applications_dir.join(DESKTOP_FILE);
+    let exe = env::current_exe()?;
+    fs::write(&desktop_path, desktop_entry(&exe.to_string_lossy()))?;
+    println!("Создан {}", desktop_path.display().to_string().dimmed());
+
+    // Обновление кэша необязательно: на многих системах его нет.
+    let _ = process::Command::new("update-desktop-database")
+        .arg(&applications_dir)
+        .status();
+
+    match process::Command::new("xdg-mime")
+        .args(["default", DESKTOP_FILE, NXM_MIME])
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        _ => {
+            eprintln!(
+                "{} Не удалось выполнить xdg-mime; зарегистрируйте обработчик вручную:",
+                "Предупреждение:".bold().yellow()
+            );
+            eprintln!("  xdg-mime default {DESKTOP_FILE} {NXM_MIME}");
+        }
+    }
+
+    println!(
+        "Ссылки nxm:// будут открываться инструментом {} в контексте {}",
+        tool.green(),
+        appid.green()
+    );
+    Config::save_nxm(&NxmConfig { appid, tool })
+}
+
+fn handle_nxm_url(phd: &Path, url: &str) -> io::Result<()> {
+    if !url.starts_with(NXM_SCHEME) {
+        eprintln!(
+            "{} Ожидалась ссылка nxm://, получено: {url}",
+            "Ошибка:".bold().red()
+        );
+        process::exit(2);
+    }
+
+    let config = Config::load()?;
+    let Some(nxm) = config.nxm else {
+        eprintln!(
+            "{} Обработчик nxm не настроен (см. `protonhax nxm install`).",
+            "Ошибка:".bold().red()
+        );
+        process::exit(2);
+    };
+
+    run_tool(phd, &nxm.appid, Some(&nxm.tool), &[url.to_string()], false)
+}
+
+fn desktop_entry(exe: &str) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=protonhax NXM handler\n\
+         Exec={} nxm handle %u\n\
+         MimeType={NXM_MIME};\n\
+         NoDisplay=true\n\
+         Terminal=false\n",
+        desktop_quote(exe)
+    )
+}
+
+/// Кавычки для аргумента `Exec=` по спецификации Desktop Entry.
+fn desktop_quote(arg: &str) -> String {
+    let mut res = String::with_capacity(arg.len() + 2);
+    res.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::desktop_entry;
+
+    #[test]
+    fn desktop_entry_quotes_exe_path() {
+        let entry = desktop_entry("/home/user/my bin/protonhax");
+        assert!(entry.contains("Exec=\"/home/user/my bin/protonhax\" nxm handle %u\n"));
+        assert!(entry.contains("MimeType=x-scheme-handler/nxm;\n"));
+    }
+}
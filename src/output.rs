@@ -0,0 +1,9 @@
+use serde::Serialize;
+use std::io;
+
+/// Общий вывод для глобального `--json`: один документ на команду, отформатированный JSON.
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> io::Result<()> {
+    let serialized = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+    println!("{serialized}");
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::fail,
+    runtime::exit_with_status,
+    steam::find_app_meta,
+    verbosity,
+    wine::{PrefixContext, locate_prefix},
+};
+
+const STEAMUSER_PROFILE: &str = "drive_c/users/steamuser";
+/// Насколько глубоко в каталоге игры искать файл по голому имени.
+const INSTALL_SEARCH_DEPTH: usize = 3;
+
+/// Именованные каталоги игры и её префикса.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PathTarget {
+    /// Game install directory (from the app manifest)
+    Install,
+    /// Wine prefix (`compatdata/<appid>/pfx`)
+    Pfx,
+    /// `C:` drive of the prefix
+    #[value(name = "drive_c", alias = "drive-c")]
+    DriveC,
+    /// `Saved Games` in the steamuser profile
+    Save,
+    /// `AppData` in the steamuser profile
+    Appdata,
+    /// `AppData/Roaming` in the steamuser profile
+    Roaming,
+    /// `AppData/Local` in the steamuser profile
+    Local,
+    /// `Documents` in the steamuser profile (`My Documents` in old prefixes)
+    Documents,
+    /// `Desktop` in the steamuser profile
+    Desktop,
+}
+
+impl PathTarget {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Install => "install",
+            Self::Pfx => "pfx",
+            Self::DriveC => "drive_c",
+            Self::Save => "save",
+            Self::Appdata => "appdata",
+            Self::Roaming => "roaming",
+            Self::Local => "local",
+            Self::Documents => "documents",
+            Self::Desktop => "desktop",
+        }
+    }
+}
+
+/// Находит каталог `target` для игры `selector` и завершает процесс, если он неизвестен.
+pub fn resolve_path_target(phd: &Path, selector: &str, target: PathTarget) -> io::Result<PathBuf> {
+    let prefix = locate_prefix(phd, selector)?;
+    match target_path(&prefix, target) {
+        Some(path) => Ok(path),
+        None => fail(&format!(
+            "Каталог {} для {} не найден",
+            target.label(),
+            prefix.appid
+        )),
+    }
+}
+
+fn target_path(prefix: &PrefixContext, target: PathTarget) -> Option<PathBuf> {
+    let profile = prefix.pfx.join(STEAMUSER_PROFILE);
+    let path = match target {
+        PathTarget::Install => PathBuf::from(find_app_meta(&prefix.appid).install_path?),
+        PathTarget::Pfx => prefix.pfx.clone(),
+        PathTarget::DriveC => prefix.pfx.join("drive_c"),
+        PathTarget::Save => profile.join("Saved Games"),
+        PathTarget::Appdata => profile.join("AppData"),
+        PathTarget::Roaming => profile.join("AppData/Roaming"),
+        PathTarget::Local => profile.join("AppData/Local"),
+        // Старые префиксы Proton создавали профиль в стиле Windows XP.
+        PathTarget::Documents => ["Documents", "My Documents"]
+            .iter()
+            .map(|name| profile.join(name))
+            .find(|path| path.is_dir())?,
+        PathTarget::Desktop => profile.join("Desktop"),
+    };
+    path.is_dir().then_some(path)
+}
+
+pub fn handle_open(phd: &Path, selector: &str, target: PathTarget) -> io::Result<()> {
+    let path = resolve_path_target(phd, selector, target)?;
+    let status = process::Command::new("xdg-open").arg(&path).status()?;
+    if !status.success() {
+        exit_with_status(status);
+    }
+    Ok(())
+}
+
+pub fn handle_cd(phd: &Path, selector: &str, target: PathTarget, print: bool) -> io::Result<()> {
+    let path = resolve_path_target(phd, selector, target)?;
+    println!("{}", path.display());
+
+    if !print && io::stdout().is_terminal() {
+        verbosity::info(format_args!(
+            "protonhax не может сменить каталог оболочки: используйте {} из `protonhax completions <shell>` или cd \"$(protonhax cd --print ...)\"",
+            "phcd".green()
+        ));
+    }
+    Ok(())
+}
+
+/// Файл команды `run` в каталоге игры, если его нет ни по абсолютному пути, ни
+/// относительно текущего каталога: `bin/x64/tool.exe` — от корня игры, голое имя — поиском
+/// в ширину без учёта регистра (ближайший к корню файл).
+pub fn find_in_install_dir(install_dir: &Path, program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.is_absolute() || path.exists() {
+        return None;
+    }
+    let candidate = install_dir.join(path);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+    if program.contains('/') {
+        return None;
+    }
+
+    let mut queue = VecDeque::from([(install_dir.to_path_buf(), 0)]);
+    while let Some((dir, depth)) = queue.pop_front() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() {
+                if depth < INSTALL_SEARCH_DEPTH {
+                    queue.push_back((entry, depth + 1));
+                }
+            } else if entry
+                .file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(program))
+            {
+                return Some(entry);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,46 @@
+use colored::Colorize;
+use std::{fs, io, os::unix::fs::DirBuilderExt, path::Path};
+
+use crate::{
+    cli::fail,
+    context::{EXE_FILE, PFX_FILE, registered_root, resolve_target_app},
+    env_store::ENV_FILE,
+    steam::META_FILE,
+};
+
+/// Файлы живого контекста, которых достаточно для `run`/`cmd`/`exec` без игры.
+const PERSISTED_FILES: &[&str] = &[EXE_FILE, PFX_FILE, ENV_FILE, META_FILE];
+
+/// Копирует живой контекст в `$XDG_DATA_HOME/protonhax/contexts/<appid>`.
+pub fn handle_register(phd: &Path, selector: &str) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let root = registered_root();
+    if target.app_dir.starts_with(&root) {
+        fail(&format!(
+            "Нет запущенной игры {} — зарегистрировать можно только живой контекст",
+            target.appid
+        ));
+    }
+
+    let dest = root.join(&target.appid);
+    let existed = dest.exists();
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dest)?;
+    for file in PERSISTED_FILES {
+        let src = target.app_dir.join(file);
+        if src.is_file() {
+            fs::copy(&src, dest.join(file))?;
+        }
+    }
+
+    let action = if existed { "updated" } else { "registered" };
+    println!(
+        "{} context {} {action}: {}",
+        "OK".green().bold(),
+        target.appid,
+        dest.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,389 @@
+use clap::Args;
+use colored::Colorize;
+use std::{
+    fs, io,
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::{PrefixCommands, fail},
+    config::Config,
+    context::{EXE_FILE, PFX_FILE, STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV, registered_root},
+    doctor::Report,
+    env_store::{EnvFilter, set_env_var, write_env_file},
+    output::print_json,
+    proton::{find_proton, parse_version_file},
+    registry::{decode_reg_file, parse_reg},
+    runtime::data_home,
+    steam::{
+        AppMeta, STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV, STEAM_GAME_ID_ENV, steam_roots,
+        write_cached_meta,
+    },
+    ui, verbosity,
+    wine::{PrefixContext, locate_prefix, resolve_prefix},
+};
+
+/// Действие `wineboot`; флаги взаимоисключающие, так что по сути это одно значение.
+#[derive(Args)]
+#[group(multiple = false)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct WinebootMode {
+    /// Shut the prefix down: end all its processes (`wineboot -s`)
+    #[arg(long)]
+    pub shutdown: bool,
+    /// Simulate a Windows reboot: run pending renames and `RunOnce` entries (`wineboot -r`, default)
+    #[arg(long)]
+    pub restart: bool,
+    /// Update the prefix after a Wine/Proton upgrade (`wineboot -u`)
+    #[arg(long)]
+    pub update: bool,
+    /// Run the first-boot initialization (`wineboot -i`)
+    #[arg(long)]
+    pub init: bool,
+}
+
+impl WinebootMode {
+    fn flag(&self) -> &'static str {
+        if self.shutdown {
+            "-s"
+        } else if self.update {
+            "-u"
+        } else if self.init {
+            "-i"
+        } else {
+            "-r"
+        }
+    }
+}
+
+/// Без `--appid` Proton получает нулевой appid: игровые фиксы не применяются.
+const DEFAULT_PREFIX_APPID: &str = "0";
+const REGISTRY_HEADER: &str = "WINE REGISTRY Version 2";
+const WINDOWS_VERSION_KEY: &str = r"Software\Microsoft\Windows NT\CurrentVersion";
+/// Глобальная версия Windows из winecfg (`user.reg`).
+const WINE_SETTINGS_KEY: &str = r"Software\Wine";
+const EXPECTED_WINDOWS_VERSIONS: &[&str] = &["win10", "win11"];
+/// Сколько уровней `drive_c` просматривать в поисках битых симлинков.
+const SYMLINK_SCAN_DEPTH: usize = 4;
+/// Сколько битых симлинков показать в отчёте.
+const BROKEN_SAMPLES: usize = 3;
+
+pub fn handle_prefix(phd: &Path, command: PrefixCommands, json_output: bool) -> io::Result<()> {
+    match command {
+        PrefixCommands::New {
+            name,
+            proton,
+            appid,
+        } => create_prefix(&name, proton.as_deref(), appid.as_deref()),
+        PrefixCommands::Verify { appid } => {
+            verify_prefix(&locate_prefix(phd, &appid)?, json_output)
+        }
+        PrefixCommands::Repair { appid } => repair_prefix(phd, &appid, json_output),
+    }
+}
+
+/// Создаёт compatdata в `$XDG_DATA_HOME/protonhax/prefixes/<name>` через `proton run wineboot`
+/// и регистрирует его как постоянный контекст `<name>`.
+fn create_prefix(name: &str, proton: Option<&str>, appid: Option<&str>) -> io::Result<()> {
+    if !is_valid_prefix_name(name) {
+        fail(&format!(
+            "Некорректное имя префикса \"{name}\": допустимы буквы, цифры, `-` и `_`, \
+             и имя не должно состоять из одних цифр (их занимают appid)"
+        ));
+    }
+    let context_dir = registered_root().join(name);
+    if context_dir.exists() {
+        fail(&format!(
+            "Контекст {name} уже существует: {}",
+            context_dir.display()
+        ));
+    }
+    let Some(build) = find_proton(proton) else {
+        fail(&match proton {
+            Some(proton) => format!("Сборка Proton \"{proton}\" не найдена"),
+            None => "Не найдено ни одной сборки Proton (укажите --proton <путь>)".to_string(),
+        });
+    };
+
+    let compat_data = data_home().join("protonhax").join("prefixes").join(name);
+    fs::create_dir_all(&compat_data)?;
+    let appid = appid.unwrap_or(DEFAULT_PREFIX_APPID);
+    set_env_var(STEAM_COMPAT_DATA_PATH_ENV, &compat_data.to_string_lossy());
+    set_env_var(STEAM_APP_ID_ENV, appid);
+    set_env_var(STEAM_GAME_ID_ENV, appid);
+    if let Some(steam_root) = steam_roots().first() {
+        set_env_var(
+            STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV,
+            &steam_root.to_string_lossy(),
+        );
+    }
+
+    println!(
+        "{} {} ({})",
+        "Creating prefix with".bold(),
+        build.name,
+        compat_data.display()
+    );
+    let status = process::Command::new(build.script())
+        .args(["run", "wineboot", "-u"])
+        .status()?;
+    if !status.success() {
+        fail(&format!(
+            "proton run wineboot завершился с ошибкой ({status})"
+        ));
+    }
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&context_dir)?;
+    fs::write(
+        context_dir.join(EXE_FILE),
+        build.script().to_string_lossy().as_bytes(),
+    )?;
+    fs::write(
+        context_dir.join(PFX_FILE),
+        compat_data.join("pfx").to_string_lossy().as_bytes(),
+    )?;
+    let filter = Config::load().map_or_else(|_| EnvFilter::default(), |config| config.env);
+    write_env_file(&context_dir, &filter)?;
+    write_cached_meta(
+        &context_dir,
+        &AppMeta {
+            name: Some(name.to_string()),
+            install_path: None,
+            ..AppMeta::default()
+        },
+    )?;
+
+    println!(
+        "{} prefix {name} ready; use it as `protonhax run {name} <cmd>`",
+        "OK".green().bold()
+    );
+    Ok(())
+}
+
+fn verify_prefix(prefix: &PrefixContext, json_output: bool) -> io::Result<()> {
+    let mut report = Report::default();
+    report.section("Registry");
+    let system = inspect_registry(&prefix.pfx, "system.reg", &mut report);
+    let user = inspect_registry(&prefix.pfx, "user.reg", &mut report);
+    inspect_registry(&prefix.pfx, "userdef.reg", &mut report);
+
+    report.section("Windows");
+    inspect_windows_version(system.as_deref(), user.as_deref(), &mut report);
+    inspect_prefix_version(prefix, &mut report);
+
+    report.section("Symlinks");
+    inspect_symlinks(&prefix.pfx, &mut report);
+
+    if json_output {
+        print_json(&report)?;
+    } else {
+        report.print(&format!("protonhax prefix verify {}", prefix.appid));
+    }
+    if report.errors > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Файл реестра Wine: текст с заголовком `WINE REGISTRY Version 2` и хотя бы одним ключом.
+fn inspect_registry(pfx: &Path, file: &str, report: &mut Report) -> Option<String> {
+    let Ok(bytes) = fs::read(pfx.join(file)) else {
+        report.err(format!("{file} отсутствует — префикс не инициализирован"));
+        return None;
+    };
+    let content = decode_reg_file(&bytes);
+    if !content.starts_with(REGISTRY_HEADER) {
+        report.err(format!(
+            "{file} повреждён: нет заголовка «{REGISTRY_HEADER}»"
+        ));
+        return None;
+    }
+    let keys = parse_reg(&content).len();
+    if keys == 0 {
+        report.err(format!("{file} повреждён: ни одного ключа"));
+        return None;
+    }
+    report.ok(format!("{file}: ключей {keys}"));
+    Some(content)
+}
+
+/// Proton рассчитан на Windows 10; другая версия из winecfg ломает лаунчеры и .NET.
+fn inspect_windows_version(system: Option<&str>, user: Option<&str>, report: &mut Report) {
+    let override_version = user.and_then(|content| {
+        parse_reg(content)
+            .into_iter()
+            .find(|key| key.path.eq_ignore_ascii_case(WINE_SETTINGS_KEY))
+            .and_then(|key| key.string("Version").map(str::to_string))
+    });
+    if let Some(version) = override_version
+        && !EXPECTED_WINDOWS_VERSIONS.contains(&version.as_str())
+    {
+        report.warn(format!(
+            "winecfg переопределяет версию Windows: {version} — вернуть: protonhax pt <target> win10"
+        ));
+    }
+
+    let Some(system) = system else {
+        return;
+    };
+    let keys = parse_reg(system);
+    let Some(current) = keys
+        .iter()
+        .find(|key| key.path.eq_ignore_ascii_case(WINDOWS_VERSION_KEY))
+    else {
+        report.warn("версия Windows не найдена в system.reg");
+        return;
+    };
+    let product = current.string("ProductName").unwrap_or("?");
+    let build = current.string("CurrentBuild").unwrap_or("?");
+    if product.contains("Windows 10") || product.contains("Windows 11") {
+        report.ok(format!("{product} (сборка {build})"));
+    } else {
+        report.warn(format!(
+            "{product} (сборка {build}) — Proton ожидает Windows 10: protonhax pt <target> win10"
+        ));
+    }
+}
+
+/// Сборка, создавшая префикс (`compatdata/version`), и текущая сборка контекста.
+fn inspect_prefix_version(prefix: &PrefixContext, report: &mut Report) {
+    if prefix.wine.is_some() {
+        return;
+    }
+    let created = fs::read_to_string(prefix.compat_data().join("version"))
+        .ok()
+        .and_then(|content| parse_version_file(&content));
+    let current = fs::read_to_string(prefix.proton_dir().join("version"))
+        .ok()
+        .and_then(|content| parse_version_file(&content));
+    match (created, current) {
+        (Some(created), Some(current)) if created != current => report.info(format!(
+            "префикс обновлён сборкой {created}, сейчас {current}: Proton обновит его при запуске"
+        )),
+        (Some(created), _) => report.ok(format!("сборка префикса: {created}")),
+        (None, _) => report.info("compatdata/version отсутствует"),
+    }
+}
+
+/// Битые симлинки в `dosdevices` и `drive_c`: чаще всего ведут в удалённую сборку Proton
+/// или в отключённый диск.
+fn inspect_symlinks(pfx: &Path, report: &mut Report) {
+    let dosdevices = pfx.join("dosdevices");
+    if fs::metadata(dosdevices.join("c:")).is_ok_and(|meta| meta.is_dir()) {
+        report.ok("dosdevices/c: → drive_c");
+    } else {
+        report.err("dosdevices/c: отсутствует или битый — нужен prefix repair");
+    }
+
+    let mut broken = Vec::new();
+    collect_broken_symlinks(&dosdevices, 0, &mut broken);
+    collect_broken_symlinks(&pfx.join("drive_c"), 0, &mut broken);
+    if broken.is_empty() {
+        report.ok("битых симлинков нет");
+        return;
+    }
+    let samples: Vec<String> = broken
+        .iter()
+        .take(BROKEN_SAMPLES)
+        .map(|path| {
+            let target = fs::read_link(path).unwrap_or_default();
+            format!("{} → {}", path.display(), target.display())
+        })
+        .collect();
+    report.err(format!(
+        "битых симлинков: {} ({}{})",
+        broken.len(),
+        samples.join(", "),
+        if broken.len() > BROKEN_SAMPLES {
+            ", ..."
+        } else {
+            ""
+        }
+    ));
+}
+
+/// Обходит каталог без перехода по симлинкам; глубина ограничена, чтобы не читать
+/// установленные в `drive_c` игры целиком.
+fn collect_broken_symlinks(dir: &Path, depth: usize, broken: &mut Vec<PathBuf>) {
+    if depth > SYMLINK_SCAN_DEPTH {
+        return;
+    }
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if kind.is_symlink() {
+            if fs::metadata(&path).is_err() {
+                broken.push(path);
+            }
+        } else if kind.is_dir() {
+            collect_broken_symlinks(&path, depth + 1, broken);
+        }
+    }
+}
+
+/// `wineboot -u` пересоздаёт служебные файлы и ключи префикса, не трогая программы
+/// и сохранения — то же делает Proton после обновления.
+fn repair_prefix(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let prefix = resolve_prefix(phd, selector)?;
+    ui::confirm_or_abort(&format!(
+        "Пересобрать служебные файлы префикса {} (wineboot -u)?",
+        prefix.pfx.display()
+    ));
+    let mut wineboot = prefix.wineboot("-u");
+    println!("{} {}", "Updating prefix".bold(), prefix.pfx.display());
+    let status = wineboot.status()?;
+    if !status.success() {
+        fail(&format!("wineboot -u завершился с ошибкой ({status})"));
+    }
+    verify_prefix(&prefix, json_output)
+}
+
+/// Перезапуск префикса как после перезагрузки Windows: нужен после импорта реестра и
+/// установки DLL, которые ждут reboot. Без флага — `--restart`.
+pub fn handle_wineboot(phd: &Path, selector: &str, mode: &WinebootMode) -> io::Result<()> {
+    let prefix = resolve_prefix(phd, selector)?;
+    let flag = mode.flag();
+    if prefix.is_live() && mode.shutdown {
+        verbosity::info(format_args!(
+            "wineboot {flag} завершит и процессы запущенной игры"
+        ));
+    }
+    let status = prefix.wineboot(flag).status()?;
+    if !status.success() {
+        fail(&format!("wineboot {flag} завершился с ошибкой ({status})"));
+    }
+    println!(
+        "{} wineboot {flag}: {}",
+        "OK".green().bold(),
+        prefix.pfx.display()
+    );
+    Ok(())
+}
+
+fn is_valid_prefix_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        && !name.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_prefix_name;
+
+    #[test]
+    fn validates_prefix_names() {
+        assert!(is_valid_prefix_name("scratch-9"));
+        assert!(!is_valid_prefix_name("1217060"));
+        assert!(!is_valid_prefix_name("../evil"));
+        assert!(!is_valid_prefix_name(""));
+    }
+}
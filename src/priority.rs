@@ -0,0 +1,118 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::{io, os::unix::process::CommandExt, process};
+
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+const IOPRIO_CLASS_RT: i32 = 1;
+const IOPRIO_CLASS_BE: i32 = 2;
+const IOPRIO_CLASS_IDLE: i32 = 3;
+/// Уровень по умолчанию для best-effort и realtime, как у ionice(1).
+const IOPRIO_DEFAULT_LEVEL: i32 = 4;
+
+/// Приоритет CPU и ввода-вывода для запускаемой команды.
+#[derive(Args, Clone, Default, Serialize, Deserialize)]
+pub struct Priority {
+    /// CPU niceness for the launched command (-20..19, higher is nicer)
+    #[arg(long, value_name = "N", allow_hyphen_values = true, value_parser = clap::value_parser!(i32).range(-20..=19))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// I/O scheduling: `idle`, `best-effort[:0-7]` or `realtime[:0-7]`
+    #[arg(long, value_name = "CLASS[:LEVEL]", value_parser = parse_ionice)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ionice: Option<String>,
+}
+
+impl Priority {
+    /// Значения из командной строки важнее значений по умолчанию из конфига.
+    pub fn or(self, defaults: Option<&Self>) -> Self {
+        let Some(defaults) = defaults else {
+            return self;
+        };
+        Self {
+            nice: self.nice.or(defaults.nice),
+            ionice: self.ionice.or_else(|| defaults.ionice.clone()),
+        }
+    }
+
+    /// Как приоритеты выглядят в цепочке обёрток `explain run`.
+    pub fn describe(&self) -> Vec<String> {
+        let nice = self.nice.map(|nice| format!("nice {nice}"));
+        let ionice = self
+            .ionice
+            .as_ref()
+            .map(|ionice| format!("ionice {ionice}"));
+        nice.into_iter().chain(ionice).collect()
+    }
+}
+
+/// Выставляет приоритеты в дочернем процессе перед exec; Wine и его потомки их наследуют.
+pub fn apply(priority: &Priority, cmd: &mut process::Command) {
+    let nice = priority.nice;
+    let ioprio = priority
+        .ionice
+        .as_deref()
+        .and_then(|value| ioprio_value(value).ok());
+    if nice.is_none() && ioprio.is_none() {
+        return;
+    }
+
+    // SAFETY: в замыкании только системные вызовы setpriority/ioprio_set без аллокаций,
+    // что допустимо между fork и exec.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(nice) = nice
+                && libc::setpriority(libc::PRIO_PROCESS, 0, nice) == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+            if let Some(ioprio) = ioprio
+                && libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+fn parse_ionice(value: &str) -> Result<String, String> {
+    ioprio_value(value).map(|_| value.to_string())
+}
+
+fn ioprio_value(value: &str) -> Result<i32, String> {
+    let (class, level) = match value.split_once(':') {
+        Some((class, level)) => (class, Some(level)),
+        None => (value, None),
+    };
+    let class = match class {
+        "idle" => IOPRIO_CLASS_IDLE,
+        "best-effort" | "be" => IOPRIO_CLASS_BE,
+        "realtime" | "rt" => IOPRIO_CLASS_RT,
+        _ => return Err("ожидается idle, best-effort[:0-7] или realtime[:0-7]".to_string()),
+    };
+
+    let level = match level {
+        _ if class == IOPRIO_CLASS_IDLE => 0,
+        None => IOPRIO_DEFAULT_LEVEL,
+        Some(level) => match level.parse::<i32>() {
+            Ok(level) if (0..=7).contains(&level) => level,
+            _ => return Err("уровень ionice должен быть от 0 до 7".to_string()),
+        },
+    };
+    Ok((class << IOPRIO_CLASS_SHIFT) | level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ioprio_value;
+
+    #[test]
+    fn encodes_io_priorities() {
+        assert_eq!(ioprio_value("idle"), Ok(3 << 13));
+        assert_eq!(ioprio_value("best-effort"), Ok((2 << 13) | 4));
+        assert_eq!(ioprio_value("rt:0"), Ok(1 << 13));
+        assert!(ioprio_value("best-effort:9").is_err());
+        assert!(ioprio_value("low").is_err());
+    }
+}
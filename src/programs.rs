@@ -0,0 +1,121 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{fs, io, path::Path};
+
+use crate::{output::print_json, registry::parse_reg, wine::locate_prefix};
+
+const UNINSTALL_KEYS: &[&str] = &[
+    r"Software\Microsoft\Windows\CurrentVersion\Uninstall\",
+    r"Software\Wow6432Node\Microsoft\Windows\CurrentVersion\Uninstall\",
+];
+
+struct InstalledProgram {
+    name: String,
+    version: Option<String>,
+    install_location: Option<String>,
+    uninstall: Option<String>,
+}
+
+pub fn handle_programs(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let mut programs = Vec::new();
+    for reg_file in ["system.reg", "user.reg"] {
+        // Префикс мог ещё не пройти wineboot — отсутствующий файл не ошибка.
+        let Ok(content) = fs::read_to_string(prefix.pfx.join(reg_file)) else {
+            continue;
+        };
+        collect_programs(&content, &mut programs);
+    }
+
+    programs.sort_by_key(|program| program.name.to_lowercase());
+    programs.dedup_by(|left, right| {
+        left.name == right.name && left.install_location == right.install_location
+    });
+
+    if json_output {
+        let data: Vec<_> = programs
+            .iter()
+            .map(|program| {
+                json!({
+                    "name": program.name,
+                    "version": program.version,
+                    "install_location": program.install_location,
+                    "uninstall": program.uninstall,
+                })
+            })
+            .collect();
+        return print_json(&json!({ "appid": prefix.appid, "programs": data }));
+    }
+
+    for program in programs {
+        let mut parts = vec![program.name.green().to_string()];
+        if let Some(version) = program.version {
+            parts.push(version.yellow().to_string());
+        }
+        if let Some(location) = program.install_location {
+            parts.push(location.dimmed().to_string());
+        }
+        println!("{}", parts.join("  "));
+        if let Some(uninstall) = program.uninstall {
+            println!("    {} {uninstall}", "uninstall:".dimmed());
+        }
+    }
+    Ok(())
+}
+
+fn collect_programs(content: &str, out: &mut Vec<InstalledProgram>) {
+    for key in parse_reg(content) {
+        let is_uninstall_entry = UNINSTALL_KEYS.iter().any(|prefix| {
+            key.path.len() > prefix.len()
+                && key.path[..prefix.len()].eq_ignore_ascii_case(prefix)
+                && !key.path[prefix.len()..].contains('\\')
+        });
+        if !is_uninstall_entry {
+            continue;
+        }
+        // Записи без DisplayName Windows тоже не показывает (обновления, компоненты).
+        let Some(name) = key.string("DisplayName") else {
+            continue;
+        };
+
+        out.push(InstalledProgram {
+            name: name.to_string(),
+            version: key.string("DisplayVersion").map(str::to_string),
+            install_location: key
+                .string("InstallLocation")
+                .filter(|location| !location.is_empty())
+                .map(str::to_string),
+            uninstall: key.string("UninstallString").map(str::to_string),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collect_programs;
+
+    #[test]
+    fn collects_only_named_uninstall_entries() {
+        let content = r#"
+[Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{GUID}] 1
+"DisplayName"="Mod Organizer"
+"InstallLocation"="C:\\Modding\\MO2"
+"UninstallString"="C:\\Modding\\MO2\\uninstall.exe"
+
+[Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\KB123] 1
+"ParentKeyName"="OperatingSystem"
+
+[Software\\Wine\\Fonts] 1
+"DisplayName"="not a program"
+"#;
+
+        let mut programs = Vec::new();
+        collect_programs(content, &mut programs);
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].name, "Mod Organizer");
+        assert_eq!(
+            programs[0].install_location.as_deref(),
+            Some(r"C:\Modding\MO2")
+        );
+    }
+}
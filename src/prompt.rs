@@ -0,0 +1,123 @@
+use serde_json::json;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    context::{ContextStatus, collect_running_apps},
+    output::print_json,
+    runtime::{cache_dir, unix_now_secs},
+};
+
+/// Контекст, окружение которого унаследовала оболочка (`protonhax exec <appid> $SHELL`).
+pub const CONTEXT_ENV: &str = "PROTONHAX_CONTEXT";
+const PROMPT_CACHE_FILE: &str = "prompt";
+/// Сколько секунд счётчик из кэша считается свежим: завершение игры с `--keep`
+/// не меняет mtime каталога контекстов.
+const PROMPT_CACHE_TTL_SECS: u64 = 2;
+
+/// Счётчик запущенных игр для каталога контекстов с его mtime на момент подсчёта.
+#[derive(Debug, PartialEq, Eq)]
+struct PromptCache {
+    phd: PathBuf,
+    mtime_nanos: u128,
+    checked_at: u64,
+    running: usize,
+}
+
+impl PromptCache {
+    fn parse(content: &str) -> Option<Self> {
+        let mut fields = content.trim_end().split('\t');
+        let cache = Self {
+            phd: PathBuf::from(fields.next()?),
+            mtime_nanos: fields.next()?.parse().ok()?,
+            checked_at: fields.next()?.parse().ok()?,
+            running: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(cache)
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\n",
+            self.phd.display(),
+            self.mtime_nanos,
+            self.checked_at,
+            self.running
+        )
+    }
+}
+
+/// Короткая строка для PS1/starship: контекст оболочки или число запущенных игр;
+/// без игр ничего не печатает.
+pub fn handle_prompt(phd: &Path, json_output: bool) -> io::Result<()> {
+    let context = env::var(CONTEXT_ENV).ok().filter(|value| !value.is_empty());
+    let running = running_count(phd);
+    if json_output {
+        return print_json(&json!({ "context": context, "running": running }));
+    }
+    if let Some(context) = context {
+        println!("🎮 {context}");
+    } else if running > 0 {
+        println!("🎮 {running} running");
+    }
+    Ok(())
+}
+
+/// Подсчёт из кэша, пока каталог контекстов не менялся; кэш — лишь оптимизация,
+/// ошибки его чтения и записи игнорируются.
+fn running_count(phd: &Path) -> usize {
+    let Some(mtime_nanos) = dir_mtime_nanos(phd) else {
+        return 0;
+    };
+    let now = unix_now_secs();
+    let path = cache_dir().join(PROMPT_CACHE_FILE);
+    if let Some(cache) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| PromptCache::parse(&content))
+        && cache.phd == phd
+        && cache.mtime_nanos == mtime_nanos
+        && now.saturating_sub(cache.checked_at) < PROMPT_CACHE_TTL_SECS
+    {
+        return cache.running;
+    }
+
+    let running = collect_running_apps(phd, false)
+        .unwrap_or_default()
+        .iter()
+        .filter(|app| app.status() == ContextStatus::Running)
+        .count();
+    let cache = PromptCache {
+        phd: phd.to_path_buf(),
+        mtime_nanos,
+        checked_at: now,
+        running,
+    };
+    let _ = fs::create_dir_all(cache_dir()).and_then(|()| fs::write(path, cache.serialize()));
+    running
+}
+
+fn dir_mtime_nanos(dir: &Path) -> Option<u128> {
+    let modified = fs::metadata(dir).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PromptCache;
+    use std::path::PathBuf;
+
+    #[test]
+    fn cache_round_trips() {
+        let cache = PromptCache {
+            phd: PathBuf::from("/run/user/1000/protonhax"),
+            mtime_nanos: 1_718_900_000_123_456_789,
+            checked_at: 1_718_900_000,
+            running: 2,
+        };
+        assert_eq!(PromptCache::parse(&cache.serialize()), Some(cache));
+        assert_eq!(PromptCache::parse("/run\t1\t2"), None);
+    }
+}
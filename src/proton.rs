@@ -0,0 +1,248 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::ProtonsCommands,
+    ge_proton::{install_ge_proton, remove_custom_proton},
+    output::print_json,
+    steam::{steam_roots, steamapps_dirs},
+};
+
+/// Каталог пользовательских сборок (GE-Proton и т.п.) в корне Steam.
+pub const CUSTOM_TOOLS_DIR: &str = "compatibilitytools.d";
+
+/// Команда скрипта proton, которой запускается программа.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProtonVerb {
+    /// Полная подготовка префикса, как при запуске игры.
+    #[default]
+    Run,
+    /// Дождаться выхода остальных программ префикса; нужно части инжекторов.
+    Waitforexitandrun,
+    /// Сразу в готовый префикс, без подготовки: быстро для мелких утилит.
+    Runinprefix,
+}
+
+impl ProtonVerb {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Run => "run",
+            Self::Waitforexitandrun => "waitforexitandrun",
+            Self::Runinprefix => "runinprefix",
+        }
+    }
+}
+
+/// Каталог сборки Proton (содержит скрипт `proton`).
+pub struct ProtonBuild {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl ProtonBuild {
+    pub fn script(&self) -> PathBuf {
+        self.dir.join("proton")
+    }
+
+    /// Версия из файла `version` сборки (`<timestamp> <version>`).
+    pub fn version(&self) -> Option<String> {
+        let content = fs::read_to_string(self.dir.join("version")).ok()?;
+        parse_version_file(&content)
+    }
+
+    /// Сборка не от Valve: лежит в `compatibilitytools.d`.
+    pub fn is_custom(&self) -> bool {
+        self.dir
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == CUSTOM_TOOLS_DIR)
+    }
+}
+
+pub fn handle_protons(
+    phd: &Path,
+    command: Option<ProtonsCommands>,
+    json_output: bool,
+) -> io::Result<()> {
+    match command {
+        None => list_protons(json_output),
+        Some(ProtonsCommands::Install { version }) => install_ge_proton(&version),
+        Some(ProtonsCommands::Remove { name }) => remove_custom_proton(phd, &name),
+    }
+}
+
+/// Установленные сборки; `*` — сборка по умолчанию для `--proton`.
+fn list_protons(json_output: bool) -> io::Result<()> {
+    let builds = installed_protons();
+    let default_dir = find_proton(None).map(|build| build.dir);
+
+    if json_output {
+        let data: Vec<_> = builds
+            .iter()
+            .map(|build| {
+                json!({
+                    "name": build.name,
+                    "path": build.dir,
+                    "version": build.version(),
+                    "custom": build.is_custom(),
+                    "default": default_dir.as_ref() == Some(&build.dir),
+                })
+            })
+            .collect();
+        return print_json(&data);
+    }
+
+    if builds.is_empty() {
+        println!("Сборки Proton не найдены");
+        return Ok(());
+    }
+    for build in &builds {
+        let marker = if default_dir.as_ref() == Some(&build.dir) {
+            "*"
+        } else {
+            " "
+        };
+        let mut parts = vec![format!("{marker} {}", build.name.green())];
+        if let Some(version) = build.version() {
+            parts.push(version.yellow().to_string());
+        }
+        if build.is_custom() {
+            parts.push("custom".cyan().to_string());
+        }
+        parts.push(build.dir.display().to_string().dimmed().to_string());
+        println!("{}", parts.join("  "));
+    }
+    Ok(())
+}
+
+/// Сборки Valve из `steamapps/common` всех библиотек и пользовательские из
+/// `compatibilitytools.d`, отсортированные по имени.
+pub fn installed_protons() -> Vec<ProtonBuild> {
+    let common = steamapps_dirs()
+        .into_iter()
+        .map(|steamapps| steamapps.join("common"));
+    let custom = steam_roots()
+        .into_iter()
+        .map(|root| root.join(CUSTOM_TOOLS_DIR));
+
+    let mut builds: Vec<ProtonBuild> = common
+        .chain(custom)
+        .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join("proton").is_file())
+        .map(|dir| ProtonBuild {
+            name: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            dir,
+        })
+        .collect();
+    builds.sort_by(|left, right| left.name.cmp(&right.name));
+    builds.dedup_by(|left, right| left.dir == right.dir);
+    builds
+}
+
+/// Сборка по пути (каталог или сам скрипт `proton`) или по имени: точное совпадение без
+/// учёта регистра, затем подстрока. Без запроса — Proton Experimental или последняя
+/// по имени сборка Valve.
+pub fn find_proton(query: Option<&str>) -> Option<ProtonBuild> {
+    if let Some(query) = query {
+        let path = Path::new(query);
+        let dir = if path.is_file() {
+            path.parent()
+        } else {
+            Some(path)
+        };
+        if let Some(dir) = dir.filter(|dir| dir.join("proton").is_file()) {
+            return Some(ProtonBuild {
+                name: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                dir: dir.to_path_buf(),
+            });
+        }
+    }
+
+    let builds = installed_protons();
+    let pick = |predicate: &dyn Fn(&str) -> bool| {
+        builds
+            .iter()
+            .rposition(|build| predicate(&build.name.to_lowercase()))
+    };
+    let idx = match query.map(str::to_lowercase) {
+        Some(query) => pick(&|name| name == query).or_else(|| pick(&|name| name.contains(&query))),
+        None => pick(&|name| name.contains("experimental"))
+            .or_else(|| pick(&|name| name.starts_with("proton "))),
+    }?;
+    builds.into_iter().nth(idx)
+}
+
+/// Сборка Proton контекста: версия из `version` дистрибутива рядом со скриптом, а если
+/// его нет — из `compatdata/version`, то есть сборки, последней обновлявшей префикс.
+pub fn context_proton_version(proton_script: &Path, compat_data: &Path) -> Option<String> {
+    let read = |dir: &Path| {
+        fs::read_to_string(dir.join("version"))
+            .ok()
+            .and_then(|content| parse_version_file(&content))
+    };
+    proton_script
+        .parent()
+        .and_then(read)
+        .or_else(|| read(compat_data))
+        .map(|version| display_version(&version))
+}
+
+/// `proton-9.0-4` → `Proton 9.0-4`, `experimental-9.0-20240612` → `Proton Experimental
+/// 9.0-20240612`; сторонние сборки (`GE-Proton9-20`) уже названы понятно.
+fn display_version(version: &str) -> String {
+    if let Some(rest) = version.strip_prefix("proton-") {
+        format!("Proton {rest}")
+    } else if let Some(rest) = version.strip_prefix("experimental-") {
+        format!("Proton Experimental {rest}")
+    } else {
+        version.to_string()
+    }
+}
+
+pub fn parse_version_file(content: &str) -> Option<String> {
+    let line = content.lines().next()?.trim();
+    let version = line
+        .split_once(' ')
+        .map_or(line, |(_, version)| version.trim());
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display_version, parse_version_file};
+
+    #[test]
+    fn reads_version_file() {
+        assert_eq!(
+            parse_version_file("1718900000 proton-9.0-2\n").as_deref(),
+            Some("proton-9.0-2")
+        );
+        assert_eq!(
+            parse_version_file("GE-Proton9-20").as_deref(),
+            Some("GE-Proton9-20")
+        );
+        assert_eq!(parse_version_file(""), None);
+    }
+
+    #[test]
+    fn formats_versions_for_display() {
+        assert_eq!(display_version("proton-9.0-4"), "Proton 9.0-4");
+        assert_eq!(
+            display_version("experimental-9.0-20240612"),
+            "Proton Experimental 9.0-20240612"
+        );
+        assert_eq!(display_version("GE-Proton9-20"), "GE-Proton9-20");
+    }
+}
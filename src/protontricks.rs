@@ -0,0 +1,59 @@
+use std::{io, path::Path, process};
+
+use crate::{
+    cli::{fail, print_subcommand_usage_error},
+    runtime::exit_with_status,
+    verbosity,
+    wine::resolve_prefix,
+};
+
+/// Аналог protontricks: winetricks или произвольная команда в префиксе игры.
+pub fn handle_pt(
+    phd: &Path,
+    selector: &str,
+    gui: bool,
+    command: Option<&str>,
+    args: &[String],
+) -> io::Result<()> {
+    if !gui && command.is_none() && args.is_empty() {
+        print_subcommand_usage_error("pt", "Укажите --gui, --command или verbs для winetricks");
+    }
+
+    let prefix = resolve_prefix(phd, selector)?;
+    if prefix.wine_bin_dir().is_none() {
+        fail(&format!(
+            "В сборке Proton {} не найден wine",
+            prefix.proton_dir().display()
+        ));
+    }
+    if !prefix.is_live() {
+        verbosity::info(format_args!(
+            "Игра {} не запущена: используется префикс {}",
+            prefix.appid,
+            prefix.pfx.display()
+        ));
+    }
+
+    let mut child = if let Some(command) = command {
+        let mut child = process::Command::new("sh");
+        child.arg("-c").arg(command);
+        child
+    } else {
+        let mut child = process::Command::new("winetricks");
+        if gui {
+            child.arg("--gui");
+        }
+        child.args(args);
+        child
+    };
+    prefix.apply_wine_env(&mut child);
+
+    let status = child.status().map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound && command.is_none() {
+            io::Error::new(err.kind(), "winetricks не найден в PATH")
+        } else {
+            err
+        }
+    })?;
+    exit_with_status(status);
+}
@@ -0,0 +1,185 @@
+/// Значение реестра; нестроковые типы, кроме DWORD, не разбираются.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegValue {
+    String(String),
+    Dword(u32),
+    Other,
+}
+
+/// Ключ реестра с путём относительно корня файла (одиночные `\` между компонентами).
+#[derive(Debug, Default)]
+pub struct RegKey {
+    pub path: String,
+    pub values: Vec<(String, RegValue)>,
+}
+
+impl RegKey {
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.values.iter().find_map(|(key, value)| match value {
+            RegValue::String(value) if key.eq_ignore_ascii_case(name) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+}
+
+pub fn parse_reg(content: &str) -> Vec<RegKey> {
+    let mut keys = Vec::new();
+    let mut current: Option<RegKey> = None;
+    let mut continuation = false;
+
+    for line in content.lines() {
+        // Длинные hex-значения переносятся строками с `\` в конце.
+        if continuation {
+            continuation = line.trim_end().ends_with('\\');
+            continue;
+        }
+
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix('[') {
+            if let Some(key) = current.take() {
+                keys.push(key);
+            }
+            let Some(end) = rest.rfind(']') else {
+                continue;
+            };
+            current = Some(RegKey {
+                path: unescape_key_path(&rest[..end]),
+                values: Vec::new(),
+            });
+            continue;
+        }
+
+        let Some(key) = current.as_mut() else {
+            continue;
+        };
+        let Some((name, value)) = parse_value_line(line) else {
+            continue;
+        };
+        continuation = matches!(value, RegValue::Other) && line.trim_end().ends_with('\\');
+        key.values.push((name, value));
+    }
+
+    if let Some(key) = current {
+        keys.push(key);
+    }
+    keys
+}
+
+/// Декодирует `.reg`-файл: regedit экспортирует UTF-16LE с BOM, Wine — UTF-8.
+pub fn decode_reg_file(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn parse_value_line(line: &str) -> Option<(String, RegValue)> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix("@=") {
+        (String::new(), rest)
+    } else {
+        let (name, rest) = parse_quoted(line.strip_prefix('"')?)?;
+        (name, rest.strip_prefix('=')?)
+    };
+
+    let value = if let Some(rest) = rest.strip_prefix('"') {
+        RegValue::String(parse_quoted(rest)?.0)
+    } else if let Some(rest) = rest.strip_prefix("str(2):\"") {
+        RegValue::String(parse_quoted(rest)?.0)
+    } else if let Some(hex) = rest.strip_prefix("dword:") {
+        RegValue::Dword(u32::from_str_radix(hex.trim(), 16).ok()?)
+    } else {
+        RegValue::Other
+    };
+    Some((name, value))
+}
+
+/// Читает строку до закрывающей кавычки; возвращает её и остаток после кавычки.
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let mut res = String::new();
+    let mut chars = s.char_indices();
+
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Some((res, &s[idx + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => res.push('\n'),
+                'r' => res.push('\r'),
+                't' => res.push('\t'),
+                '0' => res.push('\0'),
+                'x' => {
+                    let start = idx + 2;
+                    let hex: String = s[start..]
+                        .chars()
+                        .take_while(char::is_ascii_hexdigit)
+                        .take(4)
+                        .collect();
+                    for _ in 0..hex.len() {
+                        chars.next();
+                    }
+                    let code = u32::from_str_radix(&hex, 16).ok()?;
+                    res.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                other => res.push(other),
+            },
+            c => res.push(c),
+        }
+    }
+    None
+}
+
+fn unescape_key_path(path: &str) -> String {
+    path.replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegValue, decode_reg_file, parse_reg};
+
+    #[test]
+    fn parses_keys_and_values() {
+        let content = r#"WINE REGISTRY Version 2
+;; All keys relative to \\Machine
+
+[Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\Foo] 1700000000
+#time=1d9f
+"DisplayName"="Foo \"Tool\""
+"InstallLocation"="C:\\Program Files\\Foo\\"
+"Blob"=hex:01,02,\
+  03,04
+"EstimatedSize"=dword:0000000a
+@="default \x44f"
+"#;
+
+        let keys = parse_reg(content);
+        assert_eq!(keys.len(), 1);
+        let key = &keys[0];
+        assert_eq!(
+            key.path,
+            r"Software\Microsoft\Windows\CurrentVersion\Uninstall\Foo"
+        );
+        assert_eq!(key.string("displayname"), Some("Foo \"Tool\""));
+        assert_eq!(
+            key.string("InstallLocation"),
+            Some(r"C:\Program Files\Foo\")
+        );
+        assert_eq!(key.string(""), Some("default я"));
+        assert!(
+            key.values
+                .iter()
+                .any(|(name, value)| name == "EstimatedSize" && *value == RegValue::Dword(10))
+        );
+    }
+
+    #[test]
+    fn decodes_utf16_reg_files() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("REGEDIT4".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode_reg_file(&bytes), "REGEDIT4");
+        assert_eq!(decode_reg_file(b"\xEF\xBB\xBFREGEDIT4"), "REGEDIT4");
+    }
+}
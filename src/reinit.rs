@@ -0,0 +1,166 @@
+use colored::Colorize;
+use std::{fs, io, path::Path};
+
+use crate::{
+    cli::fail,
+    config::Config,
+    context::{
+        EXE_FILE, PFX_FILE, PID_FILE, STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV, read_trimmed,
+        resolve_target_app,
+    },
+    env_store::write_env_vars,
+    lock::ContextLock,
+    proton::context_proton_version,
+    signals::process_tree,
+    steam::{LaunchChain, cache_init_meta, read_cached_meta},
+};
+
+/// Процесс игры, из которого перечитывается контекст.
+struct GameProcess {
+    pid: libc::pid_t,
+    environ: Vec<(String, String)>,
+    proton: String,
+}
+
+/// Заново определяет путь к proton и окружение по живому процессу игры и перезаписывает
+/// ими контекст, не перезапуская игру.
+pub fn handle_reinit(phd: &Path, selector: &str) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    if !target.app_dir.starts_with(phd) {
+        fail(&format!(
+            "{} — не контекст запущенной игры; reinit обновляет только контексты `init`",
+            target.appid
+        ));
+    }
+
+    // Свежие контексты знают pid `init`; у созданных старыми версиями его нет — ищем
+    // процесс по `SteamAppId` в окружении.
+    let recorded_pid = read_trimmed(target.app_dir.join(PID_FILE))
+        .ok()
+        .and_then(|pid| pid.parse::<libc::pid_t>().ok());
+    let game = match recorded_pid {
+        Some(pid) => find_in_tree(pid)?,
+        None => None,
+    }
+    .or_else(|| find_by_appid(&target.appid));
+    let Some(game) = game else {
+        fail(&format!(
+            "Не найден запущенный процесс игры {} с proton в командной строке",
+            target.appid
+        ));
+    };
+
+    let Some(compat_data) = game
+        .environ
+        .iter()
+        .find(|(name, _)| name == STEAM_COMPAT_DATA_PATH_ENV)
+        .map(|(_, value)| value.clone())
+    else {
+        fail(&format!(
+            "В окружении процесса {} нет {STEAM_COMPAT_DATA_PATH_ENV}",
+            game.pid
+        ));
+    };
+
+    let filter = Config::load()?.env;
+    let _lock = ContextLock::exclusive(&target.app_dir)?;
+    fs::write(target.app_dir.join(EXE_FILE), &game.proton)?;
+    fs::write(target.app_dir.join(PFX_FILE), format!("{compat_data}/pfx"))?;
+    write_env_vars(&target.app_dir, game.environ, &filter)?;
+    if recorded_pid.is_none() {
+        fs::write(target.app_dir.join(PID_FILE), game.pid.to_string())?;
+    }
+    // Цепочка обёрток процессу не видна: остаётся записанной при `init`.
+    let previous = read_cached_meta(&target.app_dir).unwrap_or_default();
+    cache_init_meta(
+        &target.app_dir,
+        &target.appid,
+        context_proton_version(Path::new(&game.proton), Path::new(&compat_data)),
+        LaunchChain {
+            wrappers: previous.wrappers,
+            container: previous.container,
+        },
+    );
+
+    println!(
+        "{} Контекст {} обновлён из процесса {}: {}",
+        "✓".green().bold(),
+        target.appid,
+        game.pid,
+        game.proton
+    );
+    Ok(())
+}
+
+/// Первый процесс дерева `init` с путём к proton в командной строке (сам `init` или
+/// скрипт proton); окружение берётся у него же.
+fn find_in_tree(root: libc::pid_t) -> io::Result<Option<GameProcess>> {
+    Ok(process_tree(root)?.into_iter().find_map(read_game_process))
+}
+
+fn find_by_appid(appid: &str) -> Option<GameProcess> {
+    let mut pids: Vec<libc::pid_t> = fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    // Самый ранний процесс ближе всего к исходному запуску Steam.
+    pids.sort_unstable();
+    pids.into_iter().filter_map(read_game_process).find(|game| {
+        game.environ
+            .iter()
+            .any(|(name, value)| name == STEAM_APP_ID_ENV && value == appid)
+    })
+}
+
+fn read_game_process(pid: libc::pid_t) -> Option<GameProcess> {
+    let proc_dir = Path::new("/proc").join(pid.to_string());
+    let cmdline = fs::read(proc_dir.join("cmdline")).ok()?;
+    // argv[0] пропускаем: `.../protonhax init` тоже содержит `/proton`.
+    let proton = split_nul(&cmdline)
+        .skip(1)
+        .find(|arg| {
+            Path::new(arg)
+                .file_name()
+                .is_some_and(|name| name == "proton")
+        })?
+        .to_string();
+    let environ = fs::read(proc_dir.join("environ")).ok()?;
+    Some(GameProcess {
+        pid,
+        environ: parse_environ(&environ),
+        proton,
+    })
+}
+
+fn split_nul(data: &[u8]) -> impl Iterator<Item = &str> {
+    data.split(|byte| *byte == 0)
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| std::str::from_utf8(part).ok())
+}
+
+/// Содержимое `/proc/<pid>/environ`: `NAME=value`, разделённые NUL.
+fn parse_environ(data: &[u8]) -> Vec<(String, String)> {
+    split_nul(data)
+        .filter_map(|var| var.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_environ;
+
+    #[test]
+    fn parses_proc_environ() {
+        let environ = parse_environ(b"SteamAppId=620\0WINEDEBUG=-all\0EMPTY=\0broken\0");
+        assert_eq!(
+            environ,
+            [
+                ("SteamAppId".to_string(), "620".to_string()),
+                ("WINEDEBUG".to_string(), "-all".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+    }
+}
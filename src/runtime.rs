@@ -1,17 +1,115 @@
+use colored::Colorize;
 use std::{
-    env, fs,
-    path::PathBuf,
+    env,
+    fs::{self, Permissions},
+    io::{self, IsTerminal},
+    num::NonZero,
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::{
+            fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+            process::{CommandExt, ExitStatusExt},
+        },
+    },
+    path::{Path, PathBuf},
     process,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    sync::atomic::{AtomicI32, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 const SECS_PER_MINUTE: u64 = 60;
 const SECS_PER_HOUR: u64 = 60 * SECS_PER_MINUTE;
-const SECS_PER_DAY: u64 = 24 * SECS_PER_HOUR;
+pub const SECS_PER_DAY: u64 = 24 * SECS_PER_HOUR;
 
-/// Функция для получения пути к директории protonhax.
-pub fn runtime_root() -> PathBuf {
-    runtime_dir().join("protonhax")
+/// Код выхода при срабатывании `--timeout`.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+const TIMEOUT_KILL_GRACE: Duration = Duration::from_secs(5);
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Сигналы, которые пересылаются группе команды с `--timeout`: сама она их от терминала
+/// или `kill` не получит.
+const FORWARDED_SIGNALS: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+/// Последний пришедший из `FORWARDED_SIGNALS`; 0 — не было.
+static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+const RUNTIME_ROOT_ENV: &str = "PROTONHAX_ROOT";
+/// Потоков для чтения метаданных: на HDD больше параллельных чтений уже не ускоряют.
+const MAX_PARALLEL_READS: usize = 8;
+
+/// Функция для получения пути к директории protonhax: `--runtime-dir`,
+/// затем `PROTONHAX_ROOT`, затем `$XDG_RUNTIME_DIR/protonhax`.
+pub fn runtime_root(override_dir: Option<PathBuf>) -> PathBuf {
+    override_dir
+        .or_else(|| {
+            env::var_os(RUNTIME_ROOT_ENV)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        })
+        .unwrap_or_else(|| runtime_dir().join("protonhax"))
+}
+
+/// Функция для получения пути к директории конфигурации protonhax.
+pub fn config_dir() -> PathBuf {
+    config_dir_base().join("protonhax")
+}
+
+/// Базовая директория конфигурации (`$XDG_CONFIG_HOME`).
+pub fn config_dir_base() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Директория кэша protonhax (`$XDG_CACHE_HOME/protonhax`).
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache").join("protonhax")
+}
+
+/// Логи и трассировки игры (`$XDG_STATE_HOME/protonhax/logs/<appid>`); переживают
+/// удаление runtime-контекста после выхода из игры.
+pub fn log_dir(appid: &str) -> PathBuf {
+    log_root().join(appid)
+}
+
+/// Каталог логов всех игр (`$XDG_STATE_HOME/protonhax/logs`).
+pub fn log_root() -> PathBuf {
+    state_dir().join("logs")
+}
+
+/// Состояние protonhax между запусками (`$XDG_STATE_HOME/protonhax`).
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state").join("protonhax")
+}
+
+/// Лог Proton при `PROTON_LOG=1`: `steam-<appid>.log` в `$PROTON_LOG_DIR` или домашнем каталоге.
+pub fn proton_log_path(appid: &str) -> PathBuf {
+    env::var_os("PROTON_LOG_DIR")
+        .map_or_else(home_dir, PathBuf::from)
+        .join(format!("steam-{appid}.log"))
+}
+
+/// Базовая директория пользовательских данных (`$XDG_DATA_HOME`).
+pub fn data_home() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(var: &str, home_fallback: &str) -> PathBuf {
+    env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map_or_else(|| home_dir().join(home_fallback), PathBuf::from)
+}
+
+/// Ищет исполняемый файл в `$PATH`, как это сделала бы оболочка.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| {
+            fs::metadata(candidate)
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        })
+}
+
+pub fn home_dir() -> PathBuf {
+    env::var_os("HOME").map_or_else(|| PathBuf::from("/"), PathBuf::from)
 }
 
 fn runtime_dir() -> PathBuf {
@@ -22,47 +120,206 @@ fn runtime_dir() -> PathBuf {
     )
 }
 
-fn current_uid() -> String {
-    if let Ok(uid) = env::var("UID")
-        && is_uid(&uid)
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid не имеет предусловий и не может завершиться ошибкой.
+    unsafe { libc::getuid() }
+}
+
+/// Создаёт корень контекстов с правами 0700 и проверяет, что он принадлежит
+/// текущему пользователю: в нём хранится окружение игр, включая токены.
+pub fn ensure_runtime_root(phd: &Path) -> io::Result<()> {
+    match fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(phd)
     {
-        return uid;
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::ReadOnlyFilesystem => {
+            return Err(io::Error::new(
+                err.kind(),
+                format!(
+                    "{} на файловой системе только для чтения; задайте XDG_RUNTIME_DIR на записываемый каталог",
+                    phd.display()
+                ),
+            ));
+        }
+        Err(err) => return Err(err),
     }
 
-    if let Some(uid) = uid_from_proc_status() {
-        return uid;
+    let meta = fs::symlink_metadata(phd)?;
+    if !meta.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} не является каталогом", phd.display()),
+        ));
+    }
+    if meta.uid() != current_uid() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} принадлежит другому пользователю (uid {})",
+                phd.display(),
+                meta.uid()
+            ),
+        ));
+    }
+    if meta.mode() & 0o077 != 0 {
+        fs::set_permissions(phd, Permissions::from_mode(0o700))?;
     }
+    Ok(())
+}
+
+pub fn debug_enabled() -> bool {
+    env::var_os("PROTONHAX_DEBUG").is_some()
+}
 
-    uid_from_id_command().unwrap_or_else(|| String::from("0"))
+/// `PROTONHAX_KEEP=1` — то же, что `init --keep`.
+pub fn keep_enabled() -> bool {
+    env::var_os("PROTONHAX_KEEP").is_some_and(|value| !value.is_empty() && value != "0")
 }
 
-fn uid_from_proc_status() -> Option<String> {
-    let content = fs::read_to_string("/proc/self/status").ok()?;
-    let uid_line = content.lines().find(|line| line.starts_with("Uid:"))?;
-    let uid = uid_line.split_whitespace().nth(1)?;
-    if is_uid(uid) {
-        Some(uid.to_string())
-    } else {
-        None
+pub fn exit_with_status(status: process::ExitStatus) -> ! {
+    process::exit(status.code().unwrap_or(1));
+}
+
+/// Запускает команду; с `timeout` — в отдельной группе процессов, которую по истечении
+/// времени завершаем целиком (SIGTERM, затем SIGKILL) и возвращаем код 124, как timeout(1).
+/// Как у `timeout --foreground`, группа на это время становится активной в терминале:
+/// Ctrl+C достаётся ей, а чтение stdin не останавливает её по SIGTTIN. SIGINT, SIGTERM и
+/// SIGHUP, пришедшие самой protonhax, пересылаются группе.
+pub fn run_with_timeout(
+    cmd: &mut process::Command,
+    timeout: Option<Duration>,
+) -> io::Result<process::ExitStatus> {
+    let Some(timeout) = timeout else {
+        return cmd.status();
+    };
+
+    cmd.process_group(0);
+    let terminal = io::stdin().is_terminal().then(|| io::stdin().as_raw_fd());
+    if let Some(tty) = terminal {
+        // SAFETY: в замыкании только async-signal-safe вызовы setpgid/signal/tcsetpgrp,
+        // без аллокаций, что допустимо между fork и exec.
+        unsafe {
+            cmd.pre_exec(move || {
+                // Родитель тоже передаёт терминал после spawn, но команда могла бы успеть
+                // прочитать stdin раньше и остановиться.
+                libc::setpgid(0, 0);
+                give_terminal(tty, libc::getpgrp());
+                Ok(())
+            });
+        }
+    }
+    let _signals = SignalForwarding::install();
+    let mut child = cmd.spawn()?;
+    let pgid = libc::pid_t::try_from(child.id()).map_err(io::Error::other)?;
+    let _foreground = terminal.map(|tty| Foreground::give(tty, pgid));
+
+    if let Some(status) = wait_until(&mut child, pgid, Instant::now() + timeout)? {
+        return Ok(status);
     }
+    signal_process_group(pgid, libc::SIGTERM);
+    wait_until(&mut child, pgid, Instant::now() + TIMEOUT_KILL_GRACE)?;
+    // Лидер группы мог выйти, оставив дочерние процессы Wine, — добиваем всю группу.
+    signal_process_group(pgid, libc::SIGKILL);
+    let _ = child.wait();
+
+    eprintln!(
+        "{} Команда не завершилась за {} с и была остановлена",
+        "Ошибка:".bold().red(),
+        timeout.as_secs()
+    );
+    Ok(process::ExitStatus::from_raw(TIMEOUT_EXIT_CODE << 8))
 }
 
-fn uid_from_id_command() -> Option<String> {
-    let output = process::Command::new("id").arg("-u").output().ok()?;
-    if !output.status.success() {
-        return None;
+/// Ждёт команду до `deadline`, пересылая её группе пришедшие сигналы; `None` — не успела.
+fn wait_until(
+    child: &mut process::Child,
+    pgid: libc::pid_t,
+    deadline: Instant,
+) -> io::Result<Option<process::ExitStatus>> {
+    loop {
+        let signal = PENDING_SIGNAL.swap(0, Ordering::Relaxed);
+        if signal != 0 {
+            signal_process_group(pgid, signal);
+        }
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(TIMEOUT_POLL_INTERVAL);
     }
+}
 
-    let uid = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    is_uid(&uid).then_some(uid)
+extern "C" fn remember_signal(signal: libc::c_int) {
+    PENDING_SIGNAL.store(signal, Ordering::Relaxed);
 }
 
-fn is_uid(value: &str) -> bool {
-    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit())
+/// Обработчики `FORWARDED_SIGNALS` на время ожидания команды; прежние возвращаются при
+/// выходе из области видимости.
+struct SignalForwarding {
+    previous: [libc::sighandler_t; FORWARDED_SIGNALS.len()],
 }
 
-pub fn debug_enabled() -> bool {
-    env::var_os("PROTONHAX_DEBUG").is_some()
+impl SignalForwarding {
+    fn install() -> Self {
+        let handler = remember_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        // SAFETY: обработчик только записывает атомик, что допустимо в обработчике сигнала.
+        let previous = FORWARDED_SIGNALS.map(|signal| unsafe { libc::signal(signal, handler) });
+        Self { previous }
+    }
+}
+
+impl Drop for SignalForwarding {
+    fn drop(&mut self) {
+        for (signal, previous) in FORWARDED_SIGNALS.into_iter().zip(self.previous) {
+            // SAFETY: возвращаем обработчик, который стоял до `install`.
+            unsafe {
+                libc::signal(signal, previous);
+            }
+        }
+    }
+}
+
+/// Терминал отдан группе команды; при выходе из области видимости возвращается группе
+/// protonhax.
+struct Foreground {
+    tty: RawFd,
+}
+
+impl Foreground {
+    fn give(tty: RawFd, pgid: libc::pid_t) -> Self {
+        give_terminal(tty, pgid);
+        Self { tty }
+    }
+}
+
+impl Drop for Foreground {
+    fn drop(&mut self) {
+        // SAFETY: getpgrp не принимает указателей и всегда успешен.
+        give_terminal(self.tty, unsafe { libc::getpgrp() });
+    }
+}
+
+/// Делает группу `pgid` активной в терминале. Из фоновой группы tcsetpgrp прислал бы
+/// SIGTTOU и остановил процесс, поэтому на время вызова сигнал игнорируется.
+fn give_terminal(tty: RawFd, pgid: libc::pid_t) {
+    // SAFETY: signal и tcsetpgrp принимают только числа; прежний обработчик SIGTTOU
+    // возвращается сразу после вызова.
+    unsafe {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(tty, pgid);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+fn signal_process_group(pgid: libc::pid_t, signal: libc::c_int) {
+    // SAFETY: kill лишь отправляет сигнал; отрицательный pid адресует группу процессов.
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
 }
 
 pub fn unix_now_secs() -> u64 {
@@ -72,6 +329,26 @@ pub fn unix_now_secs() -> u64 {
         .as_secs()
 }
 
+/// Размер в человекочитаемом виде с двоичными единицами (`1.5 GiB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut unit = 0;
+    let mut whole = bytes;
+    while whole >= 1024 && unit < UNITS.len() - 1 {
+        whole /= 1024;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        return format!("{bytes} B");
+    }
+    // Одна цифра после точки без перехода к f64: десятые доли от остатка.
+    let divisor = 1u64 << (10 * unit);
+    let tenths = (bytes % divisor) * 10 / divisor;
+    format!("{whole}.{tenths} {}", UNITS[unit])
+}
+
 pub fn format_duration_ago(start_unix_secs: u64) -> String {
     let mut secs = unix_now_secs().saturating_sub(start_unix_secs);
 
@@ -104,3 +381,65 @@ pub fn format_duration_ago(start_unix_secs: u64) -> String {
         format!("{s}s ago")
     }
 }
+
+/// `items.iter().map(f)` на нескольких потоках (не больше [`MAX_PARALLEL_READS`]) с
+/// сохранением порядка: разбор манифестов и env десятков игр упирается в ожидание диска.
+pub fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZero::get)
+        .min(MAX_PARALLEL_READS)
+        .min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let (next, f) = (&next, &f);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            return done;
+                        };
+                        done.push((index, f(item)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_size, parallel_map};
+
+    #[test]
+    fn formats_binary_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn parallel_map_keeps_order() {
+        let items: Vec<u64> = (0..100).collect();
+        assert_eq!(
+            parallel_map(&items, |item| item * 2),
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+        assert!(parallel_map(&[] as &[u64], |item| *item).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{cli::fail, context::TargetApp, runtime::find_in_path, verbosity};
+
+const BWRAP: &str = "bwrap";
+/// Система хоста: только для чтения, без `/home`, `/root`, `/mnt` и прочих данных.
+const SYSTEM_DIRS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/nix", "/gnu",
+];
+/// Сокет X11: окна инструментов должны открываться.
+const X11_SOCKETS: &str = "/tmp/.X11-unix";
+
+/// Команда `exec --sandbox`: нативная программа внутри bubblewrap, где из данных видны
+/// лишь compatdata и каталог установки игры (на запись), сборка Proton и сама программа
+/// (на чтение). Домашний каталог пуст, сеть остаётся — модменеджерам нужны загрузки.
+pub fn sandboxed_command(target: &TargetApp, cmd: &[String]) -> io::Result<process::Command> {
+    let Some(bwrap) = find_in_path(BWRAP) else {
+        fail("bwrap не найден в PATH: установите bubblewrap для --sandbox");
+    };
+    let mut child = process::Command::new(bwrap);
+    child.args(["--unshare-all", "--share-net", "--die-with-parent"]);
+    child.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"]);
+    for dir in SYSTEM_DIRS {
+        bind(&mut child, "--ro-bind", Path::new(dir));
+    }
+    // Пустой домашний каталог идёт до привязок: compatdata обычно лежит внутри него.
+    if let Some(home) = env::var_os("HOME") {
+        child.arg("--tmpfs").arg(home);
+    }
+    if let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR") {
+        child.arg("--tmpfs").arg(runtime_dir);
+    }
+
+    let writable = writable_dirs(target)?;
+    for dir in &writable {
+        bind(&mut child, "--bind", dir);
+    }
+    if let Ok(proton) = target.exe()
+        && let Some(proton_dir) = Path::new(&proton).parent()
+    {
+        bind(&mut child, "--ro-bind", proton_dir);
+    }
+    for program in program_bindings(&cmd[0]) {
+        bind(&mut child, "--ro-bind", &program);
+    }
+    for socket in display_sockets() {
+        bind(&mut child, "--ro-bind", &socket);
+    }
+
+    // Текущий каталог остаётся, если он внутри доступного на запись.
+    let cwd = env::current_dir()?;
+    let workdir = if writable.iter().any(|dir| cwd.starts_with(dir)) {
+        Some(cwd)
+    } else {
+        writable.last().cloned()
+    };
+    if let Some(workdir) = workdir {
+        child.arg("--chdir").arg(workdir);
+    }
+    child.arg("--").args(cmd);
+    Ok(child)
+}
+
+/// Привязка, если путь существует; симлинки (`/bin` → `usr/bin`) переносятся как есть.
+fn bind(child: &mut process::Command, flag: &str, path: &Path) {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            if let Ok(target) = fs::read_link(path) {
+                child.arg("--symlink").arg(target).arg(path);
+            }
+        }
+        Ok(_) => {
+            child.arg(flag).arg(path).arg(path);
+        }
+        Err(_) => verbosity::debug(format_args!("нет {}, не привязываем", path.display())),
+    }
+}
+
+/// compatdata и каталог установки игры: их модам и утилитам и нужно менять. У нативной
+/// игры compatdata нет.
+fn writable_dirs(target: &TargetApp) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    if !target.is_native() {
+        let pfx = PathBuf::from(target.pfx()?);
+        dirs.push(pfx.parent().map_or_else(|| pfx.clone(), Path::to_path_buf));
+    }
+    if let Some(install_path) = target.meta().install_path {
+        dirs.push(PathBuf::from(install_path));
+    }
+    Ok(dirs)
+}
+
+/// Каталоги программы вне системных: без них её нечем запустить. bwrap запускает путь как
+/// указан, поэтому привязывается и он, и настоящий, если это символьная ссылка
+/// (`~/bin/tool` → `~/apps/tool/run`).
+fn program_bindings(program: &str) -> Vec<PathBuf> {
+    let given = if program.contains('/') {
+        std::path::absolute(program).ok()
+    } else {
+        find_in_path(program)
+    };
+    let Some(given) = given else {
+        return Vec::new();
+    };
+    let real = fs::canonicalize(&given).ok();
+    let mut bindings: Vec<PathBuf> = [Some(given), real]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| program_binding(&path))
+        .collect();
+    bindings.dedup();
+    bindings
+}
+
+/// Программу прямо в `$HOME` или `/tmp` привязываем одну, а не весь каталог.
+fn program_binding(path: &Path) -> Option<PathBuf> {
+    if SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return None;
+    }
+    let parent = path.parent()?;
+    let shared = parent == Path::new("/tmp")
+        || parent == Path::new("/")
+        || env::var_os("HOME").is_some_and(|home| parent == Path::new(&home));
+    if shared {
+        Some(path.to_path_buf())
+    } else {
+        Some(parent.to_path_buf())
+    }
+}
+
+/// Сокеты X11 и Wayland и файл авторизации X.
+fn display_sockets() -> Vec<PathBuf> {
+    let mut sockets = vec![PathBuf::from(X11_SOCKETS)];
+    if let Some(xauthority) = env::var_os("XAUTHORITY") {
+        sockets.push(PathBuf::from(xauthority));
+    }
+    if let (Some(runtime_dir), Some(display)) = (
+        env::var_os("XDG_RUNTIME_DIR"),
+        env::var_os("WAYLAND_DISPLAY"),
+    ) {
+        sockets.push(Path::new(&runtime_dir).join(display));
+    }
+    sockets
+}
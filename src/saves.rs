@@ -0,0 +1,183 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::fail,
+    du::disk_usage,
+    output::print_json,
+    runtime::{format_size, unix_now_secs},
+    steam::steam_roots,
+    wine::locate_prefix,
+};
+
+/// Каталоги профиля `steamuser`, где игры обычно держат сохранения: каждый их подкаталог —
+/// кандидат.
+const PROFILE_SAVE_DIRS: &[&str] = &[
+    "Documents",
+    "Documents/My Games",
+    "Saved Games",
+    "AppData/Roaming",
+    "AppData/Local",
+    "AppData/LocalLow",
+];
+/// Подкаталоги, которые создаёт сам Wine или Windows, а не игра.
+const PROFILE_NOISE: &[&str] = &[
+    "My Games",
+    "My Music",
+    "My Pictures",
+    "My Videos",
+    "Downloads",
+    "Microsoft",
+    "Temp",
+    "CrashDumps",
+    "Packages",
+    "ConnectedDevicesPlatform",
+    "openxr",
+];
+
+struct SaveLocation {
+    kind: &'static str,
+    path: PathBuf,
+    bytes: Option<u64>,
+}
+
+/// Находит вероятные каталоги сохранений игры и по `--backup` упаковывает их в tar.gz.
+pub fn handle_saves(
+    phd: &Path,
+    selector: &str,
+    backup: Option<&Path>,
+    json_output: bool,
+) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let profile = prefix.pfx.join("drive_c/users/steamuser");
+    let mut locations: Vec<SaveLocation> = PROFILE_SAVE_DIRS
+        .iter()
+        .flat_map(|dir| child_dirs(&profile.join(dir)))
+        .map(|path| SaveLocation {
+            kind: "prefix",
+            bytes: disk_usage(&path).ok(),
+            path,
+        })
+        .collect();
+    locations.extend(
+        steam_cloud_dirs(&prefix.appid)
+            .into_iter()
+            .map(|path| SaveLocation {
+                kind: "steam cloud",
+                bytes: disk_usage(&path).ok(),
+                path,
+            }),
+    );
+
+    if let Some(dest) = backup {
+        if locations.is_empty() {
+            fail(&format!(
+                "Каталоги сохранений для {} не найдены",
+                prefix.appid
+            ));
+        }
+        let archive = archive_path(dest, &prefix.appid);
+        pack(&locations, &archive)?;
+        println!(
+            "{} saves backed up to {}",
+            "OK".green().bold(),
+            archive.display()
+        );
+        return Ok(());
+    }
+
+    if json_output {
+        let entries: Vec<_> = locations
+            .iter()
+            .map(|location| {
+                json!({
+                    "kind": location.kind,
+                    "path": location.path,
+                    "bytes": location.bytes,
+                })
+            })
+            .collect();
+        return print_json(&json!({ "appid": prefix.appid, "saves": entries }));
+    }
+
+    if locations.is_empty() {
+        println!("Каталоги сохранений не найдены");
+    }
+    for location in &locations {
+        let size = location.bytes.map_or_else(|| "-".to_string(), format_size);
+        println!(
+            "{:<12} {:>10}  {}",
+            location.kind.green(),
+            size.yellow(),
+            location.path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Непустые подкаталоги без служебных. Симлинки пропускаем: у Wine вне Proton
+/// `Documents` и соседи ведут в домашний каталог.
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter(|entry| {
+            let name = entry.file_name();
+            !PROFILE_NOISE.iter().any(|noise| name == *noise)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some()))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// `userdata/<аккаунт>/<appid>/remote` — локальная копия Steam Cloud для каждого аккаунта.
+fn steam_cloud_dirs(appid: &str) -> Vec<PathBuf> {
+    steam_roots()
+        .iter()
+        .flat_map(|root| fs::read_dir(root.join("userdata")).into_iter().flatten())
+        .flatten()
+        .map(|account| account.path().join(appid).join("remote"))
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// В каталог кладём архив с именем по умолчанию, иначе `dest` — сам путь архива.
+fn archive_path(dest: &Path, appid: &str) -> PathBuf {
+    if dest.is_dir() {
+        dest.join(format!(
+            "protonhax-saves-{appid}-{}.tar.gz",
+            unix_now_secs()
+        ))
+    } else {
+        dest.to_path_buf()
+    }
+}
+
+/// Пути в архиве — от корня: после распаковки с `-C /` всё встанет на свои места.
+fn pack(locations: &[SaveLocation], archive: &Path) -> io::Result<()> {
+    let mut tar = process::Command::new("tar");
+    tar.arg("-czf").arg(archive).arg("-C").arg("/");
+    for location in locations {
+        tar.arg(location.path.strip_prefix("/").unwrap_or(&location.path));
+    }
+    let status = tar.status().map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            io::Error::new(err.kind(), "tar не найден в PATH")
+        } else {
+            err
+        }
+    })?;
+    if !status.success() {
+        fail(&format!("tar завершился с ошибкой ({status})"));
+    }
+    Ok(())
+}
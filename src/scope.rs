@@ -0,0 +1,110 @@
+use clap::Args;
+use std::{ffi::OsStr, process};
+
+/// Имя scope-юнита `protonhax-<appid>-<tool>-<pid>`: по префиксу юниты игры легко найти
+/// (`systemctl --user list-units 'protonhax-*'`), а pid не даёт повторному запуску
+/// того же инструмента упасть на занятом имени.
+pub fn scope_unit_name(appid: &str, tool: &str, pid: u32) -> String {
+    let sanitize = |value: &str| -> String {
+        value
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    format!("protonhax-{}-{}-{pid}", sanitize(appid), sanitize(tool))
+}
+
+/// Ограничения ресурсов, которые применяются через свойства scope-юнита systemd.
+#[derive(Args, Default)]
+pub struct ResourceLimits {
+    /// CPU time limit for the launched command, e.g. `50%` (200% = two cores)
+    #[arg(long, value_name = "PERCENT", value_parser = parse_cpu_quota)]
+    pub cpu_quota: Option<String>,
+    /// Memory limit for the launched command, e.g. `4G` or `512M`
+    #[arg(long, value_name = "SIZE", value_parser = parse_memory_max)]
+    pub memory_max: Option<String>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_quota.is_none() && self.memory_max.is_none()
+    }
+
+    fn properties(&self) -> Vec<String> {
+        let mut properties = Vec::new();
+        if let Some(quota) = &self.cpu_quota {
+            properties.push(format!("CPUQuota={quota}"));
+        }
+        if let Some(memory) = &self.memory_max {
+            properties.push(format!("MemoryMax={memory}"));
+        }
+        properties
+    }
+}
+
+/// Команда, запускающая `program` через `systemd-run --user --scope`: окружение и
+/// рабочий каталог наследуются, а процессы Wine остаются в отдельной cgroup.
+pub fn command_in_scope(
+    unit: &str,
+    limits: &ResourceLimits,
+    program: impl AsRef<OsStr>,
+) -> process::Command {
+    let mut cmd = process::Command::new("systemd-run");
+    cmd.args(["--user", "--scope", "--quiet", "--collect"])
+        .arg(format!("--unit={unit}"));
+    for property in limits.properties() {
+        cmd.arg("-p").arg(property);
+    }
+    cmd.arg("--").arg(program);
+    cmd
+}
+
+fn parse_cpu_quota(value: &str) -> Result<String, String> {
+    let percent = value.strip_suffix('%').unwrap_or(value);
+    match percent.parse::<u32>() {
+        Ok(percent) if percent > 0 => Ok(format!("{percent}%")),
+        _ => Err("ожидается процент больше нуля, например 50%".to_string()),
+    }
+}
+
+fn parse_memory_max(value: &str) -> Result<String, String> {
+    let digits = value.trim_end_matches(['K', 'M', 'G', 'T', 'k', 'm', 'g', 't']);
+    let suffix = &value[digits.len()..];
+    if suffix.len() <= 1 && !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Ok(value.to_ascii_uppercase());
+    }
+    if value.ends_with('%') && parse_cpu_quota(value).is_ok() {
+        return Ok(value.to_string());
+    }
+    Err("ожидается размер вида 4G, 512M или процент от памяти".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cpu_quota, parse_memory_max, scope_unit_name};
+
+    #[test]
+    fn builds_valid_unit_names() {
+        assert_eq!(
+            scope_unit_name("1217060", "cheat engine", 42),
+            "protonhax-1217060-cheat_engine-42"
+        );
+        assert_eq!(scope_unit_name("1", "mo2/v2", 7), "protonhax-1-mo2_v2-7");
+    }
+
+    #[test]
+    fn validates_limits() {
+        assert_eq!(parse_cpu_quota("50%").as_deref(), Ok("50%"));
+        assert_eq!(parse_cpu_quota("150").as_deref(), Ok("150%"));
+        assert!(parse_cpu_quota("0%").is_err());
+        assert_eq!(parse_memory_max("4g").as_deref(), Ok("4G"));
+        assert_eq!(parse_memory_max("1048576").as_deref(), Ok("1048576"));
+        assert!(parse_memory_max("4GB").is_err());
+    }
+}
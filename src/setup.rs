@@ -0,0 +1,226 @@
+use colored::Colorize;
+use std::io;
+
+use crate::{
+    cli::fail,
+    launch_options::{
+        GameLaunch, INIT_LAUNCH_OPTION, LaunchSetup, LocalConfig, migrated, with_protonhax,
+        without_protonhax,
+    },
+    steam::steam_running,
+    ui, vdf, verbosity,
+};
+
+/// Что `setup` делает с выбранными играми.
+pub enum SetupMode {
+    Install,
+    /// `--remove`: убрать вызов protonhax, остальные параметры оставить.
+    Remove,
+    /// `--migrate`: старые вызовы и свои обёртки (имена скриптов) → текущий вид.
+    Migrate(Vec<String>),
+    /// `--interactive`: выбор флажками.
+    Interactive,
+}
+
+/// Новые параметры запуска одной игры.
+struct Change<'a> {
+    game: &'a GameLaunch,
+    options: String,
+}
+
+/// Прописывает `protonhax init %command%` в параметры запуска игр Steam, убирает его или
+/// переводит старые вызовы: по appid, всем сразу или выбором в списке. Без аргументов
+/// показывает, где protonhax уже стоит.
+pub fn handle_setup(appids: &[String], all: bool, mode: &SetupMode) -> io::Result<()> {
+    let Some(mut config) = LocalConfig::load()? else {
+        fail("localconfig.vdf не найден: войдите в Steam хотя бы раз");
+    };
+    verbosity::verbose(format_args!("настройки Steam: {}", config.path.display()));
+    let games = config.proton_games();
+    if games.is_empty() {
+        fail("Игр, запускавшихся через Proton, не найдено: запустите игру хотя бы раз");
+    }
+    // `--migrate` без appid проходит по всем играм: старый вызов есть не у каждой.
+    let every = all || matches!(mode, SetupMode::Migrate(_)) && appids.is_empty();
+    match mode {
+        SetupMode::Install if !every && appids.is_empty() => {
+            print_games(&games);
+            return Ok(());
+        }
+        SetupMode::Remove if !every && appids.is_empty() => {
+            fail("Укажите игры для --remove: appid или --all");
+        }
+        _ => {}
+    }
+    // Steam держит параметры запуска в памяти и при выходе перезапишет файл своей копией.
+    if steam_running() {
+        fail("Steam запущен: закройте его, иначе он перезапишет localconfig.vdf");
+    }
+
+    let changes = if let SetupMode::Interactive = mode {
+        let Some(changes) = pick_changes(&games)? else {
+            eprintln!("Отменено");
+            std::process::exit(1);
+        };
+        changes
+    } else {
+        let targets: Vec<&GameLaunch> = if every {
+            games.iter().collect()
+        } else {
+            appids
+                .iter()
+                .map(|appid| find_game(&games, appid))
+                .collect()
+        };
+        let mut changes = Vec::new();
+        for game in targets {
+            match change_for(game, mode) {
+                Some(change) => changes.push(change),
+                // О пропуске всех остальных игр при `--all` сообщать незачем.
+                None if !every => skip_note(game, mode),
+                None => {}
+            }
+        }
+        changes
+    };
+    if changes.is_empty() {
+        println!("Параметры запуска менять не нужно");
+        return Ok(());
+    }
+
+    print_diff(&config, &changes);
+    ui::confirm_or_abort("Записать изменения в localconfig.vdf?");
+    for change in &changes {
+        if !config.set_launch_options(&change.game.appid, &change.options) {
+            fail(&format!(
+                "Не удалось разобрать {}: файл не изменён",
+                config.path.display()
+            ));
+        }
+    }
+    let backup = config.save()?;
+    verbosity::info(format_args!("копия: {}", backup.display()));
+    println!(
+        "Параметры запуска обновлены (игр: {}); Steam применит их при следующем запуске",
+        changes.len()
+    );
+    Ok(())
+}
+
+fn find_game<'a>(games: &'a [GameLaunch], appid: &str) -> &'a GameLaunch {
+    match games.iter().find(|game| game.appid == appid) {
+        Some(game) => game,
+        None => fail(&format!(
+            "{appid} нет среди установленных игр на Proton (см. `protonhax setup`)"
+        )),
+    }
+}
+
+/// Правка параметров одной игры; `None` — менять нечего.
+fn change_for<'a>(game: &'a GameLaunch, mode: &SetupMode) -> Option<Change<'a>> {
+    match mode {
+        SetupMode::Install | SetupMode::Interactive => {
+            (game.setup != LaunchSetup::Installed).then(|| install(game))
+        }
+        SetupMode::Remove => (game.setup != LaunchSetup::Missing).then(|| Change {
+            game,
+            options: without_protonhax(&game.options),
+        }),
+        SetupMode::Migrate(wrappers) => {
+            migrated(&game.options, wrappers).map(|options| Change { game, options })
+        }
+    }
+}
+
+fn skip_note(game: &GameLaunch, mode: &SetupMode) {
+    let reason = match mode {
+        SetupMode::Remove => "protonhax в параметрах запуска нет",
+        SetupMode::Migrate(_) if game.setup == LaunchSetup::Installed => "уже в текущем виде",
+        SetupMode::Migrate(_) => "старого вызова нет (свой скрипт — через --wrapper)",
+        SetupMode::Install | SetupMode::Interactive => "уже настроена",
+    };
+    verbosity::info(format_args!("{}: {reason}", game.label()));
+}
+
+fn install(game: &GameLaunch) -> Change<'_> {
+    Change {
+        game,
+        options: with_protonhax(&game.options),
+    }
+}
+
+/// Отмеченные игры получают protonhax, с которых отметку сняли — теряют. Старый вызов,
+/// оставленный отмеченным, не трогается. `None` — выбор отменён.
+fn pick_changes(games: &[GameLaunch]) -> io::Result<Option<Vec<Change<'_>>>> {
+    let items: Vec<String> = games
+        .iter()
+        .map(|game| match game.setup {
+            LaunchSetup::Legacy => format!("{} — старый вызов: {}", game.label(), game.options),
+            _ => game.label(),
+        })
+        .collect();
+    let mut checked: Vec<bool> = games
+        .iter()
+        .map(|game| game.setup != LaunchSetup::Missing)
+        .collect();
+    let title = format!("Игры, запускаемые через «{INIT_LAUNCH_OPTION}»:");
+    if !ui::checklist(&title, &items, &mut checked)? {
+        return Ok(None);
+    }
+    let changes = games
+        .iter()
+        .zip(checked)
+        .filter_map(|(game, checked)| match (game.setup, checked) {
+            (LaunchSetup::Missing, true) => Some(install(game)),
+            (LaunchSetup::Installed | LaunchSetup::Legacy, false) => Some(Change {
+                game,
+                options: without_protonhax(&game.options),
+            }),
+            _ => None,
+        })
+        .collect();
+    Ok(Some(changes))
+}
+
+fn print_games(games: &[GameLaunch]) {
+    for game in games {
+        let mark = match game.setup {
+            LaunchSetup::Installed => "[x]".green(),
+            LaunchSetup::Legacy => "[~]".yellow(),
+            LaunchSetup::Missing => "[ ]".normal(),
+        };
+        if game.options.is_empty() {
+            println!("{mark} {}", game.label());
+        } else {
+            println!("{mark} {}  {}", game.label(), game.options.dimmed());
+        }
+    }
+    verbosity::info("настроить: protonhax setup <appid>... | --all | --interactive");
+    if games.iter().any(|game| game.setup == LaunchSetup::Legacy) {
+        verbosity::info("[~] перевести на текущий вид: protonhax setup --migrate");
+    }
+}
+
+/// Изменения в виде правки `localconfig.vdf`: строка `LaunchOptions` до и после.
+fn print_diff(config: &LocalConfig, changes: &[Change]) {
+    let path = config.path.display();
+    println!("{}", format!("--- {path}").bold());
+    println!("{}", format!("+++ {path}").bold());
+    for change in changes {
+        println!("{}", format!("@@ {} @@", change.game.label()).cyan());
+        if !change.game.options.is_empty() {
+            println!(
+                "{}",
+                format!(
+                    "-\t\"LaunchOptions\"\t\t{}",
+                    vdf::quoted(&change.game.options)
+                )
+                .red()
+            );
+        }
+        println!(
+            "{}",
+            format!("+\t\"LaunchOptions\"\t\t{}", vdf::quoted(&change.options)).green()
+        );
+    }
+}
@@ -44,6 +44,31 @@ fn needs_shell_quotes(s: &str) -> bool {
         || s.contains('`')
 }
 
+/// Значение для `eval` в bash/zsh: в одинарных кавычках ничего не раскрывается,
+/// сама кавычка записывается как `'\''`.
+pub fn sh_quote(s: &str) -> String {
+    if is_plain_word(s) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Значение для fish: в одинарных кавычках fish понимает только `\'` и `\\`.
+pub fn fish_quote(s: &str) -> String {
+    if is_plain_word(s) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Слово, которое ни одна оболочка не раскроет и не разобьёт.
+fn is_plain_word(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | ',' | '+' | '@')
+        })
+}
+
 /// Функция для деэкранирования строки в стиле shell из двойных кавычек.
 pub fn un_shell_escape(s: &str) -> String {
     // Если строка не в кавычках, возвращаем как есть.
@@ -79,9 +104,44 @@ pub fn un_shell_escape(s: &str) -> String {
     res
 }
 
+/// Проверяет, что значение записано так, как его пишет `shell_escape`: без кавычек
+/// или в двойных кавычках с экранированными обратной косой чертой, кавычкой, `$` и обратной
+/// кавычкой.
+pub fn validate_shell_value(s: &str) -> Result<(), &'static str> {
+    let Some(quoted) = s.strip_prefix('"') else {
+        if needs_shell_quotes(s) {
+            return Err("значение с пробелами или спецсимволами нужно взять в двойные кавычки");
+        }
+        return Ok(());
+    };
+
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => return Err("нет закрывающей кавычки"),
+            '"' if chars.peek().is_none() => return Ok(()),
+            '"' | '$' | '`' => return Err("символы \" $ ` внутри кавычек нужно экранировать \\"),
+            _ => {}
+        }
+    }
+    Err("нет закрывающей кавычки")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{is_env_assignment, shell_escape, split_env_assignment, un_shell_escape};
+    use super::{
+        fish_quote, is_env_assignment, sh_quote, shell_escape, split_env_assignment,
+        un_shell_escape, validate_shell_value,
+    };
+
+    #[test]
+    fn quotes_for_eval() {
+        assert_eq!(sh_quote("/usr/bin:/bin"), "/usr/bin:/bin");
+        assert_eq!(sh_quote(""), "''");
+        assert_eq!(sh_quote("it's $HOME; `x`"), r"'it'\''s $HOME; `x`'");
+        assert_eq!(fish_quote(r"it's a\b"), r"'it\'s a\\b'");
+        assert_eq!(fish_quote("a;b"), "'a;b'");
+    }
 
     #[test]
     fn env_assignment_detection() {
@@ -110,4 +170,14 @@ mod tests {
         assert_eq!(escaped, "\"\\`uname\\`\"");
         assert_eq!(un_shell_escape(&escaped), "`uname`");
     }
+
+    #[test]
+    fn validates_escaped_values() {
+        assert_eq!(validate_shell_value("plain"), Ok(()));
+        assert_eq!(validate_shell_value(&shell_escape(r#"a "b" $c\"#)), Ok(()));
+        assert!(validate_shell_value("two words").is_err());
+        assert!(validate_shell_value(r#""open"#).is_err());
+        assert!(validate_shell_value(r#""a"b""#).is_err());
+        assert!(validate_shell_value(r#""a\""#).is_err());
+    }
 }
@@ -0,0 +1,413 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::{ShortcutsCommands, fail},
+    output::print_json,
+    runtime::exit_with_status,
+    wine::{PrefixContext, locate_prefix, resolve_prefix},
+};
+
+/// Каталоги префикса, куда установщики кладут ярлыки.
+const SHORTCUT_DIRS: &[&str] = &[
+    "drive_c/users/steamuser/AppData/Roaming/Microsoft/Windows/Start Menu/Programs",
+    "drive_c/ProgramData/Microsoft/Windows/Start Menu/Programs",
+    "drive_c/users/steamuser/Desktop",
+    "drive_c/users/Public/Desktop",
+];
+
+const LNK_HEADER_SIZE: u32 = 0x4c;
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const IS_UNICODE: u32 = 0x80;
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+
+/// Разобранный `.lnk`: пути в Windows-формате, как их записал установщик.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct LnkInfo {
+    target: Option<String>,
+    relative_path: Option<String>,
+    working_dir: Option<String>,
+    arguments: Option<String>,
+}
+
+struct Shortcut {
+    name: String,
+    path: PathBuf,
+    info: LnkInfo,
+}
+
+pub fn handle_shortcuts(
+    phd: &Path,
+    appid: Option<String>,
+    command: Option<ShortcutsCommands>,
+    json_output: bool,
+) -> io::Result<()> {
+    match (command, appid) {
+        (Some(ShortcutsCommands::Run { appid, name }), _) => run_shortcut(phd, &appid, &name),
+        (None, Some(appid)) => list_shortcuts(phd, &appid, json_output),
+        (None, None) => fail("Укажите игру: `protonhax shortcuts <target>`"),
+    }
+}
+
+fn list_shortcuts(phd: &Path, selector: &str, json_output: bool) -> io::Result<()> {
+    let prefix = locate_prefix(phd, selector)?;
+    let shortcuts = collect_shortcuts(&prefix)?;
+    if json_output {
+        let data: Vec<_> = shortcuts
+            .iter()
+            .map(|shortcut| {
+                json!({
+                    "name": shortcut.name,
+                    "target": shortcut_target(shortcut),
+                    "arguments": shortcut.info.arguments,
+                    "working_dir": shortcut.info.working_dir,
+                })
+            })
+            .collect();
+        return print_json(&json!({ "appid": prefix.appid, "shortcuts": data }));
+    }
+
+    for shortcut in shortcuts {
+        let mut parts = vec![shortcut.name.green().to_string()];
+        match shortcut_target(&shortcut) {
+            Some(target) => parts.push(target.yellow().to_string()),
+            None => parts.push("<цель не определена>".red().to_string()),
+        }
+        if let Some(arguments) = &shortcut.info.arguments {
+            parts.push(arguments.clone());
+        }
+        if let Some(working_dir) = &shortcut.info.working_dir {
+            parts.push(format!("(in {working_dir})").dimmed().to_string());
+        }
+        println!("{}", parts.join("  "));
+    }
+    Ok(())
+}
+
+fn run_shortcut(phd: &Path, selector: &str, query: &str) -> io::Result<()> {
+    let prefix = resolve_prefix(phd, selector)?;
+    let shortcuts = collect_shortcuts(&prefix)?;
+
+    let exact: Vec<&Shortcut> = shortcuts
+        .iter()
+        .filter(|shortcut| shortcut.name.eq_ignore_ascii_case(query))
+        .collect();
+    let matches = if exact.is_empty() {
+        let query = query.to_lowercase();
+        shortcuts
+            .iter()
+            .filter(|shortcut| shortcut.name.to_lowercase().contains(&query))
+            .collect()
+    } else {
+        exact
+    };
+
+    let shortcut = match matches.as_slice() {
+        [shortcut] => *shortcut,
+        [] => fail(&format!(
+            "Ярлык \"{query}\" не найден (см. `protonhax shortcuts {selector}`)"
+        )),
+        _ => {
+            eprintln!(
+                "{} Несколько ярлыков подходят под \"{query}\":",
+                "Ошибка:".bold().red()
+            );
+            for shortcut in matches {
+                eprintln!("  {}", shortcut.name.green());
+            }
+            process::exit(2);
+        }
+    };
+
+    let Some(target) = shortcut_target(shortcut) else {
+        fail(&format!(
+            "Не удалось определить цель ярлыка {}",
+            shortcut.path.display()
+        ));
+    };
+
+    let mut child = process::Command::new(&prefix.proton);
+    child.arg("run").arg(&target);
+    if let Some(arguments) = &shortcut.info.arguments {
+        child.args(split_windows_args(arguments));
+    }
+    if let Some(dir) = shortcut
+        .info
+        .working_dir
+        .as_deref()
+        .and_then(|dir| prefix.windows_to_host(dir))
+        .filter(|dir| dir.is_dir())
+    {
+        child.current_dir(dir);
+    }
+
+    let status = child.status()?;
+    exit_with_status(status);
+}
+
+/// Цель ярлыка: абсолютный путь из `LinkInfo`, иначе относительный путь от каталога `.lnk`.
+fn shortcut_target(shortcut: &Shortcut) -> Option<String> {
+    if let Some(target) = &shortcut.info.target {
+        return Some(target.clone());
+    }
+
+    let relative = shortcut.info.relative_path.as_deref()?;
+    let base = shortcut.path.parent()?;
+    Some(
+        base.join(relative.replace('\\', "/"))
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+fn collect_shortcuts(prefix: &PrefixContext) -> io::Result<Vec<Shortcut>> {
+    let mut files = Vec::new();
+    for dir in SHORTCUT_DIRS {
+        let dir = prefix.pfx.join(dir);
+        if dir.is_dir() {
+            collect_lnk_files(&dir, &mut files)?;
+        }
+    }
+
+    let mut shortcuts: Vec<Shortcut> = files
+        .into_iter()
+        .filter_map(|path| {
+            let data = fs::read(&path).ok()?;
+            let info = parse_lnk(&data)?;
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some(Shortcut { name, path, info })
+        })
+        .collect();
+    shortcuts.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(shortcuts)
+}
+
+fn collect_lnk_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_lnk_files(&path, out)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Минимальный разбор формата Shell Link (MS-SHLLINK): `LinkInfo` и `StringData`.
+fn parse_lnk(data: &[u8]) -> Option<LnkInfo> {
+    if read_u32(data, 0)? != LNK_HEADER_SIZE {
+        return None;
+    }
+
+    let flags = read_u32(data, 0x14)?;
+    let mut offset = LNK_HEADER_SIZE as usize;
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        offset += 2 + usize::from(read_u16(data, offset)?);
+    }
+
+    let mut info = LnkInfo::default();
+    if flags & HAS_LINK_INFO != 0 {
+        let link_info = data.get(offset..)?;
+        info.target = parse_link_info(link_info);
+        offset += read_u32(link_info, 0)? as usize;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    for (flag, slot) in [
+        (HAS_NAME, None),
+        (HAS_RELATIVE_PATH, Some(&mut info.relative_path)),
+        (HAS_WORKING_DIR, Some(&mut info.working_dir)),
+        (HAS_ARGUMENTS, Some(&mut info.arguments)),
+    ] {
+        if flags & flag == 0 {
+            continue;
+        }
+        let (value, next) = read_string_data(data, offset, unicode)?;
+        offset = next;
+        if let Some(slot) = slot {
+            *slot = Some(value).filter(|value| !value.is_empty());
+        }
+    }
+
+    Some(info)
+}
+
+fn parse_link_info(link_info: &[u8]) -> Option<String> {
+    let header_size = read_u32(link_info, 4)? as usize;
+    let link_info_flags = read_u32(link_info, 8)?;
+    if link_info_flags & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+
+    let suffix_offset = read_u32(link_info, 0x18)? as usize;
+    if header_size >= 0x24 {
+        let unicode_base = read_u32(link_info, 0x1c)? as usize;
+        let unicode_suffix = read_u32(link_info, 0x20)? as usize;
+        if unicode_base != 0 {
+            let mut path = read_utf16_cstr(link_info, unicode_base)?;
+            if unicode_suffix != 0 {
+                path.push_str(&read_utf16_cstr(link_info, unicode_suffix)?);
+            }
+            return Some(path);
+        }
+    }
+
+    let base_offset = read_u32(link_info, 0x10)? as usize;
+    let mut path = read_ansi_cstr(link_info, base_offset)?;
+    if suffix_offset != 0 {
+        path.push_str(&read_ansi_cstr(link_info, suffix_offset)?);
+    }
+    Some(path)
+}
+
+fn read_string_data(data: &[u8], offset: usize, unicode: bool) -> Option<(String, usize)> {
+    let count = usize::from(read_u16(data, offset)?);
+    let start = offset + 2;
+    if unicode {
+        let bytes = data.get(start..start + count * 2)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        Some((String::from_utf16_lossy(&units), start + count * 2))
+    } else {
+        let bytes = data.get(start..start + count)?;
+        Some((String::from_utf8_lossy(bytes).into_owned(), start + count))
+    }
+}
+
+fn read_ansi_cstr(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&byte| byte == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+fn read_utf16_cstr(data: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = data
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Разбивает командную строку Windows на аргументы (пробелы, двойные кавычки).
+fn split_windows_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LnkInfo, parse_lnk, split_windows_args};
+
+    fn push_u16(buf: &mut Vec<u8>, value: u16) {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_u32(buf: &mut Vec<u8>, value: u32) {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_string_data(buf: &mut Vec<u8>, value: &str) {
+        let units: Vec<u16> = value.encode_utf16().collect();
+        push_u16(buf, u16::try_from(units.len()).unwrap());
+        for unit in units {
+            push_u16(buf, unit);
+        }
+    }
+
+    #[test]
+    fn parses_link_info_and_string_data() {
+        // HasLinkInfo | HasWorkingDir | HasArguments | IsUnicode
+        let flags = 0x02 | 0x10 | 0x20 | 0x80;
+        let mut lnk = Vec::new();
+        push_u32(&mut lnk, 0x4c);
+        lnk.extend_from_slice(&[0u8; 16]);
+        push_u32(&mut lnk, flags);
+        lnk.resize(0x4c, 0);
+
+        let base_path = b"C:\\Games\\Tool\\tool.exe\0";
+        let header_size = 0x1c_u32;
+        let base_offset = header_size;
+        let suffix_offset = base_offset + u32::try_from(base_path.len()).unwrap();
+        let link_info_size = suffix_offset + 1;
+        push_u32(&mut lnk, link_info_size);
+        push_u32(&mut lnk, header_size);
+        push_u32(&mut lnk, 0x01);
+        push_u32(&mut lnk, 0);
+        push_u32(&mut lnk, base_offset);
+        push_u32(&mut lnk, 0);
+        push_u32(&mut lnk, suffix_offset);
+        lnk.extend_from_slice(base_path);
+        lnk.push(0);
+
+        push_string_data(&mut lnk, "C:\\Games\\Tool");
+        push_string_data(&mut lnk, "-windowed \"a b\"");
+
+        assert_eq!(
+            parse_lnk(&lnk),
+            Some(LnkInfo {
+                target: Some("C:\\Games\\Tool\\tool.exe".to_string()),
+                relative_path: None,
+                working_dir: Some("C:\\Games\\Tool".to_string()),
+                arguments: Some("-windowed \"a b\"".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn splits_windows_command_line() {
+        assert_eq!(split_windows_args(r#"-a "b c" """#), ["-a", "b c", ""]);
+    }
+}
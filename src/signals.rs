@@ -0,0 +1,156 @@
+use colored::Colorize;
+use std::{collections::HashMap, fs, io, path::Path, process};
+
+use crate::{
+    audit::{self, AuditAction},
+    cli::fail,
+    context::{PID_FILE, prepare_context, read_trimmed, resolve_target_app},
+    runtime::{exit_with_status, unix_now_secs},
+};
+
+/// Приостанавливает (`SIGSTOP`) или продолжает (`SIGCONT`) процессы игры.
+///
+/// Группа процессов `init` совпадает с группой лаунчера Steam, поэтому сигнал получает
+/// дерево потомков записанного pid, а не вся группа.
+pub fn handle_pause(phd: &Path, selector: &str, pause: bool) -> io::Result<()> {
+    let target = resolve_target_app(phd, selector)?;
+    let Some(pid) = read_trimmed(target.app_dir.join(PID_FILE))
+        .ok()
+        .and_then(|pid| pid.parse::<libc::pid_t>().ok())
+    else {
+        fail(&format!(
+            "Для {} не записан pid — перезапустите игру с актуальной версией protonhax",
+            target.appid
+        ));
+    };
+
+    let signal = if pause { libc::SIGSTOP } else { libc::SIGCONT };
+    let signaled = signal_tree(pid, signal)?;
+    if signaled == 0 {
+        fail(&format!("Процесс {pid} игры {} не найден", target.appid));
+    }
+
+    let action = if pause {
+        "Приостановлено"
+    } else {
+        "Возобновлено"
+    };
+    println!(
+        "{} {action} процессов: {signaled} ({})",
+        "✓".green().bold(),
+        target.appid
+    );
+    Ok(())
+}
+
+/// Принудительно завершает Windows-процесс по имени образа через `taskkill` в префиксе.
+pub fn handle_killwin(phd: &Path, selector: &str, image: &str) -> io::Result<()> {
+    let target = prepare_context(phd, selector)?;
+    let started_at = unix_now_secs();
+    let status = process::Command::new(target.exe()?)
+        .arg("run")
+        .args(["taskkill", "/F", "/IM"])
+        .arg(image_name(image))
+        .status()?;
+    audit::record(AuditAction::Killwin, &target.appid, started_at, status);
+    exit_with_status(status);
+}
+
+/// `taskkill /IM` сравнивает полное имя образа, поэтому дописываем `.exe`, если его нет.
+fn image_name(image: &str) -> String {
+    if Path::new(image).extension().is_some() {
+        image.to_string()
+    } else {
+        format!("{image}.exe")
+    }
+}
+
+/// Отправляет сигнал процессу и всем его потомкам; возвращает, скольким дошёл.
+pub fn signal_tree(root: libc::pid_t, signal: libc::c_int) -> io::Result<usize> {
+    Ok(signal_all(&process_tree(root)?, signal))
+}
+
+/// Как [`signal_tree`], но без самого `root`: `init` переживает игру и успевает убрать
+/// контекст.
+pub fn signal_descendants(root: libc::pid_t, signal: libc::c_int) -> io::Result<usize> {
+    let tree = process_tree(root)?;
+    Ok(signal_all(tree.get(1..).unwrap_or_default(), signal))
+}
+
+fn signal_all(pids: &[libc::pid_t], signal: libc::c_int) -> usize {
+    let mut signaled = 0;
+    for &pid in pids {
+        // SAFETY: kill лишь отправляет сигнал процессу.
+        if unsafe { libc::kill(pid, signal) } == 0 {
+            signaled += 1;
+        }
+    }
+    signaled
+}
+
+pub fn process_alive(pid: libc::pid_t) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Процесс `root` и все его потомки по данным `/proc`; пусто, если `root` уже завершился.
+pub fn process_tree(root: libc::pid_t) -> io::Result<Vec<libc::pid_t>> {
+    let mut children: HashMap<libc::pid_t, Vec<libc::pid_t>> = HashMap::new();
+    let mut root_alive = false;
+    for entry in fs::read_dir("/proc")?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        // Процесс мог завершиться между read_dir и чтением stat.
+        let Some(parent) = fs::read_to_string(entry.path().join("stat"))
+            .ok()
+            .and_then(|stat| parse_stat_ppid(&stat))
+        else {
+            continue;
+        };
+        root_alive |= pid == root;
+        children.entry(parent).or_default().push(pid);
+    }
+    if !root_alive {
+        return Ok(Vec::new());
+    }
+
+    let mut tree = vec![root];
+    let mut idx = 0;
+    while let Some(&pid) = tree.get(idx) {
+        if let Some(kids) = children.get(&pid) {
+            tree.extend(kids);
+        }
+        idx += 1;
+    }
+    Ok(tree)
+}
+
+/// Родительский pid из `/proc/<pid>/stat`; имя процесса в скобках может содержать пробелы.
+fn parse_stat_ppid(stat: &str) -> Option<libc::pid_t> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{image_name, parse_stat_ppid};
+
+    #[test]
+    fn parses_ppid_with_spaces_in_comm() {
+        assert_eq!(
+            parse_stat_ppid("4242 (Game (x64).exe) S 4200 4200 1 0"),
+            Some(4200)
+        );
+        assert_eq!(parse_stat_ppid("garbage"), None);
+    }
+
+    #[test]
+    fn appends_exe_to_bare_image_names() {
+        assert_eq!(image_name("ModOrganizer"), "ModOrganizer.exe");
+        assert_eq!(image_name("Vortex.EXE"), "Vortex.EXE");
+    }
+}
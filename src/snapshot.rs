@@ -0,0 +1,357 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::{SnapshotCommands, fail},
+    output::print_json,
+    runtime::{format_duration_ago, unix_now_secs},
+    ui,
+    wine::{PrefixContext, locate_prefix},
+};
+
+/// Снимки лежат рядом с префиксом, чтобы reflink и снапшоты btrfs работали в пределах одной ФС.
+const SNAPSHOTS_DIR: &str = "protonhax_snapshots";
+const CREATED_AT_FILE: &str = "created_at";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct EntryInfo {
+    kind: EntryKind,
+    size: u64,
+    mtime: i64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Change<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+    Modified(&'a str),
+}
+
+pub fn handle_snapshot(phd: &Path, command: SnapshotCommands, json_output: bool) -> io::Result<()> {
+    match command {
+        SnapshotCommands::Create { appid, name } => {
+            let prefix = locate_prefix(phd, &appid)?;
+            let name = name.unwrap_or_else(|| unix_now_secs().to_string());
+            create_snapshot(&prefix, &name)
+        }
+        SnapshotCommands::List { appid } => {
+            list_snapshots(&locate_prefix(phd, &appid)?, json_output)
+        }
+        SnapshotCommands::Restore { appid, name, force } => {
+            restore_snapshot(&locate_prefix(phd, &appid)?, &name, force)
+        }
+        SnapshotCommands::Diff { appid, from, to } => {
+            diff_snapshots(&locate_prefix(phd, &appid)?, &from, to.as_deref())
+        }
+    }
+}
+
+fn snapshots_root(prefix: &PrefixContext) -> PathBuf {
+    prefix.compat_data().join(SNAPSHOTS_DIR)
+}
+
+fn snapshot_dir(prefix: &PrefixContext, name: &str) -> PathBuf {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        fail(&format!("Недопустимое имя снимка: \"{name}\""));
+    }
+    snapshots_root(prefix).join(name)
+}
+
+fn existing_snapshot_pfx(prefix: &PrefixContext, name: &str) -> PathBuf {
+    let pfx = snapshot_dir(prefix, name).join("pfx");
+    if !pfx.is_dir() {
+        fail(&format!(
+            "Снимок \"{name}\" не найден (см. `protonhax snapshot list`)"
+        ));
+    }
+    pfx
+}
+
+fn create_snapshot(prefix: &PrefixContext, name: &str) -> io::Result<()> {
+    let dir = snapshot_dir(prefix, name);
+    if dir.exists() {
+        fail(&format!("Снимок \"{name}\" уже существует"));
+    }
+    fs::create_dir_all(&dir)?;
+
+    let dest = dir.join("pfx");
+    let method = match copy_tree(&prefix.pfx, &dest) {
+        Ok(method) => method,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+    };
+    fs::write(dir.join(CREATED_AT_FILE), unix_now_secs().to_string())?;
+
+    println!(
+        "{} Снимок {} создан ({method})",
+        "OK".green().bold(),
+        name.green()
+    );
+    Ok(())
+}
+
+/// Копирует дерево: снапшот btrfs, если префикс — подтом, иначе `cp --reflink=auto`.
+/// Жёсткие ссылки не используются: Wine правит файлы на месте, и снимок бы менялся вместе с префиксом.
+fn copy_tree(src: &Path, dest: &Path) -> io::Result<&'static str> {
+    let is_subvolume = process::Command::new("btrfs")
+        .args(["subvolume", "show"])
+        .arg(src)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if is_subvolume {
+        let status = process::Command::new("btrfs")
+            .args(["subvolume", "snapshot"])
+            .arg(src)
+            .arg(dest)
+            .stdout(process::Stdio::null())
+            .status()?;
+        if status.success() {
+            return Ok("btrfs snapshot");
+        }
+    }
+
+    let status = process::Command::new("cp")
+        .args(["-a", "--reflink=auto"])
+        .arg(src)
+        .arg(dest)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "cp завершился с ошибкой при копировании {}",
+            src.display()
+        )));
+    }
+    Ok("reflink/copy")
+}
+
+fn remove_tree(path: &Path) -> io::Result<()> {
+    // Подтом btrfs удаляется своей командой; для обычных каталогов хватает remove_dir_all.
+    let removed = process::Command::new("btrfs")
+        .args(["subvolume", "delete"])
+        .arg(path)
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if removed {
+        return Ok(());
+    }
+    fs::remove_dir_all(path)
+}
+
+fn list_snapshots(prefix: &PrefixContext, json_output: bool) -> io::Result<()> {
+    let root = snapshots_root(prefix);
+    let mut snapshots: Vec<(String, Option<u64>)> = Vec::new();
+    for entry in fs::read_dir(&root).into_iter().flatten() {
+        let entry = entry?;
+        if !entry.path().join("pfx").is_dir() {
+            continue;
+        }
+        let created_at = fs::read_to_string(entry.path().join(CREATED_AT_FILE))
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        snapshots.push((entry.file_name().to_string_lossy().to_string(), created_at));
+    }
+    snapshots.sort_by_key(|(name, created_at)| (*created_at, name.clone()));
+
+    if json_output {
+        let data: Vec<_> = snapshots
+            .iter()
+            .map(|(name, created_at)| {
+                json!({
+                    "name": name,
+                    "path": root.join(name).join("pfx"),
+                    "created_at": created_at,
+                })
+            })
+            .collect();
+        return print_json(&json!({ "appid": prefix.appid, "snapshots": data }));
+    }
+
+    for (name, created_at) in snapshots {
+        match created_at {
+            Some(created_at) => println!(
+                "{}  {}",
+                name.green(),
+                format!("created {}", format_duration_ago(created_at)).dimmed()
+            ),
+            None => println!("{}", name.green()),
+        }
+    }
+    Ok(())
+}
+
+fn restore_snapshot(prefix: &PrefixContext, name: &str, force: bool) -> io::Result<()> {
+    let snapshot_pfx = existing_snapshot_pfx(prefix, name);
+    if prefix.is_live() && !force {
+        fail(
+            "Игра запущена: восстановление префикса под работающей игрой опасно (используйте --force)",
+        );
+    }
+    ui::confirm_or_abort(&format!(
+        "Заменить префикс {} снимком {name}? Текущее содержимое будет удалено",
+        prefix.pfx.display()
+    ));
+
+    let backup = prefix.pfx.with_extension("protonhax-old");
+    if backup.exists() {
+        remove_tree(&backup)?;
+    }
+    fs::rename(&prefix.pfx, &backup)?;
+    if let Err(err) = copy_tree(&snapshot_pfx, &prefix.pfx) {
+        // Возвращаем исходный префикс, если копирование не удалось.
+        let _ = fs::remove_dir_all(&prefix.pfx);
+        fs::rename(&backup, &prefix.pfx)?;
+        return Err(err);
+    }
+    remove_tree(&backup)?;
+
+    println!(
+        "{} Префикс восстановлен из снимка {}",
+        "OK".green().bold(),
+        name.green()
+    );
+    Ok(())
+}
+
+fn diff_snapshots(prefix: &PrefixContext, from: &str, to: Option<&str>) -> io::Result<()> {
+    let from_tree = scan_tree(&existing_snapshot_pfx(prefix, from))?;
+    let to_tree = match to {
+        Some(to) => scan_tree(&existing_snapshot_pfx(prefix, to))?,
+        None => scan_tree(&prefix.pfx)?,
+    };
+
+    for change in diff_trees(&from_tree, &to_tree) {
+        match change {
+            Change::Added(path) => println!("{} {path}", "+".green().bold()),
+            Change::Removed(path) => println!("{} {path}", "-".red().bold()),
+            Change::Modified(path) => println!("{} {path}", "M".yellow().bold()),
+        }
+    }
+    Ok(())
+}
+
+fn scan_tree(root: &Path) -> io::Result<BTreeMap<String, EntryInfo>> {
+    let mut entries = BTreeMap::new();
+    scan_dir(root, root, &mut entries)?;
+    Ok(entries)
+}
+
+fn scan_dir(root: &Path, dir: &Path, out: &mut BTreeMap<String, EntryInfo>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let meta = fs::symlink_metadata(&path)?;
+        let kind = if meta.file_type().is_symlink() {
+            EntryKind::Symlink
+        } else if meta.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        out.insert(
+            relative,
+            EntryInfo {
+                kind,
+                size: if kind == EntryKind::File {
+                    meta.len()
+                } else {
+                    0
+                },
+                mtime: if kind == EntryKind::File {
+                    meta.mtime()
+                } else {
+                    0
+                },
+            },
+        );
+
+        if kind == EntryKind::Dir {
+            scan_dir(root, &path, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn diff_trees<'a>(
+    from: &'a BTreeMap<String, EntryInfo>,
+    to: &'a BTreeMap<String, EntryInfo>,
+) -> Vec<Change<'a>> {
+    let mut changes: Vec<Change<'a>> = Vec::new();
+    for (path, info) in to {
+        match from.get(path) {
+            None => changes.push(Change::Added(path)),
+            Some(old) if old != info => changes.push(Change::Modified(path)),
+            Some(_) => {}
+        }
+    }
+    for path in from.keys() {
+        if !to.contains_key(path) {
+            changes.push(Change::Removed(path));
+        }
+    }
+
+    changes.sort_by_key(|change| match change {
+        Change::Added(path) | Change::Removed(path) | Change::Modified(path) => *path,
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Change, EntryInfo, EntryKind, diff_trees};
+
+    fn file(size: u64, mtime: i64) -> EntryInfo {
+        EntryInfo {
+            kind: EntryKind::File,
+            size,
+            mtime,
+        }
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_modified() {
+        let from = BTreeMap::from([
+            ("a.reg".to_string(), file(10, 1)),
+            ("b.dll".to_string(), file(5, 1)),
+        ]);
+        let to = BTreeMap::from([
+            ("a.reg".to_string(), file(12, 2)),
+            ("c.exe".to_string(), file(1, 3)),
+        ]);
+
+        assert_eq!(
+            diff_trees(&from, &to),
+            [
+                Change::Modified("a.reg"),
+                Change::Removed("b.dll"),
+                Change::Added("c.exe"),
+            ]
+        );
+    }
+}
@@ -1,36 +1,250 @@
-use std::{fs, path::Path};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-use crate::env_store::get_env_var;
+use crate::{
+    env_store::get_env_var,
+    names::{NameSource, lookup_app_name},
+    runtime::home_dir,
+    signals::process_alive,
+    vdf::{self, Vdf},
+};
 
-#[derive(Default)]
+/// Точка входа контейнера Steam Linux Runtime в команде запуска.
+const SLR_ENTRY_POINT: &str = "_v2-entry-point";
+/// Обёртки, которые пользователи ставят в параметры запуска без полного пути.
+const KNOWN_WRAPPERS: &[&str] = &[
+    "gamescope",
+    "gamemoderun",
+    "mangohud",
+    "obs-gamecapture",
+    "strangle",
+    "prime-run",
+    "game-performance",
+];
+
+/// Pid запущенного клиента Steam.
+const STEAM_PID_FILE: &str = ".steam/steam.pid";
+
+/// Типовые расположения корня Steam (нативный, симлинк, Flatpak).
+const STEAM_ROOT_CANDIDATES: &[&str] = &[
+    ".steam/steam",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+];
+
+pub const STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV: &str = "STEAM_COMPAT_CLIENT_INSTALL_PATH";
+pub const STEAM_GAME_ID_ENV: &str = "SteamGameId";
+
+/// Файл контекста с уже найденными метаданными (JSON), чтобы не разбирать манифест повторно.
+pub const META_FILE: &str = "meta";
+/// Настройки аккаунта в `userdata/<аккаунт>/config`, в том числе параметры запуска игр.
+pub const LOCAL_CONFIG_FILE: &str = "localconfig.vdf";
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct AppMeta {
     pub name: Option<String>,
     pub install_path: Option<String>,
+    /// Корень библиотеки Steam, где установлена игра.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    /// Сборка Proton на момент запуска (`Proton 9.0-4`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proton: Option<String>,
+    /// Обёртки вокруг игры снаружи внутрь: `gamescope`, `mangohud`, `reaper`, ...
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<String>,
+    /// Контейнер Steam Linux Runtime (`SteamLinuxRuntime_sniper`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Сборка игры в Steam (`buildid` манифеста).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_on_disk: Option<u64>,
+    /// Время последнего обновления игры (unix).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depots: Vec<InstalledDepot>,
+}
+
+/// Установленный депо из `InstalledDepots` манифеста.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InstalledDepot {
+    pub id: String,
+    pub manifest: Option<String>,
+    pub size: Option<u64>,
 }
 
 #[derive(Default)]
 struct ManifestInfo {
     name: Option<String>,
     installdir: Option<String>,
+    build_id: Option<String>,
+    size_on_disk: Option<u64>,
+    last_updated: Option<u64>,
+    depots: Vec<InstalledDepot>,
 }
 
-pub fn resolve_app_meta(app_dir: &Path, appid: &str) -> AppMeta {
-    let Ok(env_content) = fs::read_to_string(app_dir.join("env")) else {
-        return AppMeta::default();
+/// Метаданные контекста: кэш `init`, манифест и, если имени нет, база имён из `source`.
+pub fn resolve_app_meta(app_dir: &Path, appid: &str, source: NameSource) -> AppMeta {
+    let meta = match read_cached_meta(app_dir) {
+        Some(meta) if meta.name.is_some() => return meta,
+        // `init` сохраняет сведения о запуске и без имени: его дополняем из базы имён.
+        Some(meta) => meta,
+        None => manifest_meta_from_context(app_dir, appid),
     };
+    let meta = with_fallback_name(meta, appid, source);
+    // Без имени не кэшируем: сеть или манифест могут появиться позже.
+    if meta.name.is_some() {
+        let _ = write_cached_meta(app_dir, &meta);
+    }
+    meta
+}
 
-    let Some(compat_data) = get_env_var(&env_content, "STEAM_COMPAT_DATA_PATH") else {
-        return AppMeta::default();
+/// Сохраняет в контекст при `init` метаданные из манифеста (без обращения к сети) и
+/// сведения о запуске: сборку Proton, обёртки и контейнер. Не записался кэш — не беда:
+/// `ls` прочитает манифест сам.
+pub fn cache_init_meta(
+    app_dir: &Path,
+    appid: &str,
+    proton: Option<String>,
+    chain: LaunchChain,
+) -> AppMeta {
+    let meta = AppMeta {
+        proton,
+        wrappers: chain.wrappers,
+        container: chain.container,
+        ..manifest_meta_from_context(app_dir, appid)
     };
+    let _ = write_cached_meta(app_dir, &meta);
+    meta
+}
 
-    let Some(steamapps_path) = steamapps_path_from_compat(&compat_data) else {
-        return AppMeta::default();
-    };
+/// Что стоит в команде запуска Steam перед скриптом proton.
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct LaunchChain {
+    pub wrappers: Vec<String>,
+    pub container: Option<String>,
+}
 
+impl LaunchChain {
+    /// Программы команды до `proton`: первая, каждая после `--`, известные обёртки
+    /// и абсолютные пути (так Steam передаёт `reaper` и `steam-launch-wrapper`).
+    /// Обёртки до `protonhax init` в команду не попадают — их видно по окружению.
+    pub fn parse(cmd: &[String], env_content: &str) -> Self {
+        let mut chain = Self::default();
+        let outer = [
+            ("gamescope", "GAMESCOPE_WAYLAND_DISPLAY"),
+            ("mangohud", "MANGOHUD"),
+        ];
+        for (wrapper, var) in outer {
+            if get_env_var(env_content, var).is_some_and(|value| !value.is_empty() && value != "0")
+            {
+                chain.wrappers.push(wrapper.to_string());
+            }
+        }
+        if get_env_var(env_content, "LD_PRELOAD")
+            .is_some_and(|preload| preload.contains("libgamemodeauto"))
+        {
+            chain.wrappers.push("gamemode".to_string());
+        }
+
+        let mut expect_program = true;
+        for arg in cmd.iter().take_while(|arg| !arg.contains("/proton")) {
+            if arg == "--" {
+                expect_program = true;
+                continue;
+            }
+            let path = Path::new(arg);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let is_program = expect_program
+                || KNOWN_WRAPPERS.contains(&name.as_str())
+                || (path.is_absolute() && !arg.starts_with('-'));
+            expect_program = false;
+            if !is_program {
+                continue;
+            }
+            if name == SLR_ENTRY_POINT {
+                chain.container = path
+                    .parent()
+                    .and_then(Path::file_name)
+                    .map(|dir| dir.to_string_lossy().into_owned());
+            } else if !chain.wrappers.contains(&name) {
+                chain.wrappers.push(name);
+            }
+        }
+        chain
+    }
+}
+
+pub fn read_cached_meta(app_dir: &Path) -> Option<AppMeta> {
+    let content = fs::read_to_string(app_dir.join(META_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn write_cached_meta(app_dir: &Path, meta: &AppMeta) -> io::Result<()> {
+    let serialized = serde_json::to_string(meta).map_err(io::Error::other)?;
+    fs::write(app_dir.join(META_FILE), serialized)
+}
+
+/// Манифест обычно лежит в той же библиотеке, что и compatdata, но Steam может держать
+/// compatdata в другой: тогда ищем во всех библиотеках из `libraryfolders.vdf`.
+fn manifest_meta_from_context(app_dir: &Path, appid: &str) -> AppMeta {
+    let compat_data = fs::read_to_string(app_dir.join("env"))
+        .ok()
+        .and_then(|env_content| get_env_var(&env_content, "STEAM_COMPAT_DATA_PATH"));
+    let adjacent = compat_data
+        .as_deref()
+        .and_then(steamapps_path_from_compat)
+        .and_then(|steamapps| meta_from_steamapps(steamapps, appid));
+    adjacent
+        .or_else(|| meta_from_libraries(appid))
+        .unwrap_or_default()
+}
+
+fn meta_from_libraries(appid: &str) -> Option<AppMeta> {
+    steamapps_dirs()
+        .iter()
+        .find_map(|steamapps| meta_from_steamapps(steamapps, appid))
+}
+
+/// Метаданные приложения по манифесту из любой библиотеки Steam (без активного контекста).
+/// Нужен обычно каталог установки, поэтому имя ищется без сети.
+pub fn find_app_meta(appid: &str) -> AppMeta {
+    with_fallback_name(
+        meta_from_libraries(appid).unwrap_or_default(),
+        appid,
+        NameSource::Local,
+    )
+}
+
+/// Без манифеста (ярлыки, другие библиотеки, удалённые игры) имя берём из базы имён.
+fn with_fallback_name(mut meta: AppMeta, appid: &str, source: NameSource) -> AppMeta {
+    if meta.name.is_none() {
+        meta.name = lookup_app_name(appid, source);
+    }
+    meta
+}
+
+/// Каталог кэша шейдеров приложения (`steamapps/shadercache/<appid>`).
+pub fn find_shader_cache(appid: &str) -> Option<PathBuf> {
+    steamapps_dirs()
+        .into_iter()
+        .map(|steamapps| steamapps.join("shadercache").join(appid))
+        .find(|path| path.is_dir())
+}
+
+fn meta_from_steamapps(steamapps_path: &Path, appid: &str) -> Option<AppMeta> {
     let manifest_path = steamapps_path.join(format!("appmanifest_{appid}.acf"));
-    let Ok(manifest_content) = fs::read_to_string(manifest_path) else {
-        return AppMeta::default();
-    };
+    let manifest_content = fs::read_to_string(manifest_path).ok()?;
 
     let manifest = parse_manifest_info(&manifest_content);
     let install_path = manifest.installdir.map(|dir| {
@@ -41,53 +255,231 @@ pub fn resolve_app_meta(app_dir: &Path, appid: &str) -> AppMeta {
             .into_owned()
     });
 
-    AppMeta {
+    Some(AppMeta {
         name: manifest.name,
         install_path,
+        library: steamapps_path
+            .parent()
+            .map(|library| library.to_string_lossy().into_owned()),
+        build_id: manifest.build_id,
+        size_on_disk: manifest.size_on_disk,
+        last_updated: manifest.last_updated,
+        depots: manifest.depots,
+        ..AppMeta::default()
+    })
+}
+
+/// Существующие корни Steam без дубликатов (симлинки раскрываются).
+pub fn steam_roots() -> Vec<PathBuf> {
+    let home = home_dir();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for candidate in STEAM_ROOT_CANDIDATES {
+        let Ok(root) = fs::canonicalize(home.join(candidate)) else {
+            continue;
+        };
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
     }
+    roots
 }
 
-fn steamapps_path_from_compat(compat_data: &str) -> Option<&Path> {
-    Path::new(compat_data).parent()?.parent()
+/// Запущен ли клиент Steam. Он держит `shortcuts.vdf` и `localconfig.vdf` в памяти и при
+/// выходе перезаписывает их своей копией, поэтому править их можно только при закрытом.
+pub fn steam_running() -> bool {
+    fs::read_to_string(home_dir().join(STEAM_PID_FILE))
+        .ok()
+        .and_then(|pid| pid.trim().parse().ok())
+        .is_some_and(process_alive)
 }
 
-fn parse_manifest_info(content: &str) -> ManifestInfo {
-    let mut info = ManifestInfo::default();
+/// `userdata/<аккаунт>/config` аккаунта, входившего последним (по `localconfig.vdf`):
+/// там лежат параметры запуска и ярлыки сторонних приложений.
+pub fn user_config_dir() -> Option<PathBuf> {
+    steam_roots()
+        .into_iter()
+        .flat_map(|root| fs::read_dir(root.join("userdata")).into_iter().flatten())
+        .flatten()
+        .filter(|account| {
+            account
+                .file_name()
+                .to_str()
+                .is_some_and(|id| id != "0" && id.bytes().all(|byte| byte.is_ascii_digit()))
+        })
+        .map(|account| account.path().join("config"))
+        .max_by_key(|config| {
+            fs::metadata(config.join(LOCAL_CONFIG_FILE))
+                .and_then(|meta| meta.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        })
+}
 
-    for line in content.lines() {
-        let Some((key, value)) = parse_acf_line(line) else {
+/// Установленные приложения всех библиотек: appid и название из `appmanifest_*.acf`.
+pub fn installed_apps() -> Vec<(String, Option<String>)> {
+    let mut apps: Vec<(String, Option<String>)> = Vec::new();
+    for steamapps in steamapps_dirs() {
+        let Ok(entries) = fs::read_dir(&steamapps) else {
             continue;
         };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(appid) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix("appmanifest_"))
+                .and_then(|name| name.strip_suffix(".acf"))
+            else {
+                continue;
+            };
+            if apps.iter().any(|(known, _)| known == appid) {
+                continue;
+            }
+            let name = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|content| parse_manifest_info(&content).name);
+            apps.push((appid.to_string(), name));
+        }
+    }
+    apps.sort_by(|left, right| left.1.cmp(&right.1));
+    apps
+}
 
-        match key {
-            "name" => info.name = Some(value.to_string()),
-            "installdir" => info.installdir = Some(value.to_string()),
-            _ => {}
+/// Все каталоги `steamapps` из всех библиотек Steam (по `libraryfolders.vdf`).
+pub fn steamapps_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut push_dir = |dir: PathBuf| {
+        let dir = fs::canonicalize(&dir).unwrap_or(dir);
+        if dir.is_dir() && !dirs.contains(&dir) {
+            dirs.push(dir);
         }
+    };
 
-        if info.name.is_some() && info.installdir.is_some() {
-            break;
+    for root in steam_roots() {
+        let steamapps = root.join("steamapps");
+        if let Ok(content) = fs::read_to_string(steamapps.join("libraryfolders.vdf")) {
+            for library in parse_library_paths(&content) {
+                push_dir(PathBuf::from(library).join("steamapps"));
+            }
         }
+        push_dir(steamapps);
     }
+    dirs
+}
 
-    info
+/// Ищет `compatdata/<appid>` во всех библиотеках Steam.
+pub fn find_compat_data(appid: &str) -> Option<PathBuf> {
+    steamapps_dirs()
+        .into_iter()
+        .map(|steamapps| steamapps.join("compatdata").join(appid))
+        .find(|path| path.join("pfx").is_dir())
 }
 
-fn parse_acf_line(line: &str) -> Option<(&str, &str)> {
-    let mut tokens = line
-        .trim()
-        .split('"')
-        .map(str::trim)
-        .filter(|token| !token.is_empty());
+/// Каталог Proton из `compatdata/<appid>/config_info`: вторая строка указывает
+/// внутрь `<proton>/files/...` (или `dist/...` у старых версий).
+pub fn proton_dir_from_config_info(content: &str) -> Option<PathBuf> {
+    let path = Path::new(content.lines().nth(1)?.trim());
+    path.ancestors()
+        .find(|ancestor| {
+            ancestor
+                .file_name()
+                .is_some_and(|name| name == "files" || name == "dist")
+        })?
+        .parent()
+        .map(Path::to_path_buf)
+}
 
-    let key = tokens.next()?;
-    let value = tokens.next()?;
-    Some((key, value))
+fn parse_library_paths(content: &str) -> Vec<String> {
+    let Some(vdf) = vdf::parse(content) else {
+        return Vec::new();
+    };
+    vdf.get("libraryfolders")
+        .map(Vdf::entries)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(_, library)| library.get("path")?.as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+fn steamapps_path_from_compat(compat_data: &str) -> Option<&Path> {
+    Path::new(compat_data).parent()?.parent()
+}
+
+fn parse_manifest_info(content: &str) -> ManifestInfo {
+    let Some(vdf) = vdf::parse(content) else {
+        return ManifestInfo::default();
+    };
+    let Some(state) = vdf.get("AppState") else {
+        return ManifestInfo::default();
+    };
+    let text = |value: &Vdf, key: &str| value.get(key)?.as_str().map(str::to_string);
+    let number = |value: &Vdf, key: &str| value.get(key)?.as_str()?.parse().ok();
+    let depots = state
+        .get("InstalledDepots")
+        .map(Vdf::entries)
+        .unwrap_or_default()
+        .iter()
+        .map(|(id, depot)| InstalledDepot {
+            id: id.clone(),
+            manifest: text(depot, "manifest"),
+            size: number(depot, "size"),
+        })
+        .collect();
+
+    ManifestInfo {
+        name: text(state, "name"),
+        installdir: text(state, "installdir"),
+        build_id: text(state, "buildid"),
+        size_on_disk: number(state, "SizeOnDisk"),
+        last_updated: number(state, "LastUpdated"),
+        depots,
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_manifest_info;
+    use std::path::PathBuf;
+
+    use super::{
+        LaunchChain, parse_library_paths, parse_manifest_info, proton_dir_from_config_info,
+    };
+
+    #[test]
+    fn parses_launch_chain() {
+        let cmd: Vec<String> = [
+            "/home/u/.steam/steam/ubuntu12_32/reaper",
+            "SteamLaunch",
+            "AppId=620",
+            "--",
+            "/home/u/.steam/steam/ubuntu12_32/steam-launch-wrapper",
+            "--",
+            "gamemoderun",
+            "mangohud",
+            "/home/u/.steam/steam/steamapps/common/SteamLinuxRuntime_sniper/_v2-entry-point",
+            "--verb=waitforexitandrun",
+            "--",
+            "/home/u/.steam/steam/steamapps/common/Proton 9.0/proton",
+            "waitforexitandrun",
+            "/games/Portal 2/portal2.exe",
+        ]
+        .map(String::from)
+        .to_vec();
+        let env = "declare -x GAMESCOPE_WAYLAND_DISPLAY=gamescope-0\n";
+        assert_eq!(
+            LaunchChain::parse(&cmd, env),
+            LaunchChain {
+                wrappers: [
+                    "gamescope",
+                    "reaper",
+                    "steam-launch-wrapper",
+                    "gamemoderun",
+                    "mangohud"
+                ]
+                .map(String::from)
+                .to_vec(),
+                container: Some("SteamLinuxRuntime_sniper".to_string()),
+            }
+        );
+    }
 
     #[test]
     fn parses_manifest_fields() {
@@ -97,11 +489,63 @@ mod tests {
                 "appid"      "1217060"
                 "name"       "Gunfire Reborn"
                 "installdir" "Gunfire Reborn"
+                "buildid"    "14523188"
+                "LastUpdated" "1718900000"
+                "SizeOnDisk" "7516192768"
+                "InstalledDepots"
+                {
+                    "1217061"
+                    {
+                        "manifest" "3125553491871537216"
+                        "size"     "7516192768"
+                    }
+                }
             }
         "#;
 
         let info = parse_manifest_info(manifest);
         assert_eq!(info.name.as_deref(), Some("Gunfire Reborn"));
         assert_eq!(info.installdir.as_deref(), Some("Gunfire Reborn"));
+        assert_eq!(info.build_id.as_deref(), Some("14523188"));
+        assert_eq!(info.size_on_disk, Some(7_516_192_768));
+        assert_eq!(info.last_updated, Some(1_718_900_000));
+        assert_eq!(info.depots.len(), 1);
+        assert_eq!(info.depots[0].id, "1217061");
+        assert_eq!(info.depots[0].size, Some(7_516_192_768));
+    }
+
+    #[test]
+    fn parses_library_folder_paths() {
+        let vdf = r#"
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"      "/home/user/.local/share/Steam"
+                    "apps" { "228980" "0" }
+                }
+                "1"
+                {
+                    "path"      "/mnt/games/SteamLibrary"
+                }
+            }
+        "#;
+
+        assert_eq!(
+            parse_library_paths(vdf),
+            ["/home/user/.local/share/Steam", "/mnt/games/SteamLibrary"]
+        );
+    }
+
+    #[test]
+    fn proton_dir_from_config_info_line() {
+        let config_info =
+            "9.0-4\n/home/u/.steam/steam/steamapps/common/Proton 9.0 (Beta)/files/share/fonts/\n";
+        assert_eq!(
+            proton_dir_from_config_info(config_info),
+            Some(PathBuf::from(
+                "/home/u/.steam/steam/steamapps/common/Proton 9.0 (Beta)"
+            ))
+        );
     }
 }
@@ -0,0 +1,304 @@
+use colored::Colorize;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::{ShortcutCommands, fail},
+    runtime::unix_now_secs,
+    steam::{steam_running, user_config_dir},
+    verbosity,
+};
+
+const SHORTCUTS_FILE: &str = "shortcuts.vdf";
+
+const MAP: u8 = 0x00;
+const STRING: u8 = 0x01;
+const INT32: u8 = 0x02;
+const MAP_END: u8 = 0x08;
+
+/// Значение бинарного `KeyValues` из `shortcuts.vdf`; строки хранятся байтами, чтобы
+/// чужие записи в любой кодировке переписались без изменений.
+#[derive(Debug, PartialEq, Eq)]
+enum Value {
+    Map(Vec<(String, Value)>),
+    String(Vec<u8>),
+    Int32(u32),
+}
+
+pub fn handle_shortcut(command: ShortcutCommands) -> io::Result<()> {
+    match command {
+        ShortcutCommands::Add { appid, name, cmd } => handle_shortcut_add(&appid, &name, &cmd),
+    }
+}
+
+/// Добавляет в Steam ярлык стороннего приложения, который запускает `protonhax run`:
+/// так инструменты доступны из Big Picture и игрового режима Steam Deck.
+fn handle_shortcut_add(selector: &str, name: &str, cmd: &[String]) -> io::Result<()> {
+    // Steam держит ярлыки в памяти и при выходе перезапишет файл своей копией.
+    if steam_running() {
+        fail("Steam запущен: закройте его, иначе он перезапишет shortcuts.vdf");
+    }
+    let path = shortcuts_path();
+    verbosity::verbose(format_args!("ярлыки Steam: {}", path.display()));
+
+    let mut root = match fs::read(&path) {
+        Ok(data) => match parse(&data) {
+            Some(root) => root,
+            None => fail(&format!(
+                "Не удалось разобрать {}: файл не изменён",
+                path.display()
+            )),
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            vec![("shortcuts".to_string(), Value::Map(Vec::new()))]
+        }
+        Err(err) => return Err(err),
+    };
+    let Some(Value::Map(shortcuts)) = root
+        .iter_mut()
+        .find(|(key, _)| key.eq_ignore_ascii_case("shortcuts"))
+        .map(|(_, value)| value)
+    else {
+        fail(&format!("В {} нет блока shortcuts", path.display()));
+    };
+
+    let exe = env::current_exe()?;
+    let launch_options = shell_words::join(
+        ["run", selector]
+            .into_iter()
+            .chain(cmd.iter().map(String::as_str)),
+    );
+    let entry = shortcut_entry(name, &exe, &launch_options);
+    // Повторное добавление того же имени обновляет ярлык, а не плодит копии; чужой ярлык
+    // с тем же именем не трогаем.
+    let existing = shortcuts
+        .iter_mut()
+        .find(|(_, shortcut)| string_field(shortcut, "AppName") == Some(name.as_bytes()));
+    let replaced = if let Some((_, shortcut)) = existing {
+        if !runs_protonhax(shortcut, &exe) {
+            fail(&format!(
+                "Ярлык «{name}» уже есть и запускает не protonhax: выберите другое --name"
+            ));
+        }
+        *shortcut = entry;
+        true
+    } else {
+        let index = shortcuts
+            .iter()
+            .filter_map(|(key, _)| key.parse::<usize>().ok())
+            .max()
+            .map_or(0, |max| max + 1);
+        shortcuts.push((index.to_string(), entry));
+        false
+    };
+
+    if path.exists() {
+        let backup = path.with_extension(format!("vdf.{}.bak", unix_now_secs()));
+        fs::copy(&path, &backup)?;
+        verbosity::info(format_args!("копия: {}", backup.display()));
+    } else if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("vdf.tmp");
+    fs::write(&tmp_path, serialize(&root))?;
+    fs::rename(tmp_path, &path)?;
+
+    let action = if replaced {
+        "обновлён"
+    } else {
+        "добавлен"
+    };
+    println!(
+        "Ярлык {} {action}: protonhax {launch_options}",
+        name.green()
+    );
+    Ok(())
+}
+
+/// Ярлык запускает protonhax: этот же файл или другую его копию.
+fn runs_protonhax(shortcut: &Value, exe: &Path) -> bool {
+    let Some(target) = string_field(shortcut, "Exe") else {
+        return false;
+    };
+    let target = String::from_utf8_lossy(target);
+    let target = Path::new(target.trim_matches('"'));
+    target == exe || target.file_name() == exe.file_name()
+}
+
+fn shortcuts_path() -> PathBuf {
+    match user_config_dir() {
+        Some(config) => config.join(SHORTCUTS_FILE),
+        None => fail("Не найден каталог userdata Steam: войдите в Steam хотя бы раз"),
+    }
+}
+
+fn shortcut_entry(name: &str, exe: &Path, launch_options: &str) -> Value {
+    let quoted_exe = format!("\"{}\"", exe.display());
+    let start_dir = exe
+        .parent()
+        .map_or_else(String::new, |dir| format!("\"{}\"", dir.display()));
+    let string = |value: &str| Value::String(value.as_bytes().to_vec());
+    Value::Map(vec![
+        (
+            "appid".into(),
+            Value::Int32(shortcut_appid(&quoted_exe, name)),
+        ),
+        ("AppName".into(), string(name)),
+        ("Exe".into(), string(&quoted_exe)),
+        ("StartDir".into(), string(&start_dir)),
+        ("icon".into(), string("")),
+        ("ShortcutPath".into(), string("")),
+        ("LaunchOptions".into(), string(launch_options)),
+        ("IsHidden".into(), Value::Int32(0)),
+        ("AllowDesktopConfig".into(), Value::Int32(1)),
+        ("AllowOverlay".into(), Value::Int32(1)),
+        ("OpenVR".into(), Value::Int32(0)),
+        ("Devkit".into(), Value::Int32(0)),
+        ("DevkitGameID".into(), string("")),
+        ("DevkitOverrideAppID".into(), Value::Int32(0)),
+        ("LastPlayTime".into(), Value::Int32(0)),
+        ("FlatpakAppID".into(), string("")),
+        ("tags".into(), Value::Map(Vec::new())),
+    ])
+}
+
+/// appid ярлыка так же, как его считает Steam: CRC32 от `Exe` и имени со старшим битом.
+fn shortcut_appid(exe: &str, name: &str) -> u32 {
+    crc32(format!("{exe}{name}").as_bytes()) | 0x8000_0000
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn string_field<'a>(value: &'a Value, key: &str) -> Option<&'a [u8]> {
+    let Value::Map(entries) = value else {
+        return None;
+    };
+    entries
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .and_then(|(_, value)| match value {
+            Value::String(bytes) => Some(bytes.as_slice()),
+            _ => None,
+        })
+}
+
+/// Разбирает файл целиком; `None` — обрыв или тип, который мы не сумели бы записать
+/// обратно без потерь.
+fn parse(data: &[u8]) -> Option<Vec<(String, Value)>> {
+    let mut pos = 0;
+    let entries = parse_map(data, &mut pos)?;
+    (pos == data.len()).then_some(entries)
+}
+
+fn parse_map(data: &[u8], pos: &mut usize) -> Option<Vec<(String, Value)>> {
+    let mut entries = Vec::new();
+    loop {
+        let kind = *data.get(*pos)?;
+        *pos += 1;
+        if kind == MAP_END {
+            return Some(entries);
+        }
+        let key = String::from_utf8(read_cstr(data, pos)?.to_vec()).ok()?;
+        let value = match kind {
+            MAP => Value::Map(parse_map(data, pos)?),
+            STRING => Value::String(read_cstr(data, pos)?.to_vec()),
+            INT32 => {
+                let bytes = data.get(*pos..*pos + 4)?;
+                *pos += 4;
+                Value::Int32(u32::from_le_bytes(bytes.try_into().ok()?))
+            }
+            _ => return None,
+        };
+        entries.push((key, value));
+    }
+}
+
+fn read_cstr<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let rest = data.get(*pos..)?;
+    let len = rest.iter().position(|&byte| byte == 0)?;
+    *pos += len + 1;
+    Some(&rest[..len])
+}
+
+fn serialize(entries: &[(String, Value)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_map(entries, &mut out);
+    out
+}
+
+fn write_map(entries: &[(String, Value)], out: &mut Vec<u8>) {
+    for (key, value) in entries {
+        let kind = match value {
+            Value::Map(_) => MAP,
+            Value::String(_) => STRING,
+            Value::Int32(_) => INT32,
+        };
+        out.push(kind);
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        match value {
+            Value::Map(entries) => write_map(entries, out),
+            Value::String(bytes) => {
+                out.extend_from_slice(bytes);
+                out.push(0);
+            }
+            Value::Int32(number) => out.extend_from_slice(&number.to_le_bytes()),
+        }
+    }
+    out.push(MAP_END);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Value, crc32, parse, runs_protonhax, serialize, shortcut_entry};
+    use std::path::Path;
+
+    #[test]
+    fn round_trips_shortcuts_file() {
+        let root = vec![(
+            "shortcuts".to_string(),
+            Value::Map(vec![(
+                "0".to_string(),
+                Value::Map(vec![
+                    ("appid".to_string(), Value::Int32(0x8000_1234)),
+                    ("AppName".to_string(), Value::String(b"MO2 \xff".to_vec())),
+                    ("tags".to_string(), Value::Map(Vec::new())),
+                ]),
+            )]),
+        )];
+        let data = serialize(&root);
+        assert_eq!(&data[..11], b"\x00shortcuts\x00");
+        assert_eq!(&data[data.len() - 3..], b"\x08\x08\x08");
+        assert_eq!(parse(&data), Some(root));
+        assert_eq!(parse(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn recognizes_own_shortcuts() {
+        let exe = Path::new("/usr/bin/protonhax");
+        let own = shortcut_entry("MO2", Path::new("/home/user/.cargo/bin/protonhax"), "run 1");
+        assert!(runs_protonhax(&own, exe));
+        let foreign = shortcut_entry("MO2", Path::new("/opt/mo2/ModOrganizer"), "");
+        assert!(!runs_protonhax(&foreign, exe));
+    }
+
+    #[test]
+    fn computes_ieee_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,255 @@
+use colored::Colorize;
+use serde_json::json;
+use std::{io, path::Path, process};
+
+use crate::{
+    cli::{ToolCommands, fail},
+    companions::handle_tool_detect,
+    config::{Config, ToolConfig},
+    context::{TargetApp, prepare_context},
+    output::print_json,
+    runtime::exit_with_status,
+    scope::{ResourceLimits, command_in_scope, scope_unit_name},
+};
+
+pub fn handle_tool(phd: &Path, command: ToolCommands, json_output: bool) -> io::Result<()> {
+    match command {
+        ToolCommands::Add {
+            name,
+            path,
+            args,
+            native,
+            scope,
+        } => handle_tool_add(
+            &name,
+            &ToolConfig {
+                path,
+                args,
+                native,
+                scope,
+            },
+        ),
+        ToolCommands::Detect { appid } => handle_tool_detect(phd, &appid, json_output),
+        ToolCommands::List => handle_tool_list(json_output),
+        ToolCommands::Run {
+            appid,
+            name,
+            scope,
+            args,
+        } => run_tool(phd, &appid, name.as_deref(), &args, scope),
+    }
+}
+
+fn handle_tool_add(name: &str, tool: &ToolConfig) -> io::Result<()> {
+    let replaced = Config::load()?.tools.contains_key(name);
+    Config::save_tools([(name, tool)])?;
+
+    let action = if replaced {
+        "обновлён"
+    } else {
+        "добавлен"
+    };
+    println!("Инструмент {} {action}", name.green());
+    Ok(())
+}
+
+fn handle_tool_list(json_output: bool) -> io::Result<()> {
+    let config = Config::load()?;
+    if json_output {
+        let tools: Vec<_> = config
+            .tools
+            .iter()
+            .map(|(name, tool)| {
+                json!({
+                    "name": name,
+                    "path": tool.path,
+                    "args": tool.args,
+                    "native": tool.native,
+                    "scope": tool.scope,
+                })
+            })
+            .collect();
+        return print_json(&tools);
+    }
+    for (name, tool) in &config.tools {
+        let mut parts = vec![name.green().to_string(), tool.path.yellow().to_string()];
+        if !tool.args.is_empty() {
+            parts.push(shell_words::join(&tool.args).dimmed().to_string());
+        }
+        if tool.native {
+            parts.push("native".cyan().to_string());
+        }
+        if tool.scope {
+            parts.push("scope".cyan().to_string());
+        }
+        println!("{}", parts.join("  "));
+    }
+    Ok(())
+}
+
+/// Запускает зарегистрированный инструмент в контексте `selector` и завершает процесс.
+pub fn run_tool(
+    phd: &Path,
+    selector: &str,
+    name: Option<&str>,
+    extra_args: &[String],
+    scope: bool,
+) -> io::Result<()> {
+    let config = Config::load()?;
+    let target = prepare_context(phd, selector)?;
+    let default_tool = config
+        .apps
+        .get(&target.appid)
+        .and_then(|app| app.tool.as_deref());
+    let Some(name) = name.or(default_tool) else {
+        fail(&format!(
+            "Не указан инструмент, и для {} нет `tool` в [apps.{}] конфига",
+            target.appid, target.appid
+        ));
+    };
+    let Some(tool) = config.tools.get(name) else {
+        eprintln!(
+            "{} Инструмент \"{name}\" не зарегистрирован (см. `protonhax tool list`).",
+            "Ошибка:".bold().red()
+        );
+        process::exit(2);
+    };
+
+    let vars = TemplateVars::resolve(&target);
+    let path = expand_or_exit(&tool.path, &vars);
+    let mut args = Vec::with_capacity(tool.args.len() + extra_args.len());
+    for arg in &tool.args {
+        args.push(expand_or_exit(arg, &vars));
+    }
+    args.extend_from_slice(extra_args);
+
+    let (program, leading_args) = if tool.native {
+        (path, Vec::new())
+    } else {
+        (target.exe()?, vec!["run".to_string(), path])
+    };
+    let mut cmd = if scope || tool.scope {
+        let unit = scope_unit_name(&target.appid, name, process::id());
+        command_in_scope(&unit, &ResourceLimits::default(), program)
+    } else {
+        process::Command::new(program)
+    };
+    let status = cmd.args(leading_args).args(args).status()?;
+    exit_with_status(status);
+}
+
+/// Значения для подстановки `{...}` в путь и аргументы инструмента.
+struct TemplateVars {
+    appid: String,
+    name: Option<String>,
+    install_dir: Option<String>,
+    pfx: Option<String>,
+}
+
+impl TemplateVars {
+    fn resolve(target: &TargetApp) -> Self {
+        let meta = target.meta();
+        Self {
+            appid: target.appid.clone(),
+            name: meta.name,
+            install_dir: meta.install_path,
+            pfx: target.pfx().ok(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, ()> {
+        match key {
+            "appid" => Ok(Some(self.appid.clone())),
+            "name" => Ok(self.name.clone()),
+            "install_dir" => Ok(self.install_dir.clone()),
+            "pfx" => Ok(self.pfx.clone()),
+            "drive_c" => Ok(self.pfx.as_ref().map(|pfx| format!("{pfx}/drive_c"))),
+            _ => Err(()),
+        }
+    }
+}
+
+fn expand_or_exit(template: &str, vars: &TemplateVars) -> String {
+    expand_template(template, |key| vars.get(key)).unwrap_or_else(|message| {
+        eprintln!("{} {message}", "Ошибка:".bold().red());
+        process::exit(2);
+    })
+}
+
+/// Подставляет значения вместо `{key}`; `{{` и `}}` дают литеральные скобки.
+fn expand_template<F>(template: &str, lookup: F) -> Result<String, String>
+where
+    F: Fn(&str) -> Result<Option<String>, ()>,
+{
+    let mut res = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(idx) = rest.find(['{', '}']) {
+        res.push_str(&rest[..idx]);
+        let tail = &rest[idx..];
+
+        if let Some(after) = tail.strip_prefix("{{") {
+            res.push('{');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = tail.strip_prefix("}}") {
+            res.push('}');
+            rest = after;
+            continue;
+        }
+        if tail.starts_with('}') {
+            return Err(format!("Непарная `}}` в шаблоне \"{template}\""));
+        }
+
+        let Some(end) = tail.find('}') else {
+            return Err(format!("Незакрытая `{{` в шаблоне \"{template}\""));
+        };
+        let key = &tail[1..end];
+        match lookup(key) {
+            Ok(Some(value)) => res.push_str(&value),
+            Ok(None) => {
+                return Err(format!(
+                    "Значение {{{key}}} неизвестно для этого контекста (шаблон \"{template}\")"
+                ));
+            }
+            Err(()) => return Err(format!("Неизвестная переменная {{{key}}} в \"{template}\"")),
+        }
+        rest = &tail[end + 1..];
+    }
+
+    res.push_str(rest);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_template;
+
+    fn lookup(key: &str) -> Result<Option<String>, ()> {
+        match key {
+            "install_dir" => Ok(Some("/games/Skyrim".to_string())),
+            "name" => Ok(None),
+            _ => Err(()),
+        }
+    }
+
+    #[test]
+    fn expands_known_placeholders() {
+        assert_eq!(
+            expand_template("{install_dir}/skse64_loader.exe", lookup).as_deref(),
+            Ok("/games/Skyrim/skse64_loader.exe")
+        );
+        assert_eq!(
+            expand_template("{{literal}}", lookup).as_deref(),
+            Ok("{literal}")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_or_missing_placeholders() {
+        assert!(expand_template("{nope}", lookup).is_err());
+        assert!(expand_template("{name}", lookup).is_err());
+        assert!(expand_template("{install_dir", lookup).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use std::{ffi::OsString, fs, io, path::PathBuf};
+
+use crate::{
+    config::Config,
+    logs::prune_logs,
+    runtime::{log_dir, unix_now_secs},
+};
+
+pub const STRACE: &str = "strace";
+
+/// Путь к трассе в каталоге логов игры и аргументы strace: `-f` для потомков Wine,
+/// метки времени и фильтр `-e` (например, `trace=file` или просто `file`), если он не пуст.
+pub fn strace_args(appid: &str, filter: &str) -> io::Result<(PathBuf, Vec<OsString>)> {
+    prune_logs(&Config::load()?.logs);
+    let dir = log_dir(appid);
+    fs::create_dir_all(&dir)?;
+    let log = dir.join(format!("strace-{}.log", unix_now_secs()));
+
+    let mut args: Vec<OsString> = ["-f", "-tt", "-s", "256", "-o"]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    args.push(log.clone().into_os_string());
+    if !filter.is_empty() {
+        args.push("-e".into());
+        args.push(filter.into());
+    }
+    Ok((log, args))
+}
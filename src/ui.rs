@@ -0,0 +1,254 @@
+use colored::Colorize;
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    os::fd::{AsRawFd, RawFd},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::cli::fail;
+
+/// `-y/--yes`: задаётся один раз в `main`.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+/// Подтверждение необратимого действия. С `--yes` не спрашивает; без терминала
+/// отказывает: скрипт должен согласиться явно.
+pub fn confirm(question: &str) -> bool {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return true;
+    }
+    if !io::stdin().is_terminal() {
+        fail(&format!(
+            "Нужно подтверждение, но stdin не терминал (добавьте --yes): {question}"
+        ));
+    }
+    eprint!("{} {question} [y/N] ", "?".yellow().bold());
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    is_yes(&answer)
+}
+
+/// Можно ли спросить: есть `--yes` или терминал. Необязательные предложения без этого
+/// лучше пропустить, чем прерывать команду.
+pub fn can_confirm() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed) || io::stdin().is_terminal()
+}
+
+/// Прерывает команду, если пользователь не согласился.
+pub fn confirm_or_abort(question: &str) {
+    if !confirm(question) {
+        eprintln!("Отменено");
+        std::process::exit(1);
+    }
+}
+
+/// Клавиша в списке с флажками.
+#[derive(Debug, PartialEq, Eq)]
+enum Key {
+    Up,
+    Down,
+    Toggle,
+    ToggleAll,
+    Done,
+    Cancel,
+    Other,
+}
+
+/// Пауза после Esc: за ней может прийти остаток последовательности стрелки.
+const ESCAPE_TIMEOUT_MS: libc::c_int = 50;
+
+/// Список с флажками в терминале: стрелки или j/k — выбор строки, пробел — отметить,
+/// `a` — отметить все (или снять, если отмечены все), Enter — готово, q или Esc — отмена
+/// (`Ok(false)`). Отметки меняются в `checked` на месте.
+pub fn checklist(title: &str, items: &[String], checked: &mut [bool]) -> io::Result<bool> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        fail("Для выбора нужен терминал");
+    }
+    let raw = RawMode::enable()?;
+    let mut stderr = io::stderr().lock();
+    let (rows, columns) = terminal_size(stderr.as_raw_fd());
+    // Заголовок и подсказка занимают две строки, остальное — окно списка.
+    let visible = items.len().min(rows.saturating_sub(3).max(3));
+    let (mut cursor, mut offset) = (0, 0);
+    write!(stderr, "{}\n\x1b[?25l", title.bold())?;
+    let mut drawn = false;
+    let accepted = loop {
+        if cursor < offset {
+            offset = cursor;
+        } else if cursor >= offset + visible {
+            offset = cursor + 1 - visible;
+        }
+        if drawn {
+            write!(stderr, "\x1b[{}A", visible + 1)?;
+        }
+        for index in offset..offset + visible {
+            let mark = if checked[index] { "[x]" } else { "[ ]" };
+            let line = truncate(
+                &format!("{mark} {}", items[index]),
+                columns.saturating_sub(3),
+            );
+            if index == cursor {
+                writeln!(stderr, "\r\x1b[2K{} {}", ">".cyan().bold(), line.bold())?;
+            } else {
+                writeln!(stderr, "\r\x1b[2K  {line}")?;
+            }
+        }
+        let hint = "↑/↓ — выбор, пробел — отметить, a — все, Enter — применить, q — отмена";
+        writeln!(stderr, "\r\x1b[2K{}", truncate(hint, columns).dimmed())?;
+        stderr.flush()?;
+        drawn = true;
+
+        match raw.read_key()? {
+            Key::Up => cursor = cursor.checked_sub(1).unwrap_or(items.len() - 1),
+            Key::Down => cursor = (cursor + 1) % items.len(),
+            Key::Toggle => checked[cursor] = !checked[cursor],
+            Key::ToggleAll => {
+                let all = checked.iter().all(|checked| *checked);
+                checked.fill(!all);
+            }
+            Key::Done => break true,
+            Key::Cancel => break false,
+            Key::Other => {}
+        }
+    };
+    write!(stderr, "\x1b[?25h")?;
+    Ok(accepted)
+}
+
+/// Строка не длиннее `width` символов: перенос сбил бы перерисовку списка.
+fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(width.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+/// Строки и столбцы терминала; если размер неизвестен — 24×80.
+fn terminal_size(fd: RawFd) -> (usize, usize) {
+    // SAFETY: winsize — простая структура из чисел, нули для неё допустимы; ioctl пишет
+    // в неё не больше её размера.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &raw mut size) } != 0 || size.ws_row == 0 {
+        return (24, 80);
+    }
+    (usize::from(size.ws_row), usize::from(size.ws_col))
+}
+
+/// Посимвольный ввод без эха на время выбора; прежний режим терминала возвращается при
+/// выходе из области видимости, в том числе при ошибке.
+struct RawMode {
+    fd: RawFd,
+    saved: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let fd = io::stdin().as_raw_fd();
+        // SAFETY: termios — структура из чисел, нули допустимы; tcgetattr её заполняет.
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &raw mut saved) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = saved;
+        // Без ISIG Ctrl+C приходит байтом и отменяет выбор, не оставляя терминал без эха.
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: raw живёт до конца вызова.
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw const raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd, saved })
+    }
+
+    fn read_key(&self) -> io::Result<Key> {
+        let Some(byte) = self.read_byte(-1)? else {
+            return Ok(Key::Cancel);
+        };
+        let key = match byte {
+            b'k' => Key::Up,
+            b'j' => Key::Down,
+            b' ' | b'x' => Key::Toggle,
+            b'a' => Key::ToggleAll,
+            b'\r' | b'\n' => Key::Done,
+            // Ctrl+C, Ctrl+D.
+            b'q' | 3 | 4 => Key::Cancel,
+            0x1b => match self.read_byte(ESCAPE_TIMEOUT_MS)? {
+                None => Key::Cancel,
+                Some(b'[' | b'O') => match self.read_byte(ESCAPE_TIMEOUT_MS)? {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    _ => Key::Other,
+                },
+                Some(_) => Key::Other,
+            },
+            _ => Key::Other,
+        };
+        Ok(key)
+    }
+
+    /// Байт из терминала напрямую, мимо буфера stdin: иначе `poll` не увидит уже
+    /// прочитанный остаток последовательности. `None` — таймаут или конец ввода.
+    fn read_byte(&self, timeout_ms: libc::c_int) -> io::Result<Option<u8>> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            // SAFETY: poll_fd живёт до конца вызова, передаётся ровно один элемент.
+            match unsafe { libc::poll(&raw mut poll_fd, 1, timeout_ms) } {
+                0 => return Ok(None),
+                -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {}
+                -1 => return Err(io::Error::last_os_error()),
+                _ => break,
+            }
+        }
+        let mut byte = 0u8;
+        // SAFETY: буфер — один доступный для записи байт.
+        match unsafe { libc::read(self.fd, (&raw mut byte).cast(), 1) } {
+            1 => Ok(Some(byte)),
+            0 => Ok(None),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: saved получен из tcgetattr и живёт до конца вызова.
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &raw const self.saved);
+        }
+    }
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "д" | "да"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_yes;
+
+    #[test]
+    fn accepts_only_explicit_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" Yes "));
+        assert!(is_yes("да\n"));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("n"));
+        assert!(!is_yes("yep"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    context::{STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV},
+    env_store::set_env_var,
+    proton::find_proton,
+    runtime::home_dir,
+    steam::{STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV, STEAM_GAME_ID_ENV, steam_roots},
+};
+
+/// Идентификатор игры umu (`umu-<id>` из базы umu или произвольная строка).
+const GAMEID_ENV: &str = "GAMEID";
+/// Каталог сборки Proton или её имя (`GE-Proton`, `UMU-Proton`).
+const PROTONPATH_ENV: &str = "PROTONPATH";
+/// Префикс umu; он же служит compatdata для Proton (umu кладёт в него ссылку `pfx -> .`).
+const WINEPREFIX_ENV: &str = "WINEPREFIX";
+/// Префикс umu-run по умолчанию, если `WINEPREFIX` не задан.
+const DEFAULT_UMU_PREFIX: &str = "Games/umu/umu-default";
+const UMU_LAUNCHERS: &[&str] = &["umu-run", "ulwgl-run"];
+
+/// Запуск через umu-launcher/ULWGL: Steam не выставляет свои переменные, их нужно вывести
+/// из окружения umu.
+pub struct UmuLaunch {
+    pub game_id: String,
+    pub compat_data: PathBuf,
+    pub proton: Option<PathBuf>,
+}
+
+impl UmuLaunch {
+    /// Распознаёт umu по `GAMEID` или по имени запускаемой программы (`umu-run ...`).
+    pub fn detect(cmd: &[String]) -> Option<Self> {
+        let launcher = cmd
+            .first()
+            .and_then(|program| Path::new(program).file_name());
+        let is_umu = launcher.is_some_and(|name| UMU_LAUNCHERS.iter().any(|umu| name == *umu));
+        let game_id = env::var(GAMEID_ENV).ok().filter(|id| !id.is_empty());
+        if !is_umu && game_id.is_none() {
+            return None;
+        }
+
+        let compat_data = env::var_os(WINEPREFIX_ENV)
+            .filter(|prefix| !prefix.is_empty())
+            .map_or_else(|| home_dir().join(DEFAULT_UMU_PREFIX), PathBuf::from);
+        let proton = env::var(PROTONPATH_ENV)
+            .ok()
+            .and_then(|value| find_proton(Some(&value)).map(|build| build.script()));
+        Some(Self {
+            game_id: game_id.unwrap_or_else(|| "0".to_string()),
+            compat_data,
+            proton,
+        })
+    }
+
+    /// Выставляет переменные, которые Proton ждёт от Steam, чтобы они попали в окружение
+    /// контекста и `run` работал так же, как после запуска из Steam.
+    pub fn export_steam_env(&self) {
+        set_env_var(
+            STEAM_COMPAT_DATA_PATH_ENV,
+            &self.compat_data.to_string_lossy(),
+        );
+        set_env_var(STEAM_APP_ID_ENV, self.steam_app_id());
+        set_env_var(STEAM_GAME_ID_ENV, self.steam_app_id());
+        if env::var_os(STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV).is_none()
+            && let Some(steam_root) = steam_roots().first()
+        {
+            set_env_var(
+                STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV,
+                &steam_root.to_string_lossy(),
+            );
+        }
+    }
+
+    /// `SteamAppId` для Proton: числовая часть `umu-<appid>`, иначе `0`.
+    pub fn steam_app_id(&self) -> &str {
+        let id = self.game_id.strip_prefix("umu-").unwrap_or(&self.game_id);
+        if !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_digit()) {
+            id
+        } else {
+            "0"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UmuLaunch;
+    use std::path::PathBuf;
+
+    fn launch(game_id: &str) -> UmuLaunch {
+        UmuLaunch {
+            game_id: game_id.to_string(),
+            compat_data: PathBuf::new(),
+            proton: None,
+        }
+    }
+
+    #[test]
+    fn derives_steam_app_id_from_game_id() {
+        assert_eq!(launch("umu-1091500").steam_app_id(), "1091500");
+        assert_eq!(launch("umu-default").steam_app_id(), "0");
+        assert_eq!(launch("292030").steam_app_id(), "292030");
+    }
+}
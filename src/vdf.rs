@@ -0,0 +1,426 @@
+use std::{iter::Peekable, ops::Range, str::CharIndices};
+
+/// Значение текстового VDF (`KeyValues`) Valve: `appmanifest_*.acf`, `libraryfolders.vdf`,
+/// `localconfig.vdf`. Порядок ключей сохраняется, повторы допустимы, как и в самом формате.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Vdf {
+    String(String),
+    Block(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    /// Значение по ключу в блоке; ключи VDF регистронезависимы.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.entries()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            Self::Block(_) => None,
+        }
+    }
+
+    /// Пары блока; у строки их нет.
+    pub fn entries(&self) -> &[(String, Self)] {
+        match self {
+            Self::String(_) => &[],
+            Self::Block(entries) => entries,
+        }
+    }
+}
+
+/// Записывает строку по пути ключей (последний — сам ключ) прямо в текст документа. Меняется
+/// только значение: комментарии, условия `[$…]`, отступы и escape-последовательности
+/// остального файла остаются байт в байт. Недостающие ключи дописываются в конец ближайшего
+/// блока пути в отступах Steam. `None` — документ не разобран или на пути стоит строка.
+pub fn set_in_text(content: &str, path: &[&str], value: &str) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+    let mut tokens = Tokenizer::new(content);
+    // Сколько ключей пути совпало с открытыми блоками и глубина чужого блока внутри них.
+    let mut matched = 0;
+    let mut skipped = 0usize;
+    let (insert_at, newline_first) = loop {
+        let key = match tokens.next_token() {
+            None if matched == 0 && skipped == 0 => {
+                break (
+                    content.len(),
+                    !content.is_empty() && !content.ends_with('\n'),
+                );
+            }
+            Some((Token::Close, _)) if skipped > 0 => {
+                skipped -= 1;
+                continue;
+            }
+            // Блок пути закрылся, а нужного ключа в нём не было.
+            Some((Token::Close, span)) if matched > 0 => {
+                break insertion_point(content, span.start);
+            }
+            Some((Token::Text(key), _)) => key,
+            _ => return None,
+        };
+        let on_path = skipped == 0 && key.eq_ignore_ascii_case(path[matched]);
+        match tokens.next_token()? {
+            (Token::Open, _) if on_path && matched + 1 < path.len() => matched += 1,
+            (Token::Open, _) if !on_path => skipped += 1,
+            (Token::Text(_), span) if on_path && matched + 1 == path.len() => {
+                return Some(format!(
+                    "{}{}{}",
+                    &content[..span.start],
+                    quoted(value),
+                    &content[span.end..]
+                ));
+            }
+            (Token::Text(_), _) if !on_path => {}
+            _ => return None,
+        }
+    };
+
+    let mut missing = Vdf::String(value.to_string());
+    for key in path[matched + 1..].iter().rev() {
+        missing = Vdf::Block(vec![((*key).to_string(), missing)]);
+    }
+    let mut text = String::new();
+    if newline_first {
+        text.push('\n');
+    }
+    write_block(&mut text, &[(path[matched].to_string(), missing)], matched);
+    Some(format!(
+        "{}{text}{}",
+        &content[..insert_at],
+        &content[insert_at..]
+    ))
+}
+
+/// Куда дописать ключи перед `}` в `pos`: в начало её строки, если перед скобкой только
+/// отступ, иначе прямо перед ней с новой строки.
+fn insertion_point(content: &str, pos: usize) -> (usize, bool) {
+    let line_start = content[..pos].rfind('\n').map_or(0, |index| index + 1);
+    if content[line_start..pos].trim().is_empty() {
+        (line_start, false)
+    } else {
+        (pos, true)
+    }
+}
+
+fn write_block(out: &mut String, entries: &[(String, Vdf)], depth: usize) {
+    let indent = "\t".repeat(depth);
+    for (key, value) in entries {
+        out.push_str(&indent);
+        write_quoted(out, key);
+        match value {
+            Vdf::String(text) => {
+                out.push_str("\t\t");
+                write_quoted(out, text);
+                out.push('\n');
+            }
+            Vdf::Block(children) => {
+                out.push('\n');
+                out.push_str(&indent);
+                out.push_str("{\n");
+                write_block(out, children, depth + 1);
+                out.push_str(&indent);
+                out.push_str("}\n");
+            }
+        }
+    }
+}
+
+/// Строка в кавычках, как её пишет Steam и `set_in_text`.
+pub fn quoted(text: &str) -> String {
+    let mut out = String::new();
+    write_quoted(&mut out, text);
+    out
+}
+
+fn write_quoted(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Разбирает документ в блок верхнего уровня. `None` — незакрытые кавычки или скобки.
+pub fn parse(content: &str) -> Option<Vdf> {
+    let mut tokens = Tokenizer::new(content);
+    let entries = parse_block(&mut tokens, false)?;
+    Some(Vdf::Block(entries))
+}
+
+enum Token {
+    Open,
+    Close,
+    Text(String),
+    /// Строка оборвалась посреди кавычек.
+    Unterminated,
+}
+
+struct Tokenizer<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            chars: content.char_indices().peekable(),
+            len: content.len(),
+        }
+    }
+
+    /// Следующий токен и его байтовый диапазон в тексте.
+    fn next_token(&mut self) -> Option<(Token, Range<usize>)> {
+        loop {
+            let (start, c) = self.chars.next()?;
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                '/' if self.chars.peek().is_some_and(|(_, next)| *next == '/') => {
+                    self.chars.by_ref().find(|(_, c)| *c == '\n');
+                    continue;
+                }
+                '{' => Token::Open,
+                '}' => Token::Close,
+                '"' => self.quoted().map_or(Token::Unterminated, Token::Text),
+                c => {
+                    let word = self.bare_word(c);
+                    // Условия платформы (`[$WIN32]`) пропускаем: значения берём все.
+                    if word.starts_with('[') && word.ends_with(']') {
+                        continue;
+                    }
+                    Token::Text(word)
+                }
+            };
+            return Some((token, start..self.offset()));
+        }
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.len, |(index, _)| *index)
+    }
+
+    fn quoted(&mut self) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next()?.1 {
+                '"' => return Some(text),
+                '\\' => match self.chars.next()?.1 {
+                    'n' => text.push('\n'),
+                    't' => text.push('\t'),
+                    c @ ('\\' | '"') => text.push(c),
+                    // Неизвестную последовательность оставляем как есть, с `\`.
+                    c => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn bare_word(&mut self, first: char) -> String {
+        let mut word = first.to_string();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() || matches!(c, '{' | '}' | '"') {
+                break;
+            }
+            word.push(c);
+            self.chars.next();
+        }
+        word
+    }
+}
+
+fn parse_block(tokens: &mut Tokenizer, nested: bool) -> Option<Vec<(String, Vdf)>> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.next_token().map(|(token, _)| token) {
+            None => return (!nested).then_some(entries),
+            Some(Token::Close) if nested => return Some(entries),
+            Some(Token::Text(key)) => key,
+            Some(_) => return None,
+        };
+        let value = match tokens.next_token()?.0 {
+            Token::Text(value) => Vdf::String(value),
+            Token::Open => Vdf::Block(parse_block(tokens, true)?),
+            _ => return None,
+        };
+        entries.push((key, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Vdf, parse, quoted, set_in_text};
+
+    #[test]
+    fn parses_nested_blocks_and_escapes() {
+        let content = r#"
+            // comment
+            "AppState"
+            {
+                "appid"		"620"
+                "name"		"Portal 2 \"Deluxe\""
+                "InstalledDepots"
+                {
+                    "621" { "manifest" "123" "size" "456" }
+                }
+                "path"  "D:\\Games" [$WIN32]
+            }
+        "#;
+        let vdf = parse(content).unwrap();
+        let state = vdf.get("appstate").unwrap();
+        assert_eq!(
+            state.get("name").and_then(Vdf::as_str),
+            Some(r#"Portal 2 "Deluxe""#)
+        );
+        assert_eq!(
+            state
+                .get("InstalledDepots")
+                .and_then(|depots| depots.get("621")?.get("size"))
+                .and_then(Vdf::as_str),
+            Some("456")
+        );
+        assert_eq!(state.get("path").and_then(Vdf::as_str), Some(r"D:\Games"));
+    }
+
+    /// Фрагмент настоящего `localconfig.vdf` с тем, что парсер не сохраняет: комментарием,
+    /// условием платформы и неизвестной escape-последовательностью.
+    const LOCAL_CONFIG: &str = "\"UserLocalConfigStore\"
+{
+	// edited by hand
+	\"Software\"
+	{
+		\"Valve\"
+		{
+			\"Steam\"
+			{
+				\"apps\"
+				{
+					\"620\"
+					{
+						\"LastPlayed\"		\"1700000000\"
+						\"LaunchOptions\"		\"PROTON_LOG=1 %command%\"
+						\"cloud\"
+						{
+							\"last_sync_state\"		\"synchronized\"
+						}
+					}
+					\"730\"
+					{
+						\"LastPlayed\"		\"1700000001\"
+					}
+				}
+				\"ShaderCachePath\"		\"C:\\x\\\\y\"	[$WIN32]
+			}
+		}
+	}
+	\"friends\" { \"620\" { \"LaunchOptions\" \"untouched\" } }
+}
+";
+    const APPS: [&str; 5] = ["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"];
+
+    fn launch_options_path(appid: &str) -> Vec<&str> {
+        APPS.iter()
+            .copied()
+            .chain([appid, "LaunchOptions"])
+            .collect()
+    }
+
+    #[test]
+    fn replaces_only_the_value_in_place() {
+        let updated = set_in_text(
+            LOCAL_CONFIG,
+            &launch_options_path("620"),
+            r#"A="b c" protonhax init %command%"#,
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            LOCAL_CONFIG.replace(
+                "\"PROTON_LOG=1 %command%\"",
+                r#""A=\"b c\" protonhax init %command%""#
+            )
+        );
+        let vdf = parse(&updated).unwrap();
+        let steam = APPS
+            .iter()
+            .try_fold(&vdf, |block, key| block.get(key))
+            .unwrap();
+        assert_eq!(
+            steam
+                .get("620")
+                .and_then(|app| app.get("LaunchOptions")?.as_str()),
+            Some(r#"A="b c" protonhax init %command%"#)
+        );
+    }
+
+    #[test]
+    fn appends_missing_keys_with_steam_indentation() {
+        let updated = set_in_text(LOCAL_CONFIG, &launch_options_path("730"), "-novid").unwrap();
+        assert_eq!(
+            updated,
+            LOCAL_CONFIG.replace(
+                "\"1700000001\"\n",
+                "\"1700000001\"\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"-novid\"\n"
+            )
+        );
+
+        let updated = set_in_text(LOCAL_CONFIG, &launch_options_path("1245620"), "-x").unwrap();
+        let inserted = "\t\t\t\t\t\"1245620\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"-x\"\n\t\t\t\t\t}\n";
+        assert_eq!(
+            updated,
+            LOCAL_CONFIG.replace(
+                "\t\t\t\t}\n\t\t\t\t\"ShaderCachePath\"",
+                &format!("{inserted}\t\t\t\t}}\n\t\t\t\t\"ShaderCachePath\"")
+            )
+        );
+
+        assert_eq!(
+            set_in_text("\"a\" { \"b\" \"c\" }", &["a", "d"], "e").unwrap(),
+            "\"a\" { \"b\" \"c\" \n\t\"d\"\t\t\"e\"\n}"
+        );
+    }
+
+    #[test]
+    fn refuses_strings_on_the_path() {
+        let mut path = launch_options_path("620");
+        path[6] = "LastPlayed";
+        path.push("x");
+        assert_eq!(set_in_text(LOCAL_CONFIG, &path, "y"), None);
+        assert_eq!(set_in_text("\"a\" {", &["a", "b"], "c"), None);
+    }
+
+    #[test]
+    fn keeps_unknown_escapes() {
+        let vdf = parse(LOCAL_CONFIG).unwrap();
+        let steam = APPS[..4]
+            .iter()
+            .try_fold(&vdf, |block, key| block.get(key))
+            .unwrap();
+        assert_eq!(
+            steam.get("ShaderCachePath").and_then(Vdf::as_str),
+            Some(r"C:\x\y")
+        );
+        assert_eq!(quoted(r"C:\x\y"), r#""C:\\x\\y""#);
+    }
+
+    #[test]
+    fn rejects_unbalanced_documents() {
+        assert_eq!(parse(r#""a" { "b" "c""#), None);
+        assert_eq!(parse(r#""a" "unterminated"#), None);
+        assert_eq!(parse(r#""a" "b" }"#), None);
+    }
+}
@@ -0,0 +1,75 @@
+use colored::Colorize;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Уровень диагностики на stderr: задаётся один раз в `main` из `-q`/`-v`/`-vv`
+/// и `PROTONHAX_DEBUG`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// `-q`: только ошибки и предупреждения.
+    Quiet,
+    Normal,
+    /// `-v`: как найден контекст, итоговый argv, применённое окружение.
+    Verbose,
+    /// `-vv` или `PROTONHAX_DEBUG=1`: подробности вплоть до отдельных переменных.
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+impl Verbosity {
+    pub fn from_flags(quiet: bool, verbose: u8, debug_env: bool) -> Self {
+        if debug_env || verbose >= 2 {
+            Self::Debug
+        } else if verbose == 1 {
+            Self::Verbose
+        } else if quiet {
+            Self::Quiet
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub fn set(self) {
+        LEVEL.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+pub fn enabled(level: Verbosity) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Пояснения для человека; `-q` их скрывает.
+pub fn info(message: impl Display) {
+    if enabled(Verbosity::Normal) {
+        eprintln!("{} {message}", "INFO".cyan().bold());
+    }
+}
+
+pub fn verbose(message: impl Display) {
+    if enabled(Verbosity::Verbose) {
+        eprintln!("{} {message}", "VERBOSE".blue().bold());
+    }
+}
+
+pub fn debug(message: impl Display) {
+    if enabled(Verbosity::Debug) {
+        eprintln!("{} {message}", "DEBUG".bold().cyan());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Verbosity;
+
+    #[test]
+    fn combines_flags() {
+        assert_eq!(Verbosity::from_flags(true, 0, false), Verbosity::Quiet);
+        assert_eq!(Verbosity::from_flags(false, 0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(false, 1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(false, 3, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(true, 0, true), Verbosity::Debug);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    cli::fail,
+    context::{STEAM_APP_ID_ENV, STEAM_COMPAT_DATA_PATH_ENV, has_context, resolve_target_app},
+    env_store::{load_env, set_env_var},
+    steam::{
+        STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV, find_compat_data, proton_dir_from_config_info,
+        steam_roots,
+    },
+    verbosity,
+};
+
+pub const WINEPREFIX_ENV: &str = "WINEPREFIX";
+/// Команды proton для перевода путей (Linux → Windows и обратно).
+const GETCOMPATPATH: &str = "getcompatpath";
+const GETNATIVEPATH: &str = "getnativepath";
+
+/// Префикс Wine вместе со сборкой Proton, которой он принадлежит.
+pub struct PrefixContext {
+    pub appid: String,
+    pub proton: PathBuf,
+    pub pfx: PathBuf,
+    /// Бинарник Wine контекста лаунчера без Proton (Heroic, Lutris).
+    pub wine: Option<PathBuf>,
+    /// Каталог активного контекста; `None`, если префикс взят из compatdata Steam.
+    pub app_dir: Option<PathBuf>,
+}
+
+impl PrefixContext {
+    pub fn is_live(&self) -> bool {
+        self.app_dir.is_some()
+    }
+
+    pub fn compat_data(&self) -> &Path {
+        self.pfx.parent().unwrap_or(&self.pfx)
+    }
+
+    pub fn proton_dir(&self) -> &Path {
+        self.proton.parent().unwrap_or_else(|| Path::new("/"))
+    }
+
+    /// Каталог с `wine`/`wineserver` внутри сборки Proton (или рядом с Wine лаунчера).
+    pub fn wine_bin_dir(&self) -> Option<PathBuf> {
+        if let Some(wine) = &self.wine {
+            return wine.parent().map(Path::to_path_buf);
+        }
+        ["files", "dist"]
+            .iter()
+            .map(|dist| self.proton_dir().join(dist).join("bin"))
+            .find(|dir| dir.join("wine").is_file())
+    }
+
+    /// Переводит путь вида `C:\dir\file.exe` в путь хоста: через proton, а у лаунчеров
+    /// без него — через `dosdevices` префикса.
+    pub fn windows_to_host(&self, windows_path: &str) -> Option<PathBuf> {
+        let proton = self.wine.is_none().then_some(self.proton.as_path());
+        to_host_path(proton, &self.pfx, windows_path)
+    }
+
+    /// `wineboot <flag>` в префиксе: через `proton run` или, у лаунчеров без Proton, через
+    /// их Wine.
+    pub fn wineboot(&self, flag: &str) -> process::Command {
+        let mut wineboot = if let Some(wine) = &self.wine {
+            let mut wineboot = process::Command::new(wine);
+            self.apply_wine_env(&mut wineboot);
+            wineboot
+        } else {
+            let mut wineboot = process::Command::new(&self.proton);
+            wineboot.arg("run");
+            wineboot
+        };
+        wineboot.args(["wineboot", flag]);
+        wineboot
+    }
+
+    /// Настраивает окружение так, чтобы `wine`/winetricks работали с этим префиксом.
+    pub fn apply_wine_env(&self, cmd: &mut process::Command) {
+        cmd.env(WINEPREFIX_ENV, &self.pfx);
+        let Some(bin_dir) = self.wine_bin_dir() else {
+            return;
+        };
+
+        let wine = bin_dir.join("wine");
+        cmd.env("WINE", &wine)
+            .env("WINELOADER", &wine)
+            .env("WINESERVER", bin_dir.join("wineserver"));
+
+        let mut paths = vec![bin_dir];
+        if let Some(path) = env::var_os("PATH") {
+            paths.extend(env::split_paths(&path));
+        }
+        if let Ok(path) = env::join_paths(paths) {
+            cmd.env("PATH", path);
+        }
+    }
+}
+
+/// Находит префикс по селектору: сначала среди активных контекстов, а для
+/// числового appid без контекста — в compatdata библиотек Steam.
+pub fn locate_prefix(phd: &Path, selector: &str) -> io::Result<PrefixContext> {
+    let is_appid = !selector.is_empty() && selector.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_appid || has_context(phd, selector) {
+        let target = resolve_target_app(phd, selector)?;
+        target.require_proton();
+        return Ok(PrefixContext {
+            proton: PathBuf::from(target.exe()?),
+            pfx: PathBuf::from(target.pfx()?),
+            wine: target.wine().map(PathBuf::from),
+            appid: target.appid,
+            app_dir: Some(target.app_dir),
+        });
+    }
+
+    let Some(compat_data) = find_compat_data(selector) else {
+        fail(&format!(
+            "Нет активного контекста и compatdata для appid {selector} (игра ни разу не запускалась через Proton?)"
+        ));
+    };
+    let config_info = fs::read_to_string(compat_data.join("config_info")).unwrap_or_default();
+    let Some(proton_dir) = proton_dir_from_config_info(&config_info) else {
+        fail(&format!(
+            "Не удалось определить сборку Proton по {}",
+            compat_data.join("config_info").display()
+        ));
+    };
+
+    Ok(PrefixContext {
+        appid: selector.to_string(),
+        proton: proton_dir.join("proton"),
+        pfx: compat_data.join("pfx"),
+        wine: None,
+        app_dir: None,
+    })
+}
+
+/// Как [`locate_prefix`], но дополнительно применяет окружение игры к текущему процессу:
+/// сохранённое окружение контекста или минимальный набор переменных Steam.
+pub fn resolve_prefix(phd: &Path, selector: &str) -> io::Result<PrefixContext> {
+    let prefix = locate_prefix(phd, selector)?;
+    set_env_var(STEAM_APP_ID_ENV, &prefix.appid);
+
+    if let Some(app_dir) = &prefix.app_dir {
+        load_env(app_dir)?;
+        return Ok(prefix);
+    }
+
+    set_env_var(
+        STEAM_COMPAT_DATA_PATH_ENV,
+        &prefix.compat_data().to_string_lossy(),
+    );
+    if let Some(root) = steam_roots().first() {
+        set_env_var(
+            STEAM_COMPAT_CLIENT_INSTALL_PATH_ENV,
+            &root.to_string_lossy(),
+        );
+    }
+    Ok(prefix)
+}
+
+/// Windows-путь для пути хоста глазами самого proton (`getcompatpath`), а если сборка
+/// этой команды не знает — по `dosdevices` префикса.
+pub fn to_windows_path(proton: Option<&Path>, pfx: &Path, host_path: &Path) -> String {
+    proton
+        .and_then(|proton| proton_translate(proton, GETCOMPATPATH, host_path.as_os_str()))
+        .unwrap_or_else(|| host_to_windows(pfx, host_path))
+}
+
+/// Путь хоста для `C:\...` через `getnativepath` proton или `dosdevices` префикса.
+pub fn to_host_path(proton: Option<&Path>, pfx: &Path, windows_path: &str) -> Option<PathBuf> {
+    proton
+        .and_then(|proton| proton_translate(proton, GETNATIVEPATH, windows_path.as_ref()))
+        .map(PathBuf::from)
+        .or_else(|| windows_to_host(pfx, windows_path))
+}
+
+/// Перевод пути командой proton: она вызывает `winepath` в префиксе из окружения
+/// контекста, так что учитываются все диски и настройки, которые видит Wine.
+fn proton_translate(proton: &Path, verb: &str, path: &OsStr) -> Option<String> {
+    let output = process::Command::new(proton)
+        .arg(verb)
+        .arg(path)
+        .stdin(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .output();
+    let translated = match output {
+        Ok(output) if output.status.success() => String::from_utf8(output.stdout).ok(),
+        _ => None,
+    }
+    .map(|stdout| stdout.trim_end_matches(['\n', '\r', '\0']).to_string())
+    .filter(|translated| !translated.is_empty());
+    if translated.is_none() {
+        verbosity::debug(format_args!(
+            "proton {verb} не сработал, путь переводим по dosdevices"
+        ));
+    }
+    translated
+}
+
+/// Переводит путь вида `C:\dir\file.exe` в путь хоста через `dosdevices` префикса `pfx`.
+pub fn windows_to_host(pfx: &Path, windows_path: &str) -> Option<PathBuf> {
+    let (drive, components) = split_windows_path(windows_path)?;
+    let drive_link = pfx.join("dosdevices").join(format!("{drive}:"));
+    let root = fs::canonicalize(drive_link).ok()?;
+    Some(resolve_case_insensitive(&root, &components))
+}
+
+/// Абсолютный Windows-путь: буква диска, двоеточие и разделитель (`C:\`, `D:/`).
+pub fn is_windows_path(arg: &str) -> bool {
+    let bytes = arg.as_bytes();
+    bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/')
+}
+
+/// Переводит путь хоста в Windows-путь префикса: ищется диск из `dosdevices`
+/// с самым длинным совпадающим корнем, иначе используется `Z:` (корень хоста).
+pub fn host_to_windows(pfx: &Path, host_path: &Path) -> String {
+    let host_path = fs::canonicalize(host_path).unwrap_or_else(|_| host_path.to_path_buf());
+    let drives = fs::read_dir(pfx.join("dosdevices"))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let (letter, rest) = name.split_at_checked(1)?;
+            if rest != ":" || !letter.chars().all(|c| c.is_ascii_alphabetic()) {
+                return None;
+            }
+            Some((
+                letter.to_ascii_uppercase(),
+                fs::canonicalize(entry.path()).ok()?,
+            ))
+        });
+
+    let (drive, relative) = drives
+        .filter_map(|(drive, root)| {
+            let relative = host_path.strip_prefix(&root).ok()?.to_path_buf();
+            Some((root.components().count(), drive, relative))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map_or_else(
+            || ("Z".to_string(), host_path.clone()),
+            |(_, drive, relative)| (drive, relative),
+        );
+
+    join_windows_path(&drive, &relative)
+}
+
+fn join_windows_path(drive: &str, relative: &Path) -> String {
+    let components: Vec<_> = relative
+        .iter()
+        .filter(|component| *component != "/")
+        .map(|component| component.to_string_lossy())
+        .collect();
+    format!("{drive}:\\{}", components.join("\\"))
+}
+
+/// Разбирает `C:\a\b` на букву диска (в нижнем регистре) и компоненты пути.
+pub fn split_windows_path(path: &str) -> Option<(char, Vec<&str>)> {
+    let mut chars = path.chars();
+    let drive = chars.next()?.to_ascii_lowercase();
+    if !drive.is_ascii_alphabetic() || chars.next()? != ':' {
+        return None;
+    }
+
+    let components = path[2..]
+        .split(['\\', '/'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    Some((drive, components))
+}
+
+/// Windows-пути нечувствительны к регистру: подбираем существующие компоненты без учёта регистра.
+fn resolve_case_insensitive(root: &Path, components: &[&str]) -> PathBuf {
+    let mut path = root.to_path_buf();
+    for component in components {
+        let exact = path.join(component);
+        if exact.exists() {
+            path = exact;
+            continue;
+        }
+
+        let matched = fs::read_dir(&path).ok().and_then(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .find(|entry| entry.file_name().eq_ignore_ascii_case(component))
+        });
+        path = matched.map_or(exact, |entry| entry.path());
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_windows_path, join_windows_path, split_windows_path};
+    use std::path::Path;
+
+    #[test]
+    fn splits_windows_paths() {
+        assert_eq!(
+            split_windows_path(r"C:\Program Files\Tool\tool.exe"),
+            Some(('c', vec!["Program Files", "Tool", "tool.exe"]))
+        );
+        assert_eq!(
+            split_windows_path("Z:/home/user"),
+            Some(('z', vec!["home", "user"]))
+        );
+        assert_eq!(split_windows_path("/home/user"), None);
+    }
+
+    #[test]
+    fn detects_windows_paths() {
+        assert!(is_windows_path(r"C:\Tools\tool.exe"));
+        assert!(is_windows_path("d:/games"));
+        assert!(!is_windows_path("C:"));
+        assert!(!is_windows_path("/home/user"));
+        assert!(!is_windows_path("tool.exe"));
+    }
+
+    #[test]
+    fn joins_windows_paths() {
+        assert_eq!(
+            join_windows_path("Z", Path::new("/home/user/fix.reg")),
+            r"Z:\home\user\fix.reg"
+        );
+        assert_eq!(
+            join_windows_path("C", Path::new("users/steamuser")),
+            r"C:\users\steamuser"
+        );
+    }
+}